@@ -1,19 +1,97 @@
+use crossterm::event::KeyCode;
 use ordered_float::*;
+use serde::{Deserialize, Serialize};
 use std::{
+    collections::HashMap,
     error::Error,
-    fs,
-    path::Path,
+    path::PathBuf,
     time::{Duration, Instant},
 };
 use tui::{
     style::{Color, Modifier, Style},
-    widgets::TableState,
+    widgets::{ListState, TableState},
 };
-use super::cube::gen_scramble;
+use super::clipboard;
+use super::command;
+use super::config::{expand, AutoExport, Config, InspectionWarnings, SessionConfig};
+use super::export;
+use super::import;
+use super::metronome::Metronome;
+use super::attempt::{self, Attempt, Format, Record};
+use super::compare::{Comparison, Side};
+use super::custom::CustomStat;
+use super::drill::{self, Drill};
+use super::format::TimeFormat;
+use super::hardware::smartcube::{self, Bridge, Stage};
+use super::hardware::stackmat::{self, Stackmat};
+use super::kinch::Kinch;
+use super::records::Records;
+use super::scramble::{self, Event, Job, Net, Settings, Subset, Worker};
+use super::session::{SessionManager, SessionMeta};
+use super::sound;
+use super::storage::{Cipher, Spread, Summary};
+
+/// Earlier scrambles kept so a knocked-over cube can be scrambled again.
+const SCRAMBLE_HISTORY: usize = 50;
+/// Solves the chart plots when not told how many.
+const CHART_SOLVES: usize = 100;
 
 pub enum Screen {
     Default,
     Help,
+    Restore,
+    /// The sessions to switch between
+    Sessions,
+    /// The session's notes, being written
+    Notes,
+}
+
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+pub enum InputMode {
+    Command,
+    /// A scramble to use for the next solve
+    Scramble,
+    /// A solve's time, typed rather than timed
+    Time,
+    /// The cubes solved in the multi-blind attempt just timed
+    Multiblind,
+    /// The solution found in a fewest-moves attempt
+    Solution,
+    /// The name of a session to start
+    Session,
+    /// A new name for the session selected in the list
+    Rename,
+    /// The tags of the session selected in the list
+    Tags,
+    /// The tag to list only the sessions with
+    TagFilter,
+}
+
+/// A drawing of the scrambled state in the Scramble block.
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+pub enum CubeView {
+    /// All six faces unfolded
+    Net,
+    /// The U, F and R faces in 3D
+    Isometric,
+    Hidden,
+}
+
+impl CubeView {
+    /// The view after this one, cycling back round to the net.
+    pub fn next(self) -> Self {
+        match self {
+            CubeView::Net => CubeView::Isometric,
+            CubeView::Isometric => CubeView::Hidden,
+            CubeView::Hidden => CubeView::Net,
+        }
+    }
+}
+
+/// A line of text being typed at the bottom of the screen.
+pub struct Input {
+    pub mode: InputMode,
+    pub text: String,
 }
 
 #[derive(PartialEq, Eq, Copy, Clone, Debug)]
@@ -52,40 +130,251 @@ impl Route {
     }
 }
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, PartialEq, Eq, Default, Debug, Serialize, Deserialize)]
+pub enum Penalty {
+    #[default]
+    None,
+    #[serde(rename = "+2")]
+    PlusTwo,
+    #[serde(rename = "DNF")]
+    Dnf,
+}
+
+impl Penalty {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Penalty::None => "",
+            Penalty::PlusTwo => "+2",
+            Penalty::Dnf => "DNF",
+        }
+    }
+}
+
+/// How a solve was done. Solves of each are averaged and ranked apart, so a session
+/// mixing them still has bests worth beating.
+#[derive(Clone, Copy, PartialEq, Eq, Default, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Variation {
+    #[default]
+    Normal,
+    #[serde(rename = "oh")]
+    OneHanded,
+    Feet,
+}
+
+impl Variation {
+    pub const ALL: [Variation; 3] = [Variation::Normal, Variation::OneHanded, Variation::Feet];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Variation::Normal => "normal",
+            Variation::OneHanded => "oh",
+            Variation::Feet => "feet",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Variation> {
+        Self::ALL.into_iter().find(|v| v.as_str() == name)
+    }
+
+    pub fn is_normal(&self) -> bool {
+        *self == Variation::Normal
+    }
+}
+
+impl std::fmt::Display for Variation {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Variation::OneHanded => f.write_str("OH"),
+            v => f.write_str(v.as_str()),
+        }
+    }
+}
+
+/// How many cubes of a multi-blind attempt were solved, which scores it before its time.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub struct Multiblind {
+    pub solved: u32,
+    pub attempted: u32,
+}
+
+impl Multiblind {
+    /// Cubes solved less those that weren't.
+    pub fn points(&self) -> i64 {
+        2 * self.solved as i64 - self.attempted as i64
+    }
+
+    /// Whether the WCA counts the attempt: it takes no negative points and at least two
+    /// solved cubes, so 1/2 is a DNF.
+    pub fn counts(&self) -> bool {
+        self.points() >= 0 && self.solved >= 2
+    }
+
+    /// The attempt as the WCA records it, `DDTTTTTMM`: 99 less the points, the time in
+    /// whole seconds and the cubes missed, so a lower code is a better result.
+    pub fn code(&self, secs: f32) -> u64 {
+        let points = (99 - self.points()).clamp(0, 99) as u64;
+        let secs = (secs.floor() as u64).min(99999);
+        let missed = (self.attempted - self.solved) as u64;
+        points * 10_000_000 + secs * 100 + missed
+    }
+}
+
+/// One solve, as it's saved.
+///
+/// Fields added since files were checksummed are left out while empty or false, so the
+/// solves of older files serialize as they did and keep their checksums.
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Time {
     pub time: f32,
+    /// The mean of this solve and the two before it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mo3: Option<OrderedFloat<f32>>,
     pub ao5: Option<OrderedFloat<f32>>,
     pub ao12: Option<OrderedFloat<f32>>,
+    /// The larger averages ending at this solve, once there are enough solves for them
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ao50: Option<OrderedFloat<f32>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ao100: Option<OrderedFloat<f32>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ao1000: Option<OrderedFloat<f32>>,
+    #[serde(default)]
+    pub scramble: String,
+    #[serde(default)]
+    pub penalty: Penalty,
+    /// Whether the penalty came from going over inspection rather than being given by hand
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub inspection_penalty: bool,
+    /// Unix timestamp of when the solve finished, if known
+    #[serde(default)]
+    pub date: Option<i64>,
+    /// How long each phase took, when the solve was split into them
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub phases: Vec<f32>,
+    /// How long memorizing took in a blindfolded solve, the rest being execution
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub memo: Option<f32>,
+    /// The turns made during the solve, when it was timed on a smart cube
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub solution: String,
+    /// The cubes solved in a multi-blind attempt
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub multiblind: Option<Multiblind>,
+    /// The length of a fewest-moves solution, which is the result in place of a time;
+    /// `time` holds it too so the stats are of moves
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub moves: Option<u32>,
+    /// One-handed, with feet or neither
+    #[serde(default, skip_serializing_if = "Variation::is_normal")]
+    pub variation: Variation,
 }
 
 impl Time {
     pub fn from(time: f32) -> Self {
         Self {
             time,
+            mo3: None,
             ao5: None,
             ao12: None,
+            ao50: None,
+            ao100: None,
+            ao1000: None,
+            scramble: String::new(),
+            penalty: Penalty::None,
+            inspection_penalty: false,
+            date: None,
+            phases: vec![],
+            memo: None,
+            solution: String::new(),
+            multiblind: None,
+            moves: None,
+            variation: Variation::Normal,
         }
     }
 
-    pub fn gen_stats(&mut self, times: &Vec<Time>) {
-        let mut tr = times.clone();
-        tr.push(*self);
-        tr.reverse();
+    /// The time that counts, with any +2 added. A DNF is infinitely slow, so it's the
+    /// worst of any average it's in, and more DNFs than are trimmed make a DNF average.
+    pub fn value(&self) -> f32 {
+        match self.penalty {
+            Penalty::None => self.time,
+            Penalty::PlusTwo => self.time + 2.0,
+            Penalty::Dnf => f32::INFINITY,
+        }
+    }
+}
 
-        self.ao5 = if tr.len() >= 5 {
-            let set = &tr[0..5];
-            Some(Times::calc_aon(set))
-        } else {
-            None
-        };
-        self.ao12 = if tr.len() >= 12 {
-            let set = &tr[0..12];
-            Some(Times::calc_aon(set))
-        } else {
-            None
-        };
+/// The most solves any average is taken over.
+pub const LONGEST: usize = 1000;
+
+/// How many solves the current speed mostly goes by: each solve counts for `2 / (SPAN + 1)`
+/// of it, so the latest 12 make up about 86%, as they would of an ao12.
+const SPEED_SPAN: f32 = 12.0;
+
+/// How many of `n` solves an average trims at each end: 5%, rounding up so an ao5 or ao12
+/// loses one.
+pub fn trim(n: usize) -> usize {
+    (n * 5).div_ceil(100)
+}
+
+/// The mean of `values` once the fastest and slowest are trimmed, as many as `trim` says.
+/// DNFs are infinitely slow, so as the WCA has it a DNF is trimmed as the worst solve, and
+/// more of them than are trimmed make the average a DNF.
+pub fn trimmed_mean(values: &[f32]) -> OrderedFloat<f32> {
+    let mut sorted: Vec<OrderedFloat<f32>> = values.iter().copied().map(OrderedFloat).collect();
+    sorted.sort();
+    let trim = trim(values.len());
+    let kept = &sorted[trim..sorted.len().saturating_sub(trim).max(trim)];
+    let sum: OrderedFloat<f32> = kept.iter().copied().sum();
+    sum / OrderedFloat(kept.len() as f32)
+}
+
+/// The plain mean of `values`, as a mean of 3 is taken. Nothing is trimmed, so a single
+/// DNF makes it a DNF.
+pub fn mean(values: &[f32]) -> OrderedFloat<f32> {
+    OrderedFloat(values.iter().sum::<f32>() / values.len() as f32)
+}
+
+/// The event and variation a puzzle's name stands for: an event's, or `oh` or `feet` for
+/// 3x3 solved that way.
+pub fn puzzle(name: &str) -> Option<(Event, Variation)> {
+    match Variation::parse(name) {
+        Some(variation) => Some((Event::Cube3, variation)),
+        None => Some((Event::parse(name)?, Variation::Normal)),
+    }
+}
+
+/// A session's event, with its variation when that isn't the usual one.
+pub fn puzzle_name(meta: &SessionMeta) -> String {
+    match meta.variation {
+        Variation::Normal => meta.event.to_string(),
+        v => format!("{} {}", meta.event, v),
+    }
+}
+
+/// Move a list's selection a place up or down, going round at either end.
+fn step(state: &mut ListState, len: usize, dir: Dir) {
+    if len == 0 {
+        return;
+    }
+    let i = state.selected().unwrap_or(0);
+    let i = match dir {
+        Dir::Up => (i + len - 1) % len,
+        Dir::Down => (i + 1) % len,
+        _ => i,
+    };
+    state.select(Some(i));
+}
+
+/// The sample standard deviation of `values`, which takes two of them.
+pub fn deviation(values: &[f32]) -> Option<OrderedFloat<f32>> {
+    let n = values.len();
+    if n < 2 {
+        return None;
     }
+    let mean = mean(values).0;
+    let squares: f32 = values.iter().map(|v| (v - mean).powi(2)).sum();
+    Some(OrderedFloat((squares / (n - 1) as f32).sqrt()))
 }
 
 impl std::fmt::Display for Time {
@@ -96,144 +385,476 @@ impl std::fmt::Display for Time {
 }
 
 pub struct Times {
+    /// The newest solves, oldest first; older ones are paged in on demand
     pub times: Vec<Time>,
+    /// Solves in the whole session, loaded or not
+    pub count: usize,
     pub pbsingle: Option<OrderedFloat<f32>>,
+    pub pbmo3: Option<OrderedFloat<f32>>,
     pub pbao5: Option<OrderedFloat<f32>>,
     pub pbao12: Option<OrderedFloat<f32>>,
+    pub pbao50: Option<OrderedFloat<f32>>,
+    pub pbao100: Option<OrderedFloat<f32>>,
+    pub pbao1000: Option<OrderedFloat<f32>>,
+    /// The latest larger averages
+    pub ao50: Option<OrderedFloat<f32>>,
     pub ao100: Option<OrderedFloat<f32>>,
-    pub ao1k: Option<OrderedFloat<f32>>,
+    pub ao1000: Option<OrderedFloat<f32>>,
     pub rollingavg: Option<OrderedFloat<f32>>,
-    pub sum: OrderedFloat<f32>,
+    pub spread: Spread,
 }
 
 impl Times {
     pub fn new() -> Self {
         Self {
             times: vec![],
+            count: 0,
             pbsingle: None,
+            pbmo3: None,
             pbao5: None,
             pbao12: None,
+            pbao50: None,
+            pbao100: None,
+            pbao1000: None,
+            ao50: None,
             ao100: None,
-            ao1k: None,
+            ao1000: None,
             rollingavg: None,
-            sum: OrderedFloat(0.0),
+            spread: Spread::default(),
         }
     }
 
     pub fn insert(&mut self, time: Time) {
         self.times.push(time);
-        Times::update_best(&mut self.pbsingle, Some(OrderedFloat(time.time)));
-        Times::update_best(&mut self.pbao5, time.ao5);
-        Times::update_best(&mut self.pbao12, time.ao12);
+    }
+
+    /// Take the session-wide numbers from the storage backend.
+    pub fn apply(&mut self, summary: Summary) {
+        self.count = summary.count;
+        self.pbsingle = summary.pbsingle;
+        self.pbmo3 = summary.pbmo3;
+        self.pbao5 = summary.pbao5;
+        self.pbao12 = summary.pbao12;
+        self.pbao50 = summary.pbao50;
+        self.pbao100 = summary.pbao100;
+        self.pbao1000 = summary.pbao1000;
+        self.ao50 = summary.ao50;
+        self.ao100 = summary.ao100;
+        self.ao1000 = summary.ao1000;
+        self.rollingavg = summary.mean;
+        self.spread = summary.spread;
+    }
+
+    /// The best and worst the next ao`n` of `variation` could be, once the `n - 1` solves
+    /// before it are in: with a solve faster than all of them, or with a DNF.
+    pub fn possible(
+        &self,
+        n: usize,
+        variation: Variation,
+    ) -> Option<(OrderedFloat<f32>, OrderedFloat<f32>)> {
+        let mut values = self.before_next(n, variation)?;
+        values.push(0.0);
+        let best = trimmed_mean(&values);
+        values[n - 1] = f32::INFINITY;
+        Some((best, trimmed_mean(&values)))
+    }
 
-        if self.times.len() >= 100 {
-            let mut tr = self.times.clone();
-            tr.reverse();
-            self.ao100 = Some(Times::calc_aon(&tr[0..100]));
-            if self.times.len() >= 1000 {
-                self.ao1k = Some(Times::calc_aon(&tr[0..1000]));
+    /// What the next solve of `variation` has to come in under for the ao`n` it ends to beat
+    /// `pb`: infinite if even a DNF would, `None` if nothing would.
+    pub fn needed(
+        &self,
+        n: usize,
+        variation: Variation,
+        pb: OrderedFloat<f32>,
+    ) -> Option<OrderedFloat<f32>> {
+        let (best, worst) = self.possible(n, variation)?;
+        if best >= pb {
+            return None;
+        }
+        if worst < pb {
+            return Some(OrderedFloat(f32::INFINITY));
+        }
+        // The average only rises with the next solve, and at `n` times the PB that solve
+        // either counts and is too slow alone or is trimmed, leaving the worst possible
+        let mut values = self.before_next(n, variation)?;
+        values.push(0.0);
+        let (mut under, mut over) = (0.0, pb.0 * n as f32);
+        for _ in 0..40 {
+            let mid = (under + over) / 2.0;
+            values[n - 1] = mid;
+            match trimmed_mean(&values) < pb {
+                true => under = mid,
+                false => over = mid,
             }
         }
+        Some(OrderedFloat(over))
+    }
 
-        self.sum += OrderedFloat(time.time);
+    /// The latest `n - 1` solves of `variation`, if there are that many to end an ao`n` with
+    /// the next.
+    fn before_next(&self, n: usize, variation: Variation) -> Option<Vec<f32>> {
+        let values: Vec<f32> = self
+            .times
+            .iter()
+            .rev()
+            .filter(|v| v.variation == variation)
+            .take(n - 1)
+            .map(Time::value)
+            .collect();
+        (values.len() == n - 1).then_some(values)
+    }
 
-        self.rollingavg = match self.rollingavg {
-            Some(_) => Some(self.sum / self.times.len() as f32),
-            None => Some(OrderedFloat(time.time)),
-        }
+    /// How many of the latest `n` loaded solves of `variation` beat `time`, and how many
+    /// there were, which is fewer than `n` early on.
+    pub fn beating(&self, time: f32, variation: Variation, n: usize) -> (usize, usize) {
+        let latest = self
+            .times
+            .iter()
+            .rev()
+            .filter(|v| v.variation == variation)
+            .take(n);
+        latest.fold((0, 0), |(hits, count), v| {
+            (hits + usize::from(v.value() < time), count + 1)
+        })
     }
 
-    fn update_best(curr: &mut Option<OrderedFloat<f32>>, t: Option<OrderedFloat<f32>>) {
-        let new = match t {
-            Some(x) => x,
-            None => return,
-        };
+    /// How many of the latest `n` solves of `variation` were DNFs and +2s, out of how many
+    /// there are.
+    pub fn penalties(&self, variation: Variation, n: usize) -> (usize, usize, usize) {
+        let latest = self
+            .times
+            .iter()
+            .rev()
+            .filter(|v| v.variation == variation)
+            .take(n);
+        latest.fold((0, 0, 0), |(dnfs, plus_twos, count), v| {
+            (
+                dnfs + usize::from(v.penalty == Penalty::Dnf),
+                plus_twos + usize::from(v.penalty == Penalty::PlusTwo),
+                count + 1,
+            )
+        })
+    }
 
-        match curr {
-            Some(v) => {
-                if new < *v {
-                    *curr = Some(OrderedFloat(*new));
-                }
-            }
-            None => *curr = Some(OrderedFloat(*new)),
+    /// How far the latest `n` singles of `variation` stray from their mean, DNFs aside, to
+    /// tell a steady average from one a few lucky solves brought down.
+    pub fn consistency(&self, n: usize, variation: Variation) -> Option<OrderedFloat<f32>> {
+        let latest: Vec<f32> = self
+            .times
+            .iter()
+            .rev()
+            .filter(|v| v.variation == variation)
+            .take(n)
+            .map(Time::value)
+            .collect();
+        if latest.len() < n {
+            return None;
         }
+        let solved: Vec<f32> = latest.into_iter().filter(|v| v.is_finite()).collect();
+        deviation(&solved)
     }
 
-    fn calc_aon(set: &[Time]) -> OrderedFloat<f32> {
-        let mut t = set
+    /// How fast solves of `variation` are going now: a mean of the loaded ones that counts
+    /// each for less the older it is, so it follows form more smoothly than an ao12 does.
+    /// DNFs aren't a speed, so they're passed over; it takes five solves to start.
+    pub fn speed(&self, variation: Variation) -> Option<OrderedFloat<f32>> {
+        let alpha = 2.0 / (SPEED_SPAN + 1.0);
+        let mut solved = self
+            .times
             .iter()
-            .take(set.len())
-            .map(|v| OrderedFloat(v.time))
-            .collect::<Vec<OrderedFloat<f32>>>();
-        // Remove best and worst time
-        t.sort();
-        t.pop();
-        t.remove(0);
+            .filter(|v| v.variation == variation)
+            .map(Time::value)
+            .filter(|v| v.is_finite());
+        let mut speed = solved.next()?;
+        let mut count = 1;
+        for value in solved {
+            speed += alpha * (value - speed);
+            count += 1;
+        }
+        (count >= 5).then_some(OrderedFloat(speed))
+    }
+
+    pub fn calc_aon(set: &[Time]) -> OrderedFloat<f32> {
+        let values: Vec<f32> = set.iter().map(Time::value).collect();
+        trimmed_mean(&values)
+    }
 
-        let mut sum = OrderedFloat(0.0);
-        let _ = t.iter().map(|v| sum += v).collect::<Vec<()>>();
-        sum / OrderedFloat(t.len() as f32)
+    pub fn calc_mean(set: &[Time]) -> OrderedFloat<f32> {
+        let values: Vec<f32> = set.iter().map(Time::value).collect();
+        mean(&values)
     }
 }
 
+/// Time the WCA gives to find a fewest-moves solution.
+pub const FMC_LIMIT: Duration = Duration::from_secs(60 * 60);
+
+/// A fewest-moves attempt under way.
+pub struct FmcAttempt {
+    pub start: Instant,
+    /// Whether the solution box opened itself when the hour ran out
+    prompted: bool,
+    /// The solution as far as it was typed, kept when the box is closed
+    draft: String,
+}
+
+/// Inspection time the WCA allows before a solve; starting later costs +2, and starting
+/// more than two seconds later still is a DNF.
+const INSPECTION: Duration = Duration::from_secs(15);
+const INSPECTION_DNF: Duration = Duration::from_secs(17);
+/// The warnings a WCA judge calls during inspection, by when, and how often the bell rings
+/// for each to tell them apart by ear.
+const WARNINGS: [(Duration, &str, u32); 2] = [
+    (Duration::from_secs(8), "8", 1),
+    (Duration::from_secs(12), "12", 2),
+];
+/// Time between rings of the bell for one warning, as terminals run rings too close
+/// together into one.
+const RING_GAP: Duration = Duration::from_millis(300);
+/// Terminals don't report keys being let go, so a held key that stops repeating for
+/// this long has been released.
+const RELEASE_GAP: Duration = Duration::from_millis(150);
+/// A press that hasn't started repeating by now was a tap rather than a hold.
+const REPEAT_WAIT: Duration = Duration::from_secs(1);
+
 #[derive(Debug)]
 pub struct CubeTimer {
     pub starttime: Option<Instant>,
     pub on: bool,
     pub lasttime: Option<Duration>,
+    /// The last solve's penalty, from its inspection or given after
+    pub lastpenalty: Penalty,
+    /// When the last solve was recorded, telling it apart from ones recorded otherwise
+    pub lastdate: Option<i64>,
+    /// Whether the first press starts inspection rather than the solve
+    inspection: bool,
+    /// When inspection for the coming solve began
+    pub inspecting: Option<Instant>,
+    penalty: Penalty,
+    /// How long space must be held before letting go starts the solve; without it,
+    /// pressing starts it
+    hold: Option<Duration>,
+    /// When space went down to start a solve, and when it last repeated
+    held: Option<(Instant, Instant)>,
+    /// When space went down or last repeated after a press that already did something,
+    /// so holding it on doesn't arm the timer
+    spent: Option<Instant>,
+    /// Phases a solve is split into, each but the last ended by a press of space
+    phases: usize,
+    /// How far into the solve under way each phase so far ended
+    splits: Vec<Duration>,
+    /// How far into a blindfolded solve memorizing ended
+    pub memo: Option<Duration>,
 }
 
 impl CubeTimer {
-    pub fn default() -> Self {
+    pub fn new(inspection: bool, hold: Option<Duration>, phases: usize) -> Self {
         Self {
             starttime: None,
             on: false,
             lasttime: None,
+            lastpenalty: Penalty::None,
+            lastdate: None,
+            inspection,
+            inspecting: None,
+            penalty: Penalty::None,
+            hold,
+            held: None,
+            spent: None,
+            phases,
+            splits: vec![],
+            memo: None,
         }
     }
 
-    pub fn space_press(&mut self) -> Option<Time> {
-        match self.on {
-            false => {
-                self.timer_on();
-                None
+    /// The phase under way, counting from 0, when solves are split.
+    pub fn phase(&self) -> Option<usize> {
+        (self.on && self.phases > 1).then_some(self.splits.len())
+    }
+
+    /// End the phase under way as of `at`, unless it's the last.
+    fn split(&mut self, at: Instant) -> bool {
+        if self.splits.len() + 1 >= self.phases {
+            return false;
+        }
+        self.splits.push(self.elapsed(at));
+        true
+    }
+
+    /// End memorizing in the blindfolded solve under way as of `at`, if it hasn't ended.
+    pub fn end_memo(&mut self, at: Instant) -> bool {
+        if !self.on || self.memo.is_some() {
+            return false;
+        }
+        self.memo = Some(self.elapsed(at));
+        true
+    }
+
+    /// Whether a solve is under way, counting its inspection.
+    pub fn busy(&self) -> bool {
+        self.on || self.inspecting.is_some() || self.held.is_some()
+    }
+
+    /// Whether space has been held long enough to start on release, while it's held.
+    pub fn ready(&self) -> Option<bool> {
+        let hold = self.hold.unwrap_or_default();
+        self.held.map(|(since, _)| since.elapsed() >= hold)
+    }
+
+    /// Act on space going down at `now`, when its key event was read, so the time spent
+    /// handling and drawing since doesn't count towards the solve.
+    pub fn space_press(&mut self, now: Instant) -> Option<Time> {
+        if self.hold.is_none() {
+            match self.on {
+                false if self.inspection && self.inspecting.is_none() => {
+                    self.inspecting = Some(now)
+                }
+                false => self.timer_on(now),
+                true => {
+                    if !self.split(now) {
+                        return Some(self.timer_off(now));
+                    }
+                }
+            }
+            return None;
+        }
+
+        self.check_release();
+        if let Some(last) = self.spent.as_mut() {
+            *last = now;
+            return None;
+        }
+        if self.on {
+            self.spent = Some(now);
+            if self.split(now) {
+                return None;
             }
-            true => Some(self.timer_off()),
+            return Some(self.timer_off(now));
         }
+        if self.inspection && self.inspecting.is_none() {
+            self.inspecting = Some(now);
+            self.spent = Some(now);
+            return None;
+        }
+        match &mut self.held {
+            Some((_, last)) => *last = now,
+            None => self.held = Some((now, now)),
+        }
+        None
+    }
+
+    /// Notice space being let go, which only shows as it no longer repeating.
+    pub fn check_release(&mut self) {
+        if self.spent.is_some_and(|v| v.elapsed() > RELEASE_GAP) {
+            self.spent = None;
+        }
+        let Some((since, last)) = self.held else {
+            return;
+        };
+        if last == since {
+            if since.elapsed() > REPEAT_WAIT {
+                self.held = None;
+            }
+        } else if last.elapsed() > RELEASE_GAP {
+            self.held = None;
+            // The last repeat is the closest there is to when it was let go
+            if last - since >= self.hold.unwrap_or_default() {
+                self.timer_on(last);
+            }
+        }
+    }
+
+    /// Whether space is being held, so releasing it needs looking out for.
+    pub fn holding(&self) -> bool {
+        self.held.is_some() || self.spent.is_some()
     }
 
-    fn timer_on(&mut self) {
+    /// Begin inspecting as of `at`, if inspection is on and nothing is under way.
+    pub fn inspect(&mut self, at: Instant) {
+        if self.inspection && !self.busy() {
+            self.inspecting = Some(at);
+        }
+    }
+
+    /// Start the solve as of `at`, ending any inspection.
+    pub fn timer_on(&mut self, at: Instant) {
+        let inspected = self.inspecting.take().map(|v| at - v);
+        self.penalty = match inspected {
+            Some(v) if v > INSPECTION_DNF => Penalty::Dnf,
+            Some(v) if v > INSPECTION => Penalty::PlusTwo,
+            _ => Penalty::None,
+        };
         self.on = true;
-        self.starttime = Some(Instant::now());
+        self.starttime = Some(at);
+        self.splits.clear();
+        self.memo = None;
     }
 
-    fn timer_off(&mut self) -> Time {
+    fn timer_off(&mut self, at: Instant) -> Time {
+        self.timer_off_with(self.elapsed(at))
+    }
+
+    /// Stop the solve, taking `time` as what it came to.
+    pub fn timer_off_with(&mut self, time: Duration) -> Time {
         self.on = false;
-        self.lasttime = Some(self.elapsed());
+        self.lasttime = Some(time);
+        self.lastpenalty = self.penalty;
+        self.lastdate = Some(chrono::Utc::now().timestamp());
         self.starttime = None;
-        Time::from(
-            self.lasttime
-                .unwrap_or(Duration::from_secs(0))
-                .as_secs_f32(),
-        )
+        let mut ends = std::mem::take(&mut self.splits);
+        let mut solve = Time::from(time.as_secs_f32());
+        solve.date = self.lastdate;
+        // Only inspection gives a solve a penalty before it's recorded
+        solve.penalty = self.penalty;
+        solve.inspection_penalty = self.penalty != Penalty::None;
+        solve.memo = self.memo.take().map(|v| v.as_secs_f32());
+        if !ends.is_empty() {
+            ends.push(time);
+            let starts = std::iter::once(Duration::ZERO).chain(ends.clone());
+            solve.phases = ends
+                .iter()
+                .zip(starts)
+                .map(|(end, start)| end.saturating_sub(start).as_secs_f32())
+                .collect();
+        }
+        solve
+    }
+
+    /// Show a typed-in time as the last solve, returning it to record.
+    pub fn typed(&mut self, secs: f32, penalty: Penalty) -> Time {
+        let typed = Duration::from_secs_f32(secs);
+        self.lasttime = Some(typed);
+        self.lastpenalty = penalty;
+        self.lastdate = Some(chrono::Utc::now().timestamp());
+        // Through a Duration like timed solves, so the Timer panel still matches it after
+        let mut time = Time::from(typed.as_secs_f32());
+        time.penalty = penalty;
+        time.date = self.lastdate;
+        time
     }
 
-    fn elapsed(&self) -> Duration {
+    /// How long the solve had been running at `at`.
+    fn elapsed(&self, at: Instant) -> Duration {
         match self.starttime {
-            Some(v) => v.elapsed(),
+            Some(v) => at.saturating_duration_since(v),
             None => Duration::new(0, 0),
         }
     }
 
-    pub fn text(&self) -> String {
+    pub fn text(&self, format: &TimeFormat) -> String {
+        if let Some(v) = self.inspecting {
+            // Counts down the whole seconds left, then shows what starting now would cost
+            return match v.elapsed() {
+                v if v > INSPECTION_DNF => "DNF".to_string(),
+                v if v > INSPECTION => "+2".to_string(),
+                v => format!("{}", (INSPECTION - v).as_secs_f32().ceil()),
+            };
+        }
         match self.starttime {
-            Some(v) => format!("{:.1}", v.elapsed().as_secs_f32()),
-            None => format!(
-                "{:.3}",
-                self.lasttime
-                    .unwrap_or(Duration::from_secs(0))
-                    .as_secs_f32()
-            ),
+            Some(v) => format.running(v.elapsed().as_secs_f32()),
+            None => {
+                let time = self.lasttime.unwrap_or(Duration::from_secs(0));
+                format.timer(time.as_secs_f32(), self.lastpenalty)
+            }
         }
     }
 }
@@ -247,98 +868,1181 @@ pub enum Dir {
 
 pub enum Tool {
     Welcome,
-    Chart,
+    Drill,
+    Stats,
+    /// Solves counted into buckets of so many seconds, or a size chosen to fit
+    Histogram {
+        bucket: Option<f32>,
+    },
+    /// Personal bests as they were set, newest first
+    Pbs,
+    /// The latest so many solves plotted with their ao12
+    Chart {
+        count: usize,
+    },
+    /// Two sessions side by side, as `App::comparison` has them
+    Compare,
+    /// Solves each day of the latest weeks
+    Calendar,
+    /// The fastest of each stat over every session of the event, as `App::records` has them
+    Records,
+    /// Bests over every event against the world records, as `App::kinch` has them
+    Kinch,
 }
 
-pub struct App<'a> {
-    pub tick_rate: Duration,
+pub struct App {
     pub timer: CubeTimer,
+    pub time_format: TimeFormat,
     pub route: Route,
-    pub path: &'a Path,
+    pub sessions: SessionManager,
+    pub backup_list: Vec<PathBuf>,
+    pub backup_state: ListState,
+    pub session_list: Vec<(String, SessionMeta)>,
+    pub session_state: ListState,
+    /// Whether the selected session is to be deleted once that's confirmed
+    pub deleting: bool,
+    /// Whether the session list has archived sessions in it too
+    pub show_archived: bool,
+    /// The tag the session list is narrowed to
+    pub session_tag: Option<String>,
+    /// The session's notes as they're being written, kept once done
+    pub notes: String,
     pub pos: (usize, usize),
-    pub times: Times,
     pub times_state: TableState,
     layout: Vec<Vec<ActiveBlock>>,
     pub scramble: String,
+    /// The scramble after this one, shown ahead of time when `show_next` is on
+    pub next_scramble: String,
+    pub show_next: bool,
+    /// Names of the phases solves are split into
+    pub phases: Vec<String>,
+    /// Space opens a box to type the solve's time into rather than starting the timer
+    pub typing: bool,
+    /// Keep the time out of sight until the solve stops
+    pub hide_timer: bool,
+    /// Any key ends a running solve, as space does
+    pub stop_on_any_key: bool,
+    /// The keys of the slam that last stopped a solve in place of space, and when the
+    /// latest of them came
+    pub slammed: Option<(Instant, Vec<KeyCode>)>,
+    /// Start a session for each day
+    daily_sessions: bool,
+    /// The day whose session was last started or switched to
+    daily: Option<chrono::NaiveDate>,
+    /// Beats kept while the timer runs
+    pub metronome: Option<Metronome>,
+    /// How to sound the warnings while inspecting, if at all
+    inspection_warnings: Option<InspectionWarnings>,
+    /// Rings or calls of the warnings made so far this inspection
+    warned: usize,
+    /// A Speed Stacks timer starting and stopping solves
+    pub stackmat: Option<Stackmat>,
+    /// A smart cube timing solves by its turns
+    pub smartcube: Option<Bridge>,
+    /// How to draw the scrambled cube beside the scramble
+    pub cube_view: CubeView,
+    /// Scrambles shown before the current one, oldest first
+    scramble_history: Vec<String>,
+    /// Where scrambles come from when racing someone on the same seed
+    pub seed: Option<String>,
+    /// Generates scrambles in the background; an empty scramble is still on its way
+    scrambler: Worker,
     pub active_screen: Screen,
     pub tool: Tool,
+    /// The sessions last compared, kept while the comparison may be showing
+    pub comparison: Option<Comparison>,
+    /// The all-time bests last looked up, kept while they may be showing
+    pub records: Option<Records>,
+    /// The Kinch score last worked out, kept while it may be showing
+    pub kinch: Option<Kinch>,
+    pub input: Option<Input>,
+    /// A multi-blind attempt waiting on its cubes to be counted
+    multiblind: Option<Time>,
+    /// The mean of 3 or average of 5 being solved
+    pub attempt: Option<Attempt>,
+    /// The alg being drilled, kept once time is up to show how it went
+    pub drill: Option<Drill>,
+    /// The fewest-moves attempt being worked on
+    pub fmc: Option<FmcAttempt>,
+    pub status: Option<String>,
+    auto_export: Option<AutoExport>,
+    last_export: Instant,
+    session_config: HashMap<String, SessionConfig>,
+    scramble_settings: HashMap<Event, Settings>,
+    pub custom_stats: Vec<CustomStat>,
 }
 
-impl<'a> App<'a> {
-    pub fn new(tick_rate: Duration, path: &'a Path) -> Result<Self, Box<dyn Error>> {
+impl App {
+    pub fn new(config: &Config, cipher: Option<Cipher>) -> Result<Self, Box<dyn Error>> {
+        if let Some(auto) = &config.auto_export {
+            if !export::FORMATS.contains(&auto.format.as_str()) {
+                return Err(format!("unknown auto_export format: {}", auto.format).into());
+            }
+        }
+        let hold = config
+            .hold
+            .map(Duration::try_from_secs_f32)
+            .transpose()
+            .map_err(|_| "hold must be a number of seconds")?;
+        config.display.check()?;
+        for (event, settings) in &config.scramble {
+            settings.check(*event)?;
+        }
+        for stat in &config.stats {
+            stat.check()?;
+        }
+        let stackmat = match (&config.stackmat, &config.stackmat_audio) {
+            (Some(_), Some(_)) => return Err("set stackmat or stackmat_audio, not both".into()),
+            (Some(path), None) => Some(Stackmat::open(&expand(&path.to_string_lossy()))?),
+            (None, Some(audio)) => Some(Stackmat::listen(&audio.command, audio.rate)?),
+            (None, None) => None,
+        };
+        let smartcube = match &config.smart_cube {
+            Some(cube) => {
+                let address = cube.address.as_deref();
+                let fallback = if address.is_some() { "gan" } else { "notation" };
+                let name = cube.protocol.as_deref().unwrap_or(fallback);
+                let protocol = smartcube::protocol(name, address)?;
+                match (address, &cube.command) {
+                    (Some(_), Some(_)) => return Err("set smart_cube address or command".into()),
+                    (Some(address), None) => Some(Bridge::connect(address, protocol)?),
+                    (None, Some(command)) => Some(Bridge::listen(command, protocol)?),
+                    (None, None) => return Err("smart_cube needs an address or command".into()),
+                }
+            }
+            None => None,
+        };
+        let metronome = config.metronome.as_ref().map(Metronome::new).transpose()?;
+        let mut sessions = SessionManager::open(&config.data_dir()?, cipher)?;
+        let status = sessions.current.take_notice();
         // Construct app
-        Ok(App {
-            tick_rate,
-            timer: CubeTimer::default(),
+        let mut app = App {
+            timer: CubeTimer::new(config.inspection, hold, config.phases.len()),
+            time_format: config.display,
             route: Route::default(),
-            path,
-            times: Times::new(),
+            sessions,
+            backup_list: vec![],
+            backup_state: ListState::default(),
+            session_list: vec![],
+            session_state: ListState::default(),
+            deleting: false,
+            show_archived: false,
+            session_tag: None,
+            notes: String::new(),
             times_state: TableState::default(),
             pos: (0, 2),
             layout: vec![
                 vec![ActiveBlock::Tools, ActiveBlock::Timer, ActiveBlock::Times],
                 vec![ActiveBlock::Scramble, ActiveBlock::Stats, ActiveBlock::Main],
             ],
-            scramble: gen_scramble(),
+            scramble: String::new(),
+            next_scramble: String::new(),
+            show_next: false,
+            phases: config.phases.clone(),
+            typing: config.typing,
+            hide_timer: config.hide_timer,
+            stop_on_any_key: config.stop_on_any_key,
+            slammed: None,
+            daily_sessions: config.daily_sessions,
+            daily: None,
+            metronome,
+            inspection_warnings: config.inspection_warnings.clone(),
+            warned: 0,
+            stackmat,
+            smartcube,
+            cube_view: CubeView::Net,
+            scramble_history: vec![],
+            seed: None,
+            scrambler: Worker::spawn(),
             active_screen: Screen::Default,
             tool: Tool::Welcome,
+            comparison: None,
+            records: None,
+            kinch: None,
+            input: None,
+            multiblind: None,
+            attempt: None,
+            drill: None,
+            fmc: None,
+            status,
+            auto_export: config.auto_export.clone(),
+            last_export: Instant::now(),
+            session_config: config.sessions.clone(),
+            scramble_settings: config.scramble.clone(),
+            custom_stats: config.stats.clone(),
+        };
+        app.reset_scrambles();
+        Ok(app)
+    }
+
+    pub fn write_times(&mut self) -> Result<(), Box<dyn Error>> {
+        self.sessions.current.flush()
+    }
+
+    /// Change the current session's puzzle and scramble for it.
+    pub fn set_event(&mut self, event: Event) -> Result<(), Box<dyn Error>> {
+        if self.timer.busy() || self.fmc.is_some() {
+            return Err("finish the solve first".into());
+        }
+        if self.attempt.is_some() {
+            return Err("finish the attempt first, or `:attempt stop`".into());
+        }
+        self.sessions.current.set_event(event)?;
+        self.reset_scrambles();
+        Ok(())
+    }
+
+    /// Refresh the configured export, if there is one.
+    pub fn auto_export(&mut self) -> Result<(), Box<dyn Error>> {
+        self.last_export = Instant::now();
+        if let Some(auto) = &self.auto_export {
+            let path = expand(&auto.path.to_string_lossy());
+            export::write(&auto.format, &path, &mut self.sessions.current)?;
+        }
+        Ok(())
+    }
+
+    pub fn add_time(&mut self, mut time: Time) -> Result<(), Box<dyn Error>> {
+        // A multi-blind attempt isn't over until its cubes are counted
+        if self.sessions.current.meta.event == Event::Multiblind && time.multiblind.is_none() {
+            self.multiblind = Some(time);
+            self.open_input(InputMode::Multiblind);
+            return Ok(());
+        }
+        time.scramble = self.scramble.clone();
+        // Solves from the timer are dated as it stopped, which the Timer panel goes by
+        if time.date.is_none() {
+            time.date = Some(chrono::Utc::now().timestamp());
+        }
+        self.sessions.current.push(time)?;
+        self.new_scramble();
+        self.advance_attempt()
+    }
+
+    /// Start a mean of 3 or average of 5, by default the one the WCA holds the event in.
+    pub fn start_attempt(&mut self, format: Option<Format>) -> Result<String, Box<dyn Error>> {
+        let event = self.sessions.current.meta.event;
+        if event == Event::Multiblind {
+            return Err("a multi-blind attempt is a single solve".into());
+        }
+        if self.timer.busy() || self.fmc.is_some() {
+            return Err("finish the solve first".into());
+        }
+        let format = format.unwrap_or_else(|| Format::of(event));
+        self.attempt = Some(Attempt::new(format));
+        let solves = format.solves();
+        Ok(format!("{} attempt: {} solves to go", format, solves))
+    }
+
+    /// Give up on the attempt under way, keeping its solves as ordinary ones.
+    pub fn stop_attempt(&mut self) -> Result<String, Box<dyn Error>> {
+        match self.attempt.take() {
+            Some(attempt) => Ok(format!("stopped the {} attempt", attempt.format)),
+            None => Err("no attempt is under way".into()),
+        }
+    }
+
+    /// Drill the alg called `name`, or typed as `alg`, for `length`.
+    pub fn start_drill(&mut self, text: &str, length: Duration) -> Result<String, Box<dyn Error>> {
+        if self.timer.busy() || self.fmc.is_some() {
+            return Err("finish the solve first".into());
+        }
+        let drill = match drill::named(text) {
+            Some(alg) => Drill::new(Some(text.to_lowercase()), alg.to_string(), length),
+            None => {
+                let alg = scramble::parse(self.sessions.current.meta.event, text)?;
+                Drill::new(None, alg, length)
+            }
+        };
+        let message = format!(
+            "drilling {} for {}s, space starts",
+            drill.title(),
+            length.as_secs()
+        );
+        self.drill = Some(drill);
+        self.tool = Tool::Drill;
+        Ok(message)
+    }
+
+    pub fn stop_drill(&mut self) -> Result<String, Box<dyn Error>> {
+        let drill = self.drill.take().ok_or("no drill is under way")?;
+        self.tool = Tool::Welcome;
+        Ok(format!("stopped drilling {}", drill.title()))
+    }
+
+    /// Show the session's stats in the Main panel, or put them back away.
+    pub fn toggle_stats(&mut self) -> String {
+        match self.tool {
+            Tool::Stats => self.put_away("stats"),
+            _ => {
+                self.tool = Tool::Stats;
+                "showing the session's stats".to_string()
+            }
+        }
+    }
+
+    /// Show how the solves spread over times `bucket` seconds wide, or wide enough to fit
+    /// the panel when not given. Put away again when it's showing and none is given.
+    pub fn toggle_histogram(&mut self, bucket: Option<f32>) -> String {
+        match (&self.tool, bucket) {
+            (Tool::Histogram { .. }, None) => self.put_away("histogram"),
+            (_, Some(v)) => {
+                self.tool = Tool::Histogram { bucket: Some(v) };
+                format!("showing solves in {}s buckets", v)
+            }
+            (_, None) => {
+                self.tool = Tool::Histogram { bucket: None };
+                "showing how the solves spread".to_string()
+            }
+        }
+    }
+
+    /// List the session's personal bests as they came down, or put them back away.
+    pub fn toggle_pbs(&mut self) -> String {
+        match self.tool {
+            Tool::Pbs => self.put_away("personal bests"),
+            _ => {
+                self.tool = Tool::Pbs;
+                "showing personal bests as they were set".to_string()
+            }
+        }
+    }
+
+    /// Plot the latest `count` solves, or put the chart away when it's showing and no count
+    /// is given.
+    pub fn toggle_chart(&mut self, count: Option<usize>) -> String {
+        match (&self.tool, count) {
+            (Tool::Chart { .. }, None) => self.put_away("chart"),
+            (_, count) => {
+                let count = count.unwrap_or(CHART_SOLVES);
+                self.tool = Tool::Chart { count };
+                format!("charting the last {} solves", count)
+            }
+        }
+    }
+
+    /// Show how many solves each recent day had, or put the calendar back away.
+    pub fn toggle_calendar(&mut self) -> String {
+        match self.tool {
+            Tool::Calendar => self.put_away("calendar"),
+            _ => {
+                self.tool = Tool::Calendar;
+                "showing solves by day".to_string()
+            }
+        }
+    }
+
+    /// Compare session `first` with `second`, or with the current session when only one is
+    /// given. With neither, put the comparison away when it's showing.
+    pub fn toggle_compare(
+        &mut self,
+        first: Option<&str>,
+        second: Option<&str>,
+    ) -> Result<String, Box<dyn Error>> {
+        let Some(first) = first else {
+            return match self.tool {
+                Tool::Compare => {
+                    self.comparison = None;
+                    Ok(self.put_away("comparison"))
+                }
+                _ => Err("usage: compare <session> [session]".into()),
+            };
+        };
+        let ids = match second {
+            Some(second) => [first.to_string(), second.to_string()],
+            None => [self.sessions.current.id.clone(), first.to_string()],
+        };
+        // Like is compared with like: the solves done the way new ones are
+        let variation = self.sessions.current.meta.variation;
+        let (meta, times) = self.sessions.peek(&ids[0])?;
+        let first = Side::of(&ids[0], meta, &times, variation);
+        let (meta, times) = self.sessions.peek(&ids[1])?;
+        let second = Side::of(&ids[1], meta, &times, variation);
+        self.comparison = Some(Comparison {
+            variation,
+            sides: [first, second],
+        });
+        self.tool = Tool::Compare;
+        Ok(format!("comparing {} with {}", ids[0], ids[1]))
+    }
+
+    /// Show the session's bests beside the fastest of every session of its event, or put
+    /// them back away.
+    pub fn toggle_records(&mut self) -> Result<String, Box<dyn Error>> {
+        if let Tool::Records = self.tool {
+            self.records = None;
+            return Ok(self.put_away("records"));
+        }
+        let event = self.sessions.current.meta.event;
+        let variation = self.sessions.current.meta.variation;
+        let mut summaries = vec![];
+        for id in self.sessions.ids()? {
+            let (meta, times) = self.sessions.peek(&id)?;
+            if meta.event == event {
+                summaries.push((id, Summary::from_times(&times, variation)));
+            }
+        }
+        let records = Records::of(event, variation, &self.sessions.current.id, summaries);
+        let sessions = records.sessions;
+        self.records = Some(records);
+        self.tool = Tool::Records;
+        Ok(match sessions {
+            1 => format!("records from the one {} session", event),
+            n => format!("records from {} {} sessions", n, event),
         })
     }
 
-    pub fn load_times(&mut self) -> Result<(), Box<dyn Error>> {
-        // Do file stuff
-        // fs::create_dir_all(self.path)?;
+    /// Score the bests of every session's event against the world records, or put the
+    /// scores back away.
+    pub fn toggle_kinch(&mut self) -> Result<String, Box<dyn Error>> {
+        if let Tool::Kinch = self.tool {
+            self.kinch = None;
+            return Ok(self.put_away("Kinch score"));
+        }
+        let mut sessions = vec![];
+        for id in self.sessions.ids()? {
+            let (meta, times) = self.sessions.peek(&id)?;
+            sessions.push((meta.event, times));
+        }
+        let kinch = Kinch::of(&sessions, &self.time_format);
+        let score = kinch.score;
+        self.kinch = Some(kinch);
+        self.tool = Tool::Kinch;
+        Ok(format!("a Kinch score of {:.2} over every session", score))
+    }
+
+    /// Go back from a view to the drill, if one is out, or else the welcome screen.
+    fn put_away(&mut self, view: &str) -> String {
+        match self.drill {
+            Some(_) => {
+                self.tool = Tool::Drill;
+                "back to the drill".to_string()
+            }
+            None => {
+                self.tool = Tool::Welcome;
+                format!("put the {} away", view)
+            }
+        }
+    }
+
+    /// Whether space goes to a drill rather than the timer.
+    pub fn drilling(&self) -> bool {
+        self.drill.as_ref().is_some_and(|v| !v.finished)
+    }
 
-        // Create file if it doesn't exist
-        match fs::File::open(&self.path) {
-            Err(_) => _ = fs::File::create(&self.path)?,
-            Ok(_) => (),
+    /// End the drill when its time is up, saying how it went.
+    pub fn poll_drill(&mut self) {
+        let Some(drill) = self.drill.as_mut() else {
+            return;
         };
+        if !drill.poll() {
+            return;
+        }
+        let format = &self.time_format;
+        self.status = Some(match (drill.mean(), drill.best()) {
+            (Some(mean), Some(best)) => format!(
+                "{} reps of {} in {}s, {} a rep on average, {} at best",
+                drill.reps.len(),
+                drill.title(),
+                drill.length.as_secs(),
+                format.time(mean),
+                format.time(best)
+            ),
+            _ => format!("no reps of {} finished in time", drill.title()),
+        });
+    }
 
-        let mut times: Vec<Time> = fs::read_to_string(&self.path)?
-            .lines()
-            .filter_map(|v| v.parse::<f32>().ok())
-            .map(|v| Time::from(v))
-            .collect();
+    /// Count the solve just added towards the attempt, recording the attempt once it's
+    /// done.
+    fn advance_attempt(&mut self) -> Result<(), Box<dyn Error>> {
+        let Some(attempt) = self.attempt.as_mut() else {
+            return Ok(());
+        };
+        if let Some(last) = self.sessions.current.times.times.last() {
+            attempt.solves.push(last.clone());
+        }
+        if !attempt.done() {
+            return Ok(());
+        }
+        let Some(attempt) = self.attempt.take() else {
+            return Ok(());
+        };
+        let event = self.sessions.current.meta.event;
+        let record = Record::new(attempt.format, event, attempt.solves);
+        // The best earlier result in the same format and event, to say if this beat it
+        let best = self
+            .sessions
+            .current
+            .attempts
+            .iter()
+            .filter(|v| v.format == record.format && v.event == event)
+            .map(|v| OrderedFloat(v.value()))
+            .min();
+        let result = self
+            .time_format
+            .average(Some(OrderedFloat(record.value())), "");
+        self.status = Some(match best {
+            Some(v) if record.value() < v.0 => format!("{}: {}, a new best", record.format, result),
+            Some(v) if v.is_finite() => format!(
+                "{}: {} (best {})",
+                record.format,
+                result,
+                self.time_format.average(Some(v), "")
+            ),
+            _ => format!("{}: {}", record.format, result),
+        });
+        self.sessions.current.record_attempt(record)
+    }
 
-        for time in &mut times {
-            time.gen_stats(&self.times.times);
-            self.times.insert(*time);
+    /// Start and stop solves as the stackmat does, taking its time for them.
+    pub fn poll_stackmat(&mut self) -> Result<(), Box<dyn Error>> {
+        let Some(stackmat) = self.stackmat.as_mut() else {
+            return Ok(());
+        };
+        let packets = stackmat.poll();
+        if !self.sessions.current.meta.event.timed() {
+            return Ok(());
+        }
+        for packet in packets {
+            match packet.state {
+                stackmat::State::Running if !self.timer.on => {
+                    // It started as long ago as it's been running
+                    let now = Instant::now();
+                    let start = now.checked_sub(packet.time).unwrap_or(now);
+                    self.timer.timer_on(start);
+                }
+                stackmat::State::Stopped if self.timer.on => {
+                    let time = self.timer.timer_off_with(packet.time);
+                    self.add_time(time)?;
+                }
+                _ => (),
+            }
         }
         Ok(())
     }
 
-    pub fn write_times(&self) -> Result<(), Box<dyn Error>> {
-        let write_data: Vec<u8> = self
+    /// Follow the smart cube's turns: scrambling it arms the timer, its next turn starts the
+    /// solve and solving it stops it, recording the turns with the time.
+    pub fn poll_smartcube(&mut self) -> Result<(), Box<dyn Error>> {
+        let Some(cube) = self.smartcube.as_mut() else {
+            return Ok(());
+        };
+        let turns = cube.poll();
+        let event = self.sessions.current.meta.event;
+        let target = scramble::net(event, &self.scramble).filter(|v| v.n == 3 && event.timed());
+        let scrambled = |state: &Net| target.as_ref().is_some_and(|v| v.faces == state.faces);
+        // Stopped with space, or the scramble skipped, so it's waiting on the next one
+        match cube.stage {
+            Stage::Solving(_) if !self.timer.on => cube.restart(),
+            Stage::Scrambled if !scrambled(&cube.state) => cube.restart(),
+            _ => (),
+        }
+
+        let mut solved = None;
+        for turn in turns {
+            cube.state.apply(&[turn.mv]);
+            match cube.stage {
+                Stage::Scrambling if scrambled(&cube.state) => {
+                    cube.stage = Stage::Scrambled;
+                    self.timer.inspect(turn.at);
+                }
+                Stage::Scrambling => (),
+                // A blindfolded solve is started with space to memorize, so its first
+                // turn ends memo instead
+                Stage::Scrambled => match self.timer.starttime {
+                    Some(start) => {
+                        if event == Event::Cube3Blind {
+                            self.timer.end_memo(turn.at);
+                        }
+                        cube.stage = Stage::Solving(start);
+                        cube.solution = vec![turn.text];
+                    }
+                    None => {
+                        self.timer.timer_on(turn.at);
+                        cube.stage = Stage::Solving(turn.at);
+                        cube.solution = vec![turn.text];
+                    }
+                },
+                Stage::Solving(_) => cube.solution.push(turn.text),
+            }
+            if let Stage::Solving(start) = cube.stage {
+                if cube.state.is_solved() {
+                    let mut time = self.timer.timer_off_with(turn.at - start);
+                    time.solution = cube.solution.join(" ");
+                    cube.restart();
+                    solved = Some(time);
+                }
+            }
+        }
+        if let Some(time) = solved {
+            self.add_time(time)?;
+        }
+        Ok(())
+    }
+
+    /// Whether a 3BLD solve is under way and still being memorized.
+    pub fn memorizing(&self) -> bool {
+        self.sessions.current.meta.event == Event::Cube3Blind
+            && self.timer.on
+            && self.timer.memo.is_none()
+    }
+
+    /// Mark memorizing done in the 3BLD solve under way, as of `at`.
+    pub fn end_memo(&mut self, at: Instant) -> Result<(), Box<dyn Error>> {
+        if self.sessions.current.meta.event != Event::Cube3Blind {
+            return Err("memo is only split off in 3bld".into());
+        }
+        match self.timer.end_memo(at) {
+            true => Ok(()),
+            false => Err("no solve is being memorized".into()),
+        }
+    }
+
+    /// Record a typed time like `12.34`, `1:02.34`, `12.34+` or `DNF` against the scramble.
+    fn enter_time(&mut self, text: &str) -> Result<String, Box<dyn Error>> {
+        let (secs, penalty) = import::parse_time(text).ok_or_else(|| {
+            format!(
+                "not a time: {} (try 12.34, 1:02.34, 12.34+ or DNF)",
+                text.trim()
+            )
+        })?;
+        let time = self.timer.typed(secs, penalty);
+        let message = format!("added {}", self.time_format.single(&time));
+        self.add_time(time)?;
+        Ok(message)
+    }
+
+    /// The best multi-blind attempt that counts among the loaded solves.
+    pub fn best_multiblind(&self) -> Option<&Time> {
+        self.sessions
+            .current
             .times
             .times
             .iter()
-            .flat_map(|v| format!("{}\n", v.to_string()).bytes().collect::<Vec<u8>>())
-            .collect();
-        fs::write(&self.path, write_data)?;
-        Ok(())
+            .filter(|v| v.penalty != Penalty::Dnf && v.multiblind.is_some_and(|m| m.counts()))
+            .min_by_key(|v| v.multiblind.map(|m| m.code(v.value())))
+    }
+
+    /// Cubes in the multi-blind attempt under way, a scramble line each.
+    pub fn attempted(&self) -> usize {
+        self.scramble.lines().count()
+    }
+
+    /// Score the multi-blind attempt just timed from the cubes solved, typed as `8` or as
+    /// `8/10` when fewer were attempted than scrambled.
+    fn enter_multiblind(&mut self, text: &str) -> Result<String, Box<dyn Error>> {
+        let Some(mut time) = self.multiblind.take() else {
+            return Ok(String::new());
+        };
+        let attempted = self.attempted() as u32;
+        let count = match text.trim().split_once('/') {
+            Some((solved, of)) => solved.trim().parse().ok().zip(of.trim().parse().ok()),
+            None => text.trim().parse().ok().map(|v| (v, attempted)),
+        };
+        let result = match count {
+            Some((solved, attempted)) if solved <= attempted && attempted >= 2 => {
+                Multiblind { solved, attempted }
+            }
+            // Ask again rather than lose the attempt
+            _ => {
+                self.multiblind = Some(time);
+                self.open_input(InputMode::Multiblind);
+                return Ok(String::new());
+            }
+        };
+        if !result.counts() {
+            time.penalty = Penalty::Dnf;
+        }
+        time.multiblind = Some(result);
+        let message = match result.counts() {
+            true => format!("scored {}", self.time_format.single(&time)),
+            false => format!("{}/{} is a DNF", result.solved, result.attempted),
+        };
+        self.add_time(time)?;
+        Ok(message)
+    }
+
+    /// Start the hour of a fewest-moves attempt, or open the box for its solution once
+    /// it's under way.
+    pub fn fmc_press(&mut self) {
+        match &self.fmc {
+            Some(attempt) => {
+                self.input = Some(Input {
+                    mode: InputMode::Solution,
+                    text: attempt.draft.clone(),
+                });
+            }
+            None => {
+                self.fmc = Some(FmcAttempt {
+                    start: Instant::now(),
+                    prompted: false,
+                    draft: String::new(),
+                });
+                self.status = Some("the hour starts now".to_string());
+            }
+        }
+    }
+
+    /// Open the solution box when the hour runs out, if it isn't open already.
+    pub fn poll_fmc(&mut self) {
+        let Some(attempt) = &self.fmc else {
+            return;
+        };
+        if !attempt.prompted && attempt.start.elapsed() >= FMC_LIMIT && self.input.is_none() {
+            self.fmc_press();
+            if let Some(attempt) = self.fmc.as_mut() {
+                attempt.prompted = true;
+            }
+            self.status = Some("time's up".to_string());
+        }
+    }
+
+    /// Keep the metronome's beat while the timer runs, starting over for each solve.
+    pub fn poll_metronome(&mut self) -> Result<(), Box<dyn Error>> {
+        let Some(metronome) = self.metronome.as_mut() else {
+            return Ok(());
+        };
+        match self.timer.starttime.filter(|_| self.timer.on) {
+            Some(start) => metronome.poll(start.elapsed()),
+            None => {
+                metronome.reset();
+                Ok(())
+            }
+        }
+    }
+
+    /// Sound the WCA's warnings as inspection runs on. Any that come due together, say
+    /// after a device was slow to read, are let go bar the latest.
+    pub fn poll_inspection(&mut self) -> Result<(), Box<dyn Error>> {
+        let Some(warnings) = &self.inspection_warnings else {
+            return Ok(());
+        };
+        let Some(since) = self.timer.inspecting else {
+            self.warned = 0;
+            return Ok(());
+        };
+        let elapsed = since.elapsed();
+        // A command says each warning once, the bell rings it out
+        let rings = WARNINGS.iter().flat_map(|&(at, secs, rings)| {
+            let rings = if warnings.command.is_some() { 1 } else { rings };
+            (0..rings).map(move |i| (at + RING_GAP * i, secs))
+        });
+        let mut due = rings.filter(|(at, _)| elapsed >= *at).collect::<Vec<_>>();
+        if due.len() <= self.warned {
+            return Ok(());
+        }
+        self.warned = due.len();
+        let (_, secs) = due.pop().unwrap_or_default();
+        match &warnings.command {
+            Some(command) => sound::play(command, secs),
+            None => sound::bell(),
+        }
+    }
+
+    /// Whether the Timer panel is lit for a metronome beat.
+    pub fn beat_flash(&self) -> bool {
+        let Some(metronome) = &self.metronome else {
+            return false;
+        };
+        let start = self.timer.starttime.filter(|_| self.timer.on);
+        start.is_some_and(|v| metronome.flashing(v.elapsed()))
+    }
+
+    /// Record the fewest-moves attempt with its solution, which must solve the scramble,
+    /// or as a DNF.
+    fn enter_solution(&mut self, text: &str) -> Result<String, Box<dyn Error>> {
+        let Some(attempt) = self.fmc.as_mut() else {
+            return Ok(String::new());
+        };
+        attempt.draft = text.to_string();
+        let time = match text.trim().eq_ignore_ascii_case("dnf") {
+            true => Time {
+                penalty: Penalty::Dnf,
+                ..Time::from(0.0)
+            },
+            false => {
+                let (solution, count) = scramble::fewest_moves(&self.scramble, text)
+                    .map_err(|err| format!("{} (space to fix it, or type DNF)", err))?;
+                Time {
+                    solution,
+                    moves: Some(count as u32),
+                    ..Time::from(count as f32)
+                }
+            }
+        };
+        self.fmc = None;
+        let message = match time.moves {
+            Some(v) => format!("added {} moves", v),
+            None => "added a DNF".to_string(),
+        };
+        self.add_time(time)?;
+        Ok(message)
+    }
+
+    /// Put away the line being typed, keeping a solution's draft and dropping a multi-blind
+    /// attempt left uncounted.
+    pub fn cancel_input(&mut self) {
+        let input = self.input.take();
+        if let (Some(input), Some(attempt)) = (input, self.fmc.as_mut()) {
+            if input.mode == InputMode::Solution {
+                attempt.draft = input.text;
+            }
+        }
+        if self.multiblind.take().is_some() {
+            self.status = Some("dropped the multi-blind attempt".to_string());
+        }
+    }
+
+    /// Show or hide the time while a solve runs.
+    pub fn toggle_hide_timer(&mut self) -> String {
+        self.hide_timer = !self.hide_timer;
+        match self.hide_timer {
+            true => "the time is hidden until the solve stops".to_string(),
+            false => "the time shows while solving".to_string(),
+        }
+    }
+
+    /// Switch between timing solves with space and typing their times in.
+    pub fn toggle_typing(&mut self) -> Result<String, Box<dyn Error>> {
+        if self.timer.busy() {
+            return Err("finish the solve first".into());
+        }
+        self.typing = !self.typing;
+        Ok(match self.typing {
+            true => "space now opens a box to type the time in".to_string(),
+            false => "space now starts the timer".to_string(),
+        })
     }
 
     pub fn esc(&mut self) {
         match self.active_screen {
             Screen::Default => self.route.esc(),
-            Screen::Help => self.active_screen = Screen::Default,
+            Screen::Help | Screen::Restore | Screen::Sessions | Screen::Notes => {
+                self.active_screen = Screen::Default
+            }
+        }
+    }
+
+    pub fn open_restore(&mut self) -> Result<(), Box<dyn Error>> {
+        self.backup_list = self.sessions.current.backups.list()?;
+        if self.backup_list.is_empty() {
+            return Err("no backups yet".into());
+        }
+        self.backup_state.select(Some(0));
+        self.active_screen = Screen::Restore;
+        Ok(())
+    }
+
+    pub fn mv_backup(&mut self, dir: Dir) {
+        step(&mut self.backup_state, self.backup_list.len(), dir);
+    }
+
+    /// Replace the session with the selected backup, snapshotting the current state first.
+    pub fn restore_backup(&mut self) -> Result<Option<String>, Box<dyn Error>> {
+        self.active_screen = Screen::Default;
+        let path = match self
+            .backup_state
+            .selected()
+            .and_then(|i| self.backup_list.get(i))
+        {
+            Some(v) => v.clone(),
+            None => return Ok(None),
+        };
+        let count = self.sessions.current.restore(&path)?;
+        self.times_state.select(None);
+        let name = path.file_name().and_then(|v| v.to_str()).unwrap_or("");
+        Ok(Some(format!("restored {} solves from {}", count, name)))
+    }
+
+    /// List the sessions to switch between, with the current one selected.
+    pub fn open_sessions(&mut self) -> Result<(), Box<dyn Error>> {
+        if self.timer.busy() {
+            return Err("finish the solve first".into());
         }
+        let current = self.sessions.current.id.clone();
+        self.relist_sessions(&current)?;
+        self.active_screen = Screen::Sessions;
+        Ok(())
+    }
+
+    pub fn mv_session(&mut self, dir: Dir) {
+        step(&mut self.session_state, self.session_list.len(), dir);
+    }
+
+    /// The id of the session selected in the list.
+    fn selected_session(&self) -> Option<String> {
+        let i = self.session_state.selected()?;
+        self.session_list.get(i).map(|(id, _)| id.clone())
+    }
+
+    /// Switch to the session selected in the list.
+    pub fn switch_selected(&mut self) -> Result<Option<String>, Box<dyn Error>> {
+        self.active_screen = Screen::Default;
+        match self.selected_session() {
+            Some(id) => self.switch_session(&id, None).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// Give the session selected in the list the name `to`.
+    pub fn rename_session(&mut self, to: &str) -> Result<String, Box<dyn Error>> {
+        let id = self.selected_session().ok_or("no session to rename")?;
+        let current = id == self.sessions.current.id;
+        self.sessions.rename(&id, to)?;
+        // Filters set for it by name may differ
+        if current {
+            self.reset_scrambles();
+        }
+        self.relist_sessions(to)?;
+        Ok(format!("renamed {} to {}", id, to))
+    }
+
+    /// Delete the session selected in the list, once `deleting` has been confirmed.
+    pub fn delete_session(&mut self) -> Result<String, Box<dyn Error>> {
+        self.deleting = false;
+        let id = self.selected_session().ok_or("no session to delete")?;
+        self.sessions.delete(&id)?;
+        let current = self.sessions.current.id.clone();
+        self.relist_sessions(&current)?;
+        Ok(format!("deleted {}", id))
+    }
+
+    /// Archive the session selected in the list, or bring it back if it's archived.
+    pub fn archive_session(&mut self) -> Result<String, Box<dyn Error>> {
+        let id = self.selected_session().ok_or("no session to archive")?;
+        let mut listed = self.session_list.iter();
+        let archived = listed.any(|(v, meta)| *v == id && meta.archived);
+        self.sessions.set_archived(&id, !archived)?;
+        self.relist_sessions(&id)?;
+        Ok(match archived {
+            true => format!("brought {} back", id),
+            false => format!("archived {}; A shows archived sessions", id),
+        })
+    }
+
+    pub fn toggle_show_archived(&mut self) -> Result<String, Box<dyn Error>> {
+        self.show_archived = !self.show_archived;
+        let id = self.selected_session().unwrap_or_default();
+        self.relist_sessions(&id)?;
+        Ok(match self.show_archived {
+            true => "showing archived sessions".to_string(),
+            false => "hiding archived sessions".to_string(),
+        })
+    }
+
+    /// Write the tags of the session selected in the list, starting from those it has.
+    pub fn open_tags(&mut self) {
+        let i = self.session_state.selected();
+        let Some((_, meta)) = i.and_then(|i| self.session_list.get(i)) else {
+            return;
+        };
+        let text = meta.tags.join(", ");
+        self.open_input(InputMode::Tags);
+        if let Some(input) = self.input.as_mut() {
+            input.text = text;
+        }
+    }
+
+    /// Tag the session selected in the list with `text`, its tags split by commas.
+    pub fn tag_session(&mut self, text: &str) -> Result<String, Box<dyn Error>> {
+        let id = self.selected_session().ok_or("no session to tag")?;
+        let mut tags: Vec<String> = vec![];
+        for tag in text.split(',').map(str::trim).filter(|v| !v.is_empty()) {
+            if !tags.iter().any(|v| v == tag) {
+                tags.push(tag.to_string());
+            }
+        }
+        let message = match tags.is_empty() {
+            true => format!("{} has no tags now", id),
+            false => format!("tagged {} {}", id, tags.join(", ")),
+        };
+        self.sessions.set_tags(&id, tags)?;
+        self.relist_sessions(&id)?;
+        Ok(message)
+    }
+
+    /// List only the sessions tagged `tag`, or all of them again if it's empty.
+    pub fn filter_sessions(&mut self, tag: &str) -> Result<String, Box<dyn Error>> {
+        let tag = tag.trim();
+        self.session_tag = Some(tag.to_string()).filter(|v| !v.is_empty());
+        let id = self.selected_session().unwrap_or_default();
+        self.relist_sessions(&id)?;
+        Ok(match &self.session_tag {
+            Some(tag) => format!("{} sessions tagged {}", self.session_list.len(), tag),
+            None => "listing every session".to_string(),
+        })
+    }
+
+    /// Read the sessions in again, with session `id` selected.
+    fn relist_sessions(&mut self, id: &str) -> Result<(), Box<dyn Error>> {
+        let show = self.show_archived;
+        self.session_list = self.sessions.list()?;
+        // The current session stays listed, archived or not
+        let current = &self.sessions.current.id;
+        self.session_list
+            .retain(|(v, meta)| show || !meta.archived || v == current);
+        if let Some(tag) = &self.session_tag {
+            let tagged = |v: &String| v.eq_ignore_ascii_case(tag);
+            self.session_list
+                .retain(|(_, meta)| meta.tags.iter().any(tagged));
+        }
+        let at = self.session_list.iter().position(|(v, _)| v == id);
+        self.session_state.select(at.or(Some(0)));
+        Ok(())
+    }
+
+    /// Make session `id` the current one. If there's no such session yet it's started for
+    /// `puzzle`, an event or variation by name, or else for the current session's event.
+    pub fn switch_session(
+        &mut self,
+        id: &str,
+        puzzle: Option<&str>,
+    ) -> Result<String, Box<dyn Error>> {
+        if self.timer.busy() || self.fmc.is_some() || self.multiblind.is_some() {
+            return Err("finish the solve first".into());
+        }
+        if self.attempt.is_some() {
+            return Err("finish the attempt first, or `:attempt stop`".into());
+        }
+        let (event, variation) = match puzzle {
+            Some(name) => self::puzzle(name).ok_or_else(|| format!("unknown puzzle: {}", name))?,
+            None => (self.sessions.current.meta.event, Variation::Normal),
+        };
+        if id == self.sessions.current.id {
+            return Ok(format!("already in {}", id));
+        }
+        if puzzle.is_some() && self.sessions.ids()?.iter().any(|v| v == id) {
+            let why = format!("{} has been started already; `:event` sets its puzzle", id);
+            return Err(why.into());
+        }
+        let fresh = self.sessions.switch(id)?;
+        if fresh {
+            self.sessions.current.set_event(event)?;
+            if !variation.is_normal() {
+                self.sessions.current.set_variation(variation)?;
+            }
+        }
+        // What was shown of the last session's solves is no longer of this one
+        self.times_state.select(None);
+        self.comparison = None;
+        self.records = None;
+        if let Tool::Compare | Tool::Records = self.tool {
+            self.tool = Tool::Welcome;
+        }
+        // Its puzzle, and filters set for it by name, may differ
+        self.reset_scrambles();
+        let session = &self.sessions.current;
+        Ok(match fresh {
+            true => format!("started {} for {}", id, puzzle_name(&session.meta)),
+            false => format!(
+                "switched to {} ({}, {} solves)",
+                id, session.meta.event, session.times.count
+            ),
+        })
+    }
+
+    /// Write the session's notes, starting from what's there.
+    pub fn open_notes(&mut self) -> Result<(), Box<dyn Error>> {
+        if self.timer.busy() {
+            return Err("finish the solve first".into());
+        }
+        self.notes = self.sessions.current.meta.notes.clone();
+        self.active_screen = Screen::Notes;
+        Ok(())
+    }
+
+    /// Keep the notes as written.
+    pub fn save_notes(&mut self) -> Result<String, Box<dyn Error>> {
+        self.active_screen = Screen::Default;
+        let notes = self.notes.trim_end().to_string();
+        if notes == self.sessions.current.meta.notes {
+            return Ok("notes unchanged".to_string());
+        }
+        self.sessions.current.set_notes(notes)?;
+        Ok("notes saved".to_string())
+    }
+
+    /// Switch to today's session of the current event, starting it if it's the first of
+    /// the day, when daily sessions are on. Mid-solve it waits to be asked again.
+    pub fn start_daily(&mut self) -> Result<Option<String>, Box<dyn Error>> {
+        let today = chrono::Local::now().date_naive();
+        if !self.daily_sessions || self.daily == Some(today) {
+            return Ok(None);
+        }
+        let busy = self.timer.busy() || self.fmc.is_some() || self.multiblind.is_some();
+        if busy || self.attempt.is_some() || self.input.is_some() {
+            return Ok(None);
+        }
+        self.daily = Some(today);
+        let meta = &self.sessions.current.meta;
+        let (event, variation) = (meta.event, meta.variation);
+        let id = match variation {
+            Variation::Normal => format!("{}-{}", event.as_str(), today),
+            v => format!("{}-{}-{}", event.as_str(), v.as_str(), today),
+        };
+        if id == self.sessions.current.id {
+            return Ok(None);
+        }
+        let fresh = !self.sessions.ids()?.contains(&id);
+        // Started for the current event, which is what it's for
+        self.switch_session(&id, None)?;
+        if fresh {
+            if !variation.is_normal() {
+                self.sessions.current.set_variation(variation)?;
+            }
+            self.sessions.set_tags(&id, vec!["daily".to_string()])?;
+        }
+        Ok(Some(format!("today's session: {}", id)))
     }
 
     pub fn help(&mut self) {
         self.active_screen = Screen::Help;
     }
 
+    pub fn open_input(&mut self, mode: InputMode) {
+        self.input = Some(Input {
+            mode,
+            text: String::new(),
+        });
+    }
+
+    pub fn submit_input(&mut self) {
+        let input = match self.input.take() {
+            Some(v) => v,
+            None => return,
+        };
+        let res = match input.mode {
+            InputMode::Command => command::run(self, &input.text),
+            InputMode::Scramble => self
+                .set_scramble(&input.text)
+                .map(|_| Some("using the entered scramble".to_string())),
+            InputMode::Time => self.enter_time(&input.text).map(Some),
+            InputMode::Multiblind => self.enter_multiblind(&input.text).map(Some),
+            InputMode::Solution => self.enter_solution(&input.text).map(Some),
+            InputMode::Session => match input.text.split_whitespace().collect::<Vec<_>>()[..] {
+                [id] => self.switch_session(id, None).map(Some),
+                [id, puzzle] => self.switch_session(id, Some(puzzle)).map(Some),
+                _ => Err("a session is a name, then its puzzle if not this one's".into()),
+            },
+            InputMode::Rename => self.rename_session(input.text.trim()).map(Some),
+            InputMode::Tags => self.tag_session(&input.text).map(Some),
+            InputMode::TagFilter => self.filter_sessions(&input.text).map(Some),
+        };
+        self.status = match res {
+            Ok(msg) => msg,
+            Err(err) => Some(format!("error: {}", err)),
+        };
+    }
+
     pub fn get_border_style_from_id(&self, id: ActiveBlock) -> Style {
         let style = Style::default();
 
         if id == self.route.active_block {
-            return style.fg(Color::LightGreen).add_modifier(Modifier::BOLD);
+            style.fg(Color::LightGreen).add_modifier(Modifier::BOLD)
         } else if id == self.route.selected_block {
-            return style.fg(Color::LightBlue).add_modifier(Modifier::BOLD);
+            style.fg(Color::LightBlue).add_modifier(Modifier::BOLD)
         } else {
-            return style.fg(Color::Gray);
+            style.fg(Color::Gray)
         }
     }
 
@@ -346,22 +2050,73 @@ impl<'a> App<'a> {
         let style = Style::default().add_modifier(Modifier::BOLD);
 
         if id == self.route.active_block {
-            return style.fg(Color::LightGreen);
+            style.fg(Color::LightGreen)
         } else if id == self.route.selected_block {
-            return style.fg(Color::LightBlue);
+            style.fg(Color::LightBlue)
+        } else {
+            style.fg(Color::White)
+        }
+    }
+
+    /// Add or take back a +2 or DNF on the latest solve, replacing any other penalty.
+    pub fn toggle_penalty(&mut self, penalty: Penalty) -> Result<String, Box<dyn Error>> {
+        if self.timer.busy() {
+            return Err("finish the solve first".into());
+        }
+        let times = &self.sessions.current.times.times;
+        let last = times.last().ok_or("no solves yet")?;
+        let message = match last.penalty == penalty {
+            true => format!("took the {} off the last solve", penalty.as_str()),
+            false => format!("{} on the last solve", penalty.as_str()),
+        };
+        let penalty = if last.penalty == penalty {
+            Penalty::None
         } else {
-            return style.fg(Color::White);
+            penalty
+        };
+        // The timer only shows the penalty if it's showing this solve
+        let timed = last.date.is_some() && self.timer.lastdate == last.date;
+        self.sessions.current.set_last_penalty(penalty)?;
+        if timed {
+            self.timer.lastpenalty = penalty;
+        }
+        // The solve may be in an attempt, under way or just finished by it
+        if let Some(last) = self.sessions.current.times.times.last().cloned() {
+            let under_way = self.attempt.as_mut().and_then(|v| v.solves.last_mut());
+            match under_way {
+                Some(solve) if attempt::same(solve, &last) => *solve = last,
+                _ => _ = self.sessions.current.rescore_attempt(&last)?,
+            }
         }
+        Ok(message)
     }
 
-    pub fn del(&mut self) {
+    /// Tag the solve selected in the Times block, or else the latest, as done `variation`.
+    pub fn tag_solve(&mut self, variation: Variation) -> Result<String, Box<dyn Error>> {
+        if self.timer.busy() {
+            return Err("finish the solve first".into());
+        }
+        let loaded = self.sessions.current.times.times.len();
+        let selected = self
+            .times_state
+            .selected()
+            .filter(|_| self.route.active_block == ActiveBlock::Times);
+        let (index, which) = match selected {
+            Some(i) => (loaded.saturating_sub(i + 1), "the selected solve"),
+            None => (loaded.saturating_sub(1), "the last solve"),
+        };
+        self.sessions.current.tag(index, variation)?;
+        Ok(format!("{} is {} now", which, variation))
+    }
+
+    pub fn del(&mut self) -> Result<(), Box<dyn Error>> {
         match self.route.active_block {
             ActiveBlock::Times => self.del_time(),
-            _ => (),
+            _ => Ok(()),
         }
     }
 
-    pub fn mv(&mut self, dir: Dir) {
+    pub fn mv(&mut self, dir: Dir) -> Result<(), Box<dyn Error>> {
         match self.route.active_block {
             ActiveBlock::Home => {
                 match dir {
@@ -374,15 +2129,16 @@ impl<'a> App<'a> {
             }
             ActiveBlock::Times => match dir {
                 Dir::Up => self.previous_time(),
-                Dir::Down => self.next_time(),
+                Dir::Down => self.next_time()?,
                 _ => (),
             },
             _ => (),
         }
+        Ok(())
     }
 
     fn mv_up(&mut self) {
-        if (self.pos.1) as i32 - 1 >= 0 {
+        if self.pos.1 > 0 {
             self.pos.1 -= 1;
         }
     }
@@ -404,20 +2160,24 @@ impl<'a> App<'a> {
     }
 
     pub fn mv_left(&mut self) {
-        if (self.pos.0) as i32 - 1 >= 0 {
+        if self.pos.0 > 0 {
             self.pos.0 -= 1;
         }
     }
 
-    pub fn next_time(&mut self) {
-        let len = self.times.times.len();
+    pub fn next_time(&mut self) -> Result<(), Box<dyn Error>> {
+        let len = self.sessions.current.times.times.len();
         if len == 0 {
-            return;
+            return Ok(());
         }
         let i = match self.times_state.selected() {
             Some(i) => {
-                if i >= self.times.times.len() - 1 {
-                    0
+                if i >= len - 1 {
+                    // Rows are newest first, so older solves are added below
+                    match self.sessions.current.load_older()? {
+                        true => i + 1,
+                        false => 0,
+                    }
                 } else {
                     i + 1
                 }
@@ -425,17 +2185,18 @@ impl<'a> App<'a> {
             None => 0,
         };
         self.times_state.select(Some(i));
+        Ok(())
     }
 
     fn previous_time(&mut self) {
-        let len = self.times.times.len();
+        let len = self.sessions.current.times.times.len();
         if len == 0 {
             return;
         }
         let i = match self.times_state.selected() {
             Some(i) => {
                 if i == 0 {
-                    self.times.times.len() - 1
+                    self.sessions.current.times.times.len() - 1
                 } else {
                     i - 1
                 }
@@ -445,29 +2206,266 @@ impl<'a> App<'a> {
         self.times_state.select(Some(i));
     }
 
-    fn del_time(&mut self) {
-        match self.times_state.selected() {
-            Some(v) => {
-                // Edge cases (literally)
-                let len = self.times.times.len();
-                if len <= 0 || v >= len {
-                    return;
-                }
-                self.times.times.remove(len - v - 1);
-                // Go up one if selection fell off
-                if v == self.times.times.len() {
-                    self.previous_time();
-                }
-            },
-            None => (),
+    fn del_time(&mut self) -> Result<(), Box<dyn Error>> {
+        let v = match self.times_state.selected() {
+            Some(v) => v,
+            None => return Ok(()),
         };
+        // Edge cases (literally)
+        let len = self.sessions.current.times.times.len();
+        if len == 0 || v >= len {
+            return Ok(());
+        }
+        // A solve taken back no longer counts towards the attempt
+        let removed = &self.sessions.current.times.times[len - v - 1];
+        if let Some(attempt) = self.attempt.as_mut() {
+            attempt.solves.retain(|t| !attempt::same(t, removed));
+        }
+        self.sessions.current.remove(len - v - 1)?;
+        // Go up one if selection fell off
+        if v == len - 1 {
+            self.previous_time();
+        }
+        Ok(())
     }
 
+    /// The solve selected in the Times block.
+    pub fn selected_solve(&self) -> Option<&Time> {
+        let times = &self.sessions.current.times.times;
+        let i = self.times_state.selected()?;
+        times.len().checked_sub(i + 1).map(|v| &times[v])
+    }
+
+    /// What the config calls the phase at `i`, counting from 0.
+    pub fn phase_name(&self, i: usize) -> String {
+        match self.phases.get(i) {
+            Some(v) => v.clone(),
+            None => format!("phase {}", i + 1),
+        }
+    }
+
+    /// The current session's settings from the config.
+    fn session_config(&self) -> SessionConfig {
+        let id = &self.sessions.current.id;
+        self.session_config.get(id).cloned().unwrap_or_default()
+    }
+
+    /// Fill in whichever scrambles have arrived from the worker, oldest request first.
+    pub fn receive_scrambles(&mut self) {
+        while let Some(scramble) = self.scrambler.try_recv() {
+            if self.scramble.is_empty() {
+                self.scramble = scramble;
+            } else if self.next_scramble.is_empty() {
+                self.next_scramble = scramble;
+            }
+            // Otherwise it was replaced before it arrived
+        }
+    }
+
+    /// Whether a scramble on show is still being generated.
+    pub fn generating(&self) -> bool {
+        self.scramble.is_empty() || self.next_scramble.is_empty()
+    }
+
+    /// The settings scrambles for the current event are generated with.
+    pub fn scramble_settings(&self) -> Settings {
+        let event = self.sessions.current.meta.event;
+        let settings = self.scramble_settings.get(&event).cloned();
+        settings.unwrap_or_default()
+    }
+
+    /// Practice a last layer subset of the 3x3, or go back to full scrambles with `None`.
+    pub fn set_subset(&mut self, subset: Option<Subset>) -> Result<(), Box<dyn Error>> {
+        if self.timer.busy() {
+            return Err("finish the solve first".into());
+        }
+        let event = self.sessions.current.meta.event;
+        let mut settings = self.scramble_settings();
+        settings.subset = subset;
+        settings.check(event)?;
+        self.scramble_settings.insert(event, settings);
+        self.reset_scrambles();
+        Ok(())
+    }
+
+    /// Draw scrambles from `seed` from its first one, or at random again with `None`.
+    pub fn set_seed(&mut self, seed: Option<&str>) -> Result<(), Box<dyn Error>> {
+        if self.timer.busy() {
+            return Err("finish the solve first".into());
+        }
+        self.seed = seed.map(String::from);
+        self.reset_scrambles();
+        Ok(())
+    }
+
+    /// Move the preview up to be the current scramble and generate the one after it.
     pub fn new_scramble(&mut self) {
-        self.scramble = gen_scramble();
+        let next = std::mem::take(&mut self.next_scramble);
+        let done = std::mem::replace(&mut self.scramble, next);
+        self.scrambler.request();
+        self.remember(done);
+    }
+
+    fn remember(&mut self, scramble: String) {
+        if scramble.is_empty() {
+            return;
+        }
+        if self.scramble_history.len() >= SCRAMBLE_HISTORY {
+            self.scramble_history.remove(0);
+        }
+        self.scramble_history.push(scramble);
+    }
+
+    /// Use a scramble typed in for the next solve instead of the generated one.
+    pub fn set_scramble(&mut self, text: &str) -> Result<(), Box<dyn Error>> {
+        if self.timer.busy() {
+            return Err("finish the solve first".into());
+        }
+        let scramble = scramble::parse(self.sessions.current.meta.event, text)?;
+        let replaced = std::mem::replace(&mut self.scramble, scramble);
+        self.remember(replaced);
+        Ok(())
+    }
+
+    /// Copy the current scramble, or the last solve's with `last`, to the clipboard.
+    pub fn copy_scramble(&self, last: bool) -> Result<String, Box<dyn Error>> {
+        let (what, scramble) = match last {
+            false => ("scramble", &self.scramble),
+            true => {
+                let time = self.sessions.current.times.times.last();
+                let time = time.ok_or("no solves yet")?;
+                ("last solve's scramble", &time.scramble)
+            }
+        };
+        if scramble.is_empty() {
+            return Err(format!("the {} isn't ready yet", what).into());
+        }
+        let via = clipboard::copy(scramble)?;
+        Ok(format!("copied the {} via {}", what, via))
+    }
+
+    /// Copy the session's best `name` average, `ao5` or the like, to the clipboard as it's
+    /// posted on forums.
+    pub fn copy_best(&mut self, name: &str) -> Result<String, Box<dyn Error>> {
+        type Stat = fn(&Time) -> Option<OrderedFloat<f32>>;
+        let (n, stat): (usize, Stat) = match name {
+            "mo3" => (3, |v| v.mo3),
+            "ao5" => (5, |v| v.ao5),
+            "ao12" => (12, |v| v.ao12),
+            "ao50" => (50, |v| v.ao50),
+            "ao100" => (100, |v| v.ao100),
+            _ => return Err(format!("can't copy an average of {}", name).into()),
+        };
+        // Averages are over the solves of one variation, so the others would get in between
+        let variation = self.sessions.current.meta.variation;
+        let mut times = self.sessions.current.all()?;
+        times.retain(|v| v.variation == variation);
+        let (value, end) = times
+            .iter()
+            .enumerate()
+            .filter_map(|(i, v)| Some((stat(v).filter(|v| v.is_finite())?, i)))
+            .min()
+            .ok_or_else(|| format!("no {} yet", name))?;
+        // A mean of 3 has nothing trimmed
+        let trim = match n {
+            3 => 0,
+            n => trim(n),
+        };
+        let start = end
+            .checked_sub(n - 1)
+            .ok_or_else(|| format!("the best {} is missing some of its {} solves", name, n))?;
+        let set = &times[start..=end];
+        let text = export::forum::average(name, value, set, trim, &self.time_format);
+        let via = clipboard::copy(&text)?;
+        Ok(format!("copied the best {} via {}", name, via))
+    }
+
+    /// Skip to the next scramble without doing a solve.
+    pub fn skip_scramble(&mut self) -> Result<(), Box<dyn Error>> {
+        if self.timer.busy() {
+            return Err("finish the solve first".into());
+        }
+        self.new_scramble();
+        Ok(())
+    }
+
+    /// Bring back the scramble before the current one, which comes up next after it.
+    pub fn previous_scramble(&mut self) -> Result<(), Box<dyn Error>> {
+        if self.timer.busy() {
+            return Err("finish the solve first".into());
+        }
+        let previous = self.scramble_history.pop().ok_or("no earlier scramble")?;
+        self.next_scramble = std::mem::replace(&mut self.scramble, previous);
+        Ok(())
+    }
+
+    /// Generate both the current scramble and the preview afresh for the session's event.
+    fn reset_scrambles(&mut self) {
+        // Keep every event's scrambles ready, as this session would generate them
+        let (filter, settings) = (self.session_config().filter, &self.scramble_settings);
+        let jobs = Event::ALL.iter().map(|&event| Job {
+            event,
+            settings: settings.get(&event).cloned().unwrap_or_default(),
+            filter: filter.clone(),
+            seed: None,
+        });
+        self.scrambler.prefetch(jobs.collect());
+        // A seed starts over, so everyone racing on it gets the same scrambles for the event
+        self.scrambler.start(Job {
+            event: self.sessions.current.meta.event,
+            settings: self.scramble_settings(),
+            filter,
+            seed: self.seed.clone(),
+        });
+        self.scramble.clear();
+        self.next_scramble.clear();
+        self.scrambler.request();
+        self.scrambler.request();
+        // Scrambles for another puzzle are no use to go back to
+        self.scramble_history.clear();
+    }
+
+    pub fn on_tick(&mut self) {
+        // A new day's session, for a run going past midnight
+        match self.start_daily() {
+            Ok(Some(message)) => self.status = Some(message),
+            Ok(None) => (),
+            Err(err) => self.status = Some(format!("error: daily session: {}", err)),
+        }
+        let every = match self.auto_export.as_ref().and_then(|v| v.every) {
+            Some(v) => Duration::from_secs(v),
+            None => return,
+        };
+        // Hold off mid-solve so the timer never stutters
+        if self.timer.busy() || self.last_export.elapsed() < every {
+            return;
+        }
+        if let Err(err) = self.auto_export() {
+            self.status = Some(format!("error: auto export: {}", err));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DNF: f32 = f32::INFINITY;
+
+    #[test]
+    fn trimmed_mean_trims_one_dnf_and_not_two() {
+        assert_eq!(trimmed_mean(&[11.0, DNF, 10.0, 12.0, 13.0]).0, 12.0);
+        assert!(trimmed_mean(&[11.0, DNF, 10.0, DNF, 13.0]).is_infinite());
+        let mut hundred = vec![10.0; 95];
+        hundred.extend([DNF; 5]);
+        assert_eq!(trimmed_mean(&hundred).0, 10.0);
+        hundred[0] = DNF;
+        assert!(trimmed_mean(&hundred).is_infinite());
     }
 
-    pub fn on_tick(&self) {
-        ()
+    #[test]
+    fn mean_is_a_dnf_with_any_dnf() {
+        assert_eq!(mean(&[10.0, 11.0, 12.0]).0, 11.0);
+        assert!(mean(&[10.0, DNF, 12.0]).is_infinite());
     }
 }
@@ -0,0 +1,123 @@
+//! Fixed-length attempts: a mean of 3 or an average of 5 done in one go, as at a
+//! competition, and kept as a result of its own with the solves in it.
+
+use super::app::{Time, Times};
+use super::scramble::Event;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum Format {
+    #[serde(rename = "mo3")]
+    Mo3,
+    #[serde(rename = "ao5")]
+    Ao5,
+}
+
+impl Format {
+    pub const ALL: [Format; 2] = [Format::Mo3, Format::Ao5];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Format::Mo3 => "mo3",
+            Format::Ao5 => "ao5",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Format> {
+        Self::ALL.into_iter().find(|v| v.as_str() == name)
+    }
+
+    /// The format the WCA holds `event` in: a mean of 3 for the slow events, an average
+    /// of 5 for the rest.
+    pub fn of(event: Event) -> Format {
+        match event {
+            Event::Cube6 | Event::Cube7 | Event::Cube3Blind | Event::Fmc => Format::Mo3,
+            _ => Format::Ao5,
+        }
+    }
+
+    pub fn solves(&self) -> usize {
+        match self {
+            Format::Mo3 => 3,
+            Format::Ao5 => 5,
+        }
+    }
+
+    /// The result of `times`, infinite when DNFs sank it. A mean has nothing trimmed, so
+    /// any DNF does.
+    pub fn result(&self, times: &[Time]) -> f32 {
+        match self {
+            Format::Mo3 => Times::calc_mean(times).0,
+            Format::Ao5 => Times::calc_aon(times).0,
+        }
+    }
+}
+
+impl fmt::Display for Format {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// An attempt under way.
+pub struct Attempt {
+    pub format: Format,
+    /// The solves done so far, oldest first
+    pub solves: Vec<Time>,
+}
+
+impl Attempt {
+    pub fn new(format: Format) -> Self {
+        Self {
+            format,
+            solves: vec![],
+        }
+    }
+
+    pub fn done(&self) -> bool {
+        self.solves.len() >= self.format.solves()
+    }
+}
+
+/// A finished attempt, kept in the session's `attempts.json`.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Record {
+    pub format: Format,
+    pub event: Event,
+    /// The mean or average, or none for a DNF
+    pub result: Option<f32>,
+    /// Unix timestamp of when the last solve finished
+    pub date: i64,
+    /// The solves with their scrambles, oldest first
+    pub solves: Vec<Time>,
+}
+
+impl Record {
+    pub fn new(format: Format, event: Event, solves: Vec<Time>) -> Self {
+        let mut record = Self {
+            format,
+            event,
+            result: None,
+            date: chrono::Utc::now().timestamp(),
+            solves,
+        };
+        record.score();
+        record
+    }
+
+    /// Work the result out again from the solves, after one of their penalties changed.
+    pub fn score(&mut self) {
+        self.result = Some(self.format.result(&self.solves)).filter(|v| v.is_finite());
+    }
+
+    /// The result as it ranks, a DNF being the slowest.
+    pub fn value(&self) -> f32 {
+        self.result.unwrap_or(f32::INFINITY)
+    }
+}
+
+/// Whether `a` and `b` are the same solve, going by when it finished and its time.
+pub fn same(a: &Time, b: &Time) -> bool {
+    a.date == b.date && a.time.to_bits() == b.time.to_bits()
+}
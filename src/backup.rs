@@ -0,0 +1,146 @@
+use chrono::Local;
+use std::{
+    error::Error,
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// How many snapshots are kept before the oldest is removed.
+const KEEP: usize = 10;
+/// Solves between automatic snapshots.
+const EVERY: usize = 25;
+
+/// Timestamped copies of the data file in a `backups` directory beside it.
+pub struct Backups {
+    source: PathBuf,
+    dir: PathBuf,
+    solves: usize,
+}
+
+impl Backups {
+    pub fn new(source: &Path) -> Self {
+        let dir = source
+            .parent()
+            .map(|v| v.join("backups"))
+            .unwrap_or_else(|| PathBuf::from("backups"));
+        Self {
+            source: source.to_path_buf(),
+            dir,
+            solves: 0,
+        }
+    }
+
+    /// Copy the data file into the backups directory, dropping old snapshots, and return
+    /// the copy. Nothing is copied when the newest snapshot is one of it already, so
+    /// opening a session again and again doesn't push the real backups out.
+    pub fn snapshot(&self) -> Result<Option<PathBuf>, Box<dyn Error>> {
+        if !self.source.exists() {
+            return Ok(None);
+        }
+        if let Some(newest) = self.list()?.first() {
+            if same_contents(&self.source, newest)? {
+                return Ok(None);
+            }
+        }
+        fs::create_dir_all(&self.dir)?;
+        let stem = self
+            .source
+            .file_stem()
+            .and_then(|v| v.to_str())
+            .unwrap_or("times");
+        let base = format!("{}-{}", stem, Local::now().format("%Y%m%d-%H%M%S"));
+        let ext = self.source.extension().and_then(|v| v.to_str());
+        // Another snapshot this second gets a count, which sorts after the plain name
+        let dest = (1..)
+            .map(|n| match n {
+                1 => base.clone(),
+                n => format!("{}_{}", base, n),
+            })
+            .map(|v| self.dir.join(ext.map_or(v.clone(), |ext| v + "." + ext)))
+            .find(|v| !v.exists())
+            .unwrap_or_default();
+        fs::copy(&self.source, &dest)?;
+
+        for old in self.list()?.into_iter().skip(KEEP) {
+            fs::remove_file(old)?;
+        }
+        Ok(Some(dest))
+    }
+
+    /// Count a finished solve, returning whether a snapshot is due.
+    pub fn solved(&mut self) -> bool {
+        self.solves += 1;
+        self.solves.is_multiple_of(EVERY)
+    }
+
+    /// Snapshots of this data file, newest first.
+    pub fn list(&self) -> Result<Vec<PathBuf>, Box<dyn Error>> {
+        if !self.dir.exists() {
+            return Ok(vec![]);
+        }
+        let stem = self
+            .source
+            .file_stem()
+            .and_then(|v| v.to_str())
+            .unwrap_or("times")
+            .to_string()
+            + "-";
+        let mut backups: Vec<PathBuf> = fs::read_dir(&self.dir)?
+            .filter_map(|v| v.ok())
+            .map(|v| v.path())
+            .filter(|v| v.extension() == self.source.extension())
+            .filter(|v| {
+                v.file_name()
+                    .and_then(|v| v.to_str())
+                    .is_some_and(|v| v.starts_with(&stem))
+            })
+            .collect();
+        // The timestamp in the name sorts chronologically
+        backups.sort();
+        backups.reverse();
+        Ok(backups)
+    }
+}
+
+fn same_contents(a: &Path, b: &Path) -> Result<bool, Box<dyn Error>> {
+    if fs::metadata(a)?.len() != fs::metadata(b)?.len() {
+        return Ok(false);
+    }
+    Ok(fs::read(a)? == fs::read(b)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn snapshot_skips_an_unchanged_file() {
+        let dir = std::env::temp_dir().join(format!("cube-tui-backup-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let source = dir.join("times.json");
+        fs::write(&source, "[]").unwrap();
+        let backups = Backups::new(&source);
+        assert!(backups.snapshot().unwrap().is_some());
+        assert!(backups.snapshot().unwrap().is_none());
+        fs::write(&source, "[1.5]").unwrap();
+        assert!(backups.snapshot().unwrap().is_some());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn snapshots_in_the_same_second_both_stay() {
+        let dir = std::env::temp_dir().join(format!("cube-tui-backups-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let source = dir.join("times.json");
+        fs::write(&source, "[]").unwrap();
+        let backups = Backups::new(&source);
+        let first = backups.snapshot().unwrap().unwrap();
+        fs::write(&source, "[1.5]").unwrap();
+        let second = backups.snapshot().unwrap().unwrap();
+        assert_ne!(first, second);
+        assert_eq!(fs::read_to_string(&first).unwrap(), "[]");
+        assert_eq!(fs::read_to_string(&second).unwrap(), "[1.5]");
+        assert_eq!(backups.list().unwrap()[0], second);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,50 @@
+use base64::{engine::general_purpose::STANDARD, Engine};
+use std::{
+    error::Error,
+    io::{self, Write},
+    process::{Command, Stdio},
+};
+
+/// Clipboard tools tried in turn, each taking the text on stdin: Wayland, X11 twice,
+/// macOS and WSL.
+const TOOLS: [(&str, &[&str]); 5] = [
+    ("wl-copy", &[]),
+    ("xclip", &["-selection", "clipboard"]),
+    ("xsel", &["--clipboard", "--input"]),
+    ("pbcopy", &[]),
+    ("clip.exe", &[]),
+];
+
+fn run(tool: &str, args: &[&str], text: &str) -> Result<(), Box<dyn Error>> {
+    let mut child = Command::new(tool)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()?;
+    child
+        .stdin
+        .take()
+        .ok_or("no stdin")?
+        .write_all(text.as_bytes())?;
+    match child.wait()?.success() {
+        true => Ok(()),
+        false => Err(format!("{} failed", tool).into()),
+    }
+}
+
+/// Put `text` on the system clipboard, returning how it got there.
+///
+/// Without a clipboard tool (say, over SSH) the text is sent to the terminal as an OSC 52
+/// sequence instead, which most terminals copy to the clipboard of the machine they run on.
+pub fn copy(text: &str) -> Result<&'static str, Box<dyn Error>> {
+    for (tool, args) in TOOLS {
+        if run(tool, args, text).is_ok() {
+            return Ok(tool);
+        }
+    }
+    let mut stdout = io::stdout();
+    write!(stdout, "\x1b]52;c;{}\x07", STANDARD.encode(text))?;
+    stdout.flush()?;
+    Ok("the terminal")
+}
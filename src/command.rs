@@ -0,0 +1,242 @@
+use super::{
+    app::{App, Penalty, Variation},
+    attempt::Format,
+    config::expand,
+    drill, export, import,
+    scramble::{Event, Subset},
+    session::Goal,
+};
+use std::{error::Error, time::Duration};
+
+/// Times `:sync` merges again after losing a race with another upload.
+#[cfg(feature = "sync")]
+const SYNC_TRIES: usize = 3;
+
+/// Run a `:` command line, returning the message to show in the status line.
+pub fn run(app: &mut App, line: &str) -> Result<Option<String>, Box<dyn Error>> {
+    let args: Vec<&str> = line.split_whitespace().collect();
+    match args.as_slice() {
+        [] => Ok(None),
+        ["export", format, path] => {
+            let path = expand(path);
+            let count = export::write(format, &path, &mut app.sessions.current)?;
+            Ok(Some(format!(
+                "exported {} solves to {}",
+                count,
+                path.display()
+            )))
+        }
+        ["export", ..] => Err("usage: export <csv|cstimer|cubedesk|json|markdown> <path>".into()),
+        #[cfg(feature = "sync")]
+        ["sync"] => {
+            idle(app)?;
+            let session = &mut app.sessions.current;
+            let remote = crate::sync::Remote::from_env(&session.id)?;
+            let cipher = session.cipher.clone();
+            // An upload from elsewhere between fetching and uploading means merging again
+            for _ in 0..SYNC_TRIES {
+                let mut pulled = remote.pull(cipher.as_ref())?;
+                let incoming = std::mem::take(&mut pulled.times);
+                let incoming = crate::sync::missing(&session.all()?, incoming);
+                let added = session.sync_in(incoming, &pulled.deleted)?;
+                let (times, deleted) = (session.all()?, session.deleted()?);
+                if remote.push(&times, &deleted, &pulled, cipher.as_ref())? {
+                    return Ok(Some(format!(
+                        "synced {} solves, {} new from remote",
+                        session.times.count, added
+                    )));
+                }
+            }
+            Err("the remote copy kept changing; sync again".into())
+        }
+        ["event", name] => {
+            let event = Event::parse(name).ok_or_else(|| format!("unknown event: {}", name))?;
+            app.set_event(event)?;
+            Ok(Some(format!("now scrambling {}", event)))
+        }
+        ["event", ..] => {
+            let names: Vec<&str> = Event::ALL.iter().map(|v| v.as_str()).collect();
+            Err(format!("usage: event <{}>", names.join("|")).into())
+        }
+        ["seed"] => {
+            app.set_seed(None)?;
+            Ok(Some("scrambling at random".to_string()))
+        }
+        ["seed", ..] => {
+            let seed = line.trim_start().trim_start_matches("seed").trim();
+            app.set_seed(Some(seed))?;
+            Ok(Some(format!("scrambling from seed \"{}\"", seed)))
+        }
+        ["subset"] => {
+            app.set_subset(None)?;
+            Ok(Some("scrambling the whole cube".to_string()))
+        }
+        ["subset", name] => {
+            let subset = Subset::parse(name).ok_or_else(|| format!("unknown subset: {}", name))?;
+            app.set_subset(Some(subset))?;
+            Ok(Some(format!("scrambling {} cases", subset)))
+        }
+        ["subset", ..] => Err("usage: subset [ll|oll|pll]".into()),
+        ["target"] => {
+            app.sessions.current.set_target(None)?;
+            Ok(Some("no target for this session".to_string()))
+        }
+        ["target", time] => {
+            let secs = match import::parse_time(time) {
+                Some((secs, Penalty::None)) if secs > 0.0 => secs,
+                _ => return Err(format!("not a target time: {}", time).into()),
+            };
+            app.sessions.current.set_target(Some(secs))?;
+            Ok(Some(format!("aiming for sub-{}", secs)))
+        }
+        ["target", ..] => Err("usage: target [time]".into()),
+        ["goal", "clear"] => {
+            app.sessions.current.clear_goals()?;
+            Ok(Some("no goals for this session".to_string()))
+        }
+        ["goal", time, percent] => {
+            let secs = match import::parse_time(time) {
+                Some((secs, Penalty::None)) if secs > 0.0 => secs,
+                _ => return Err(format!("not a goal time: {}", time).into()),
+            };
+            let percent = match percent.trim_end_matches('%').parse::<f32>() {
+                Ok(v) if v > 0.0 && v <= 100.0 => v,
+                _ => return Err(format!("not a percentage: {}", percent).into()),
+            };
+            let message = format!("aiming for {}% of solves sub-{}", percent, secs);
+            let goal = Goal {
+                time: secs,
+                percent,
+            };
+            app.sessions.current.set_goal(goal)?;
+            Ok(Some(message))
+        }
+        ["goal", ..] => Err("usage: goal <time> <percent> | goal clear".into()),
+        ["variation", name] => {
+            let variation =
+                Variation::parse(name).ok_or_else(|| format!("unknown variation: {}", name))?;
+            app.sessions.current.set_variation(variation)?;
+            Ok(Some(format!("solving {} from now on", variation)))
+        }
+        ["tag", name] => {
+            let variation =
+                Variation::parse(name).ok_or_else(|| format!("unknown variation: {}", name))?;
+            app.tag_solve(variation).map(Some)
+        }
+        [cmd @ ("variation" | "tag"), ..] => {
+            let names: Vec<&str> = Variation::ALL.iter().map(|v| v.as_str()).collect();
+            Err(format!("usage: {} <{}>", cmd, names.join("|")).into())
+        }
+        ["attempt"] => app.start_attempt(None).map(Some),
+        ["attempt", "stop"] => app.stop_attempt().map(Some),
+        ["attempt", name] => {
+            let format =
+                Format::parse(name).ok_or_else(|| format!("unknown attempt format: {}", name))?;
+            app.start_attempt(Some(format)).map(Some)
+        }
+        ["attempt", ..] => Err("usage: attempt [mo3|ao5|stop]".into()),
+        ["drill", "stop"] => app.stop_drill().map(Some),
+        ["drill", first, rest @ ..] => {
+            // A leading number is the drill's length in seconds
+            let (length, alg) = match first.parse::<u64>() {
+                Ok(secs) if secs > 0 && !rest.is_empty() => (Duration::from_secs(secs), rest),
+                _ => (drill::DEFAULT_LENGTH, &args[1..]),
+            };
+            app.start_drill(&alg.join(" "), length).map(Some)
+        }
+        ["drill"] => {
+            let names: Vec<&str> = drill::ALGS.iter().map(|v| v.0).collect();
+            Err(format!("usage: drill [seconds] <{}|alg>", names.join("|")).into())
+        }
+        ["stats"] => Ok(Some(app.toggle_stats())),
+        ["histogram"] => Ok(Some(app.toggle_histogram(None))),
+        ["histogram", secs] => match secs.parse::<f32>() {
+            Ok(v) if (0.01..=3600.0).contains(&v) => Ok(Some(app.toggle_histogram(Some(v)))),
+            _ => Err(format!("not a bucket size: {}", secs).into()),
+        },
+        ["histogram", ..] => Err("usage: histogram [seconds]".into()),
+        ["pbs"] => Ok(Some(app.toggle_pbs())),
+        ["calendar"] => Ok(Some(app.toggle_calendar())),
+        ["notes"] => {
+            app.open_notes()?;
+            Ok(None)
+        }
+        ["session"] => {
+            app.open_sessions()?;
+            Ok(None)
+        }
+        ["session", id] => app.switch_session(id, None).map(Some),
+        ["session", id, puzzle] => app.switch_session(id, Some(puzzle)).map(Some),
+        ["session", ..] => Err("usage: session [name [puzzle]]".into()),
+        ["records"] => app.toggle_records().map(Some),
+        ["kinch"] => app.toggle_kinch().map(Some),
+        ["compare"] => app.toggle_compare(None, None).map(Some),
+        ["compare", first] => app.toggle_compare(Some(first), None).map(Some),
+        ["compare", first, second] => app.toggle_compare(Some(first), Some(second)).map(Some),
+        ["compare", ..] => Err("usage: compare <session> [session]".into()),
+        ["copy"] => app.copy_best("ao5").map(Some),
+        ["copy", name] => app.copy_best(name).map(Some),
+        ["copy", ..] => Err("usage: copy [mo3|ao5|ao12|ao50|ao100]".into()),
+        ["chart"] => Ok(Some(app.toggle_chart(None))),
+        ["chart", count] => match count.parse::<usize>() {
+            Ok(v) if v >= 2 => Ok(Some(app.toggle_chart(Some(v)))),
+            _ => Err(format!("not a number of solves: {}", count).into()),
+        },
+        ["chart", ..] => Err("usage: chart [solves]".into()),
+        ["cube", "reset"] => {
+            let cube = app.smartcube.as_mut().ok_or("no smart_cube is set up")?;
+            cube.reset();
+            Ok(Some("the smart cube is solved again".to_string()))
+        }
+        ["cube", ..] => Err("usage: cube reset".into()),
+        ["restore"] => {
+            app.open_restore()?;
+            Ok(None)
+        }
+        ["add", ..] => {
+            let text = line.trim_start().trim_start_matches("add");
+            append_list(app, text)
+        }
+        ["import", "list", path] => {
+            let text = std::fs::read_to_string(expand(path))?;
+            append_list(app, &text)
+        }
+        ["import", format, path, rest @ ..] if rest.len() <= 1 => {
+            idle(app)?;
+            let path = expand(path);
+            let sessions = match *format {
+                "cstimer" => import::cstimer::read(&path)?,
+                "twisty" => import::twisty::read(&path)?,
+                _ => return Err(format!("unknown import format: {}", format).into()),
+            };
+            let chosen = import::pick(sessions, rest.first().copied())?;
+            let added = app.sessions.current.merge(chosen.times)?;
+            Ok(Some(format!(
+                "imported {} solves from {}",
+                added, chosen.name
+            )))
+        }
+        ["import", ..] => Err("usage: import <cstimer|twisty|list> <path> [session]".into()),
+        [cmd, ..] => Err(format!("unknown command: {}", cmd).into()),
+    }
+}
+
+/// Refuse to change the session's solves under a solve being timed.
+fn idle(app: &App) -> Result<(), Box<dyn Error>> {
+    if app.timer.busy() {
+        return Err("finish the solve first".into());
+    }
+    Ok(())
+}
+
+/// Append a plain list of times to the session.
+pub fn append_list(app: &mut App, text: &str) -> Result<Option<String>, Box<dyn Error>> {
+    idle(app)?;
+    let (times, skipped) = import::plain::parse(text);
+    let added = times.len();
+    app.sessions.current.append(times)?;
+    Ok(Some(match skipped {
+        0 => format!("added {} solves", added),
+        n => format!("added {} solves, skipped {} unreadable entries", added, n),
+    }))
+}
@@ -0,0 +1,50 @@
+//! Two sessions' numbers side by side, such as this month's 3x3 against last month's.
+//!
+//! Each side is worked out once when asked for, from every solve of the variation being
+//! compared, so solves timed afterwards show up the next time.
+
+use super::app::{Time, Variation};
+use super::session::SessionMeta;
+use super::storage::Summary;
+use ordered_float::OrderedFloat;
+
+/// Shares of the solves, fastest first, that the distribution is shown at.
+pub const PERCENTILES: [usize; 5] = [10, 25, 50, 75, 90];
+
+/// One session as it's compared.
+pub struct Side {
+    pub id: String,
+    pub meta: SessionMeta,
+    pub summary: Summary,
+    /// Every solve of the variation that didn't DNF, as it counts, fastest first
+    sorted: Vec<OrderedFloat<f32>>,
+}
+
+impl Side {
+    pub fn of(id: &str, meta: SessionMeta, times: &[Time], variation: Variation) -> Self {
+        let mut sorted: Vec<OrderedFloat<f32>> = times
+            .iter()
+            .filter(|v| v.variation == variation)
+            .map(|v| OrderedFloat(v.value()))
+            .filter(|v| v.is_finite())
+            .collect();
+        sorted.sort();
+        Self {
+            id: id.to_string(),
+            meta,
+            summary: Summary::from_times(times, variation),
+            sorted,
+        }
+    }
+
+    /// The time `percent`% of the solves came in at or under, by the nearest rank.
+    pub fn percentile(&self, percent: usize) -> Option<OrderedFloat<f32>> {
+        let rank = (percent * self.sorted.len()).div_ceil(100).max(1);
+        self.sorted.get(rank - 1).copied()
+    }
+}
+
+pub struct Comparison {
+    pub variation: Variation,
+    pub sides: [Side; 2],
+}
@@ -0,0 +1,173 @@
+use super::custom::CustomStat;
+use super::format::TimeFormat;
+use super::scramble::{Event, Filter, Settings};
+use serde::Deserialize;
+use std::{
+    collections::HashMap,
+    env,
+    error::Error,
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// Settings read from `$XDG_CONFIG_HOME/cube-tui/config.toml`.
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// Where sessions are kept, overriding `$XDG_DATA_HOME/cube-tui`
+    pub data_dir: Option<PathBuf>,
+    /// Encrypt saved solves with a passphrase asked for at startup
+    pub encrypt: bool,
+    /// Inspect for up to 15 seconds before each solve, with WCA penalties for going over
+    pub inspection: bool,
+    /// Sound the WCA's 8 and 12 second warnings while inspecting
+    pub inspection_warnings: Option<InspectionWarnings>,
+    /// Seconds to hold space before letting go starts a solve, rather than starting on press
+    pub hold: Option<f32>,
+    /// Names of phases to split solves into, pressing space as each but the last ends
+    pub phases: Vec<String>,
+    /// Type each solve's time in rather than timing it, for timers that can't be connected
+    pub typing: bool,
+    /// Show "solving…" rather than the time while a solve runs
+    pub hide_timer: bool,
+    /// Stop a running solve with any key rather than only space
+    pub stop_on_any_key: bool,
+    /// Keep a beat while the timer runs
+    pub metronome: Option<MetronomeConfig>,
+    /// The serial device of a Speed Stacks timer to time solves with
+    pub stackmat: Option<PathBuf>,
+    /// A Speed Stacks timer plugged into the sound card instead
+    pub stackmat_audio: Option<StackmatAudio>,
+    /// A smart cube to time solves with
+    pub smart_cube: Option<SmartCubeConfig>,
+    /// How times are written
+    pub display: TimeFormat,
+    /// An export kept up to date without asking
+    pub auto_export: Option<AutoExport>,
+    /// Start a session of the day's date for each event, such as `3x3-2026-10-14`, the
+    /// first time it's opened each day
+    pub daily_sessions: bool,
+    /// Settings for single sessions, keyed by the session's directory name
+    pub sessions: HashMap<String, SessionConfig>,
+    /// Non-standard scrambles, keyed by event
+    pub scramble: HashMap<Event, Settings>,
+    /// Stats of the user's own, each an extra block in the Bests row
+    pub stats: Vec<CustomStat>,
+}
+
+/// A `[sessions.<id>]` table.
+#[derive(Deserialize, Clone, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct SessionConfig {
+    /// Conditions generated scrambles must meet
+    pub filter: Filter,
+}
+
+/// The `[auto_export]` table: written on quit, and every so often if `every` is set.
+#[derive(Deserialize, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct AutoExport {
+    /// Any format `:export` takes
+    pub format: String,
+    pub path: PathBuf,
+    /// Seconds between exports while running
+    pub every: Option<u64>,
+}
+
+/// The `[inspection_warnings]` table: how to sound them.
+#[derive(Deserialize, Clone, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct InspectionWarnings {
+    /// Run with `sh -c` and the seconds gone, 8 or 12, as `$1`, rather than ringing the
+    /// terminal's bell once at 8 and twice at 12
+    pub command: Option<String>,
+}
+
+/// The `[metronome]` table: how fast to beat and how.
+#[derive(Deserialize, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct MetronomeConfig {
+    /// Beats a minute
+    pub bpm: u32,
+    /// Ring the terminal's bell on each beat
+    pub sound: bool,
+    /// Light up the Timer panel on each beat
+    pub flash: bool,
+}
+
+impl Default for MetronomeConfig {
+    fn default() -> Self {
+        Self {
+            bpm: 120,
+            sound: true,
+            flash: true,
+        }
+    }
+}
+
+/// The `[stackmat_audio]` table: how to record the timer's audio jack.
+#[derive(Deserialize, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct StackmatAudio {
+    /// Run with `sh -c`, writing raw signed 16-bit little-endian mono audio to stdout
+    pub command: String,
+    /// Samples a second the command records at
+    pub rate: u32,
+}
+
+/// The `[smart_cube]` table: how to reach the cube.
+#[derive(Deserialize, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct SmartCubeConfig {
+    /// The cube's Bluetooth address, to connect to it directly
+    pub address: Option<String>,
+    /// Run with `sh -c`, connecting to the cube and printing what it hears
+    pub command: Option<String>,
+    /// How to read what the cube sends: `gan` when connecting by address, `notation` for a
+    /// command, when unset
+    pub protocol: Option<String>,
+}
+
+impl Config {
+    /// Read the config file at `path`, falling back to defaults when it doesn't exist.
+    pub fn load(path: &Path) -> Result<Self, Box<dyn Error>> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let text = fs::read_to_string(path)?;
+        toml::from_str(&text).map_err(|err| format!("{}: {}", path.display(), err).into())
+    }
+
+    pub fn data_dir(&self) -> Result<PathBuf, Box<dyn Error>> {
+        match &self.data_dir {
+            Some(v) => Ok(expand(&v.to_string_lossy())),
+            None => Ok(xdg_dir("XDG_DATA_HOME", ".local/share")?.join("cube-tui")),
+        }
+    }
+}
+
+/// The default config file location.
+pub fn config_path() -> Result<PathBuf, Box<dyn Error>> {
+    Ok(xdg_dir("XDG_CONFIG_HOME", ".config")?
+        .join("cube-tui")
+        .join("config.toml"))
+}
+
+/// An XDG base directory, or its fallback under `$HOME` when the variable is unset.
+fn xdg_dir(var: &str, fallback: &str) -> Result<PathBuf, Box<dyn Error>> {
+    match env::var_os(var).filter(|v| !v.is_empty()) {
+        Some(v) => Ok(PathBuf::from(v)),
+        None => {
+            let home = env::var_os("HOME").ok_or("neither $HOME nor $XDG dirs are set")?;
+            Ok(PathBuf::from(home).join(fallback))
+        }
+    }
+}
+
+/// Expand a leading `~` to the home directory.
+pub fn expand(path: &str) -> PathBuf {
+    match (path.strip_prefix('~'), env::var("HOME")) {
+        (Some(rest), Ok(home)) => PathBuf::from(home + rest),
+        _ => PathBuf::from(path),
+    }
+}
@@ -0,0 +1,379 @@
+//! Stats of the user's own, set up as `[[stats]]` tables in the config and shown as extra
+//! blocks in the Bests row.
+
+use super::app::{mean, trimmed_mean, Time, Variation};
+use super::stats::Bag;
+use chrono::{DateTime, Datelike, Duration, Local, NaiveDate, NaiveTime, TimeZone};
+use ordered_float::OrderedFloat;
+use serde::Deserialize;
+use std::{collections::VecDeque, error::Error};
+
+/// How a window's solves make one number.
+#[derive(Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum Kind {
+    /// Trimming 5% at each end, as the built-in averages do
+    Average,
+    Mean,
+    Median,
+}
+
+/// Which solves a stat looks at, by when they were done.
+#[derive(Deserialize, Clone, Copy, PartialEq, Eq, Default, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum Within {
+    #[default]
+    Session,
+    Day,
+    Week,
+}
+
+/// A `[[stats]]` table, such as `{ name = "ao200", stat = "average", of = 200 }` or the
+/// best mo5 of the day, `{ name = "day mo5", stat = "mean", of = 5, best = true, within =
+/// "day" }`.
+#[derive(Deserialize, Clone, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct CustomStat {
+    /// The block's title
+    pub name: String,
+    pub stat: Kind,
+    /// Solves in each window; every one in range when unset
+    pub of: Option<usize>,
+    /// The best window in range rather than the latest
+    #[serde(default)]
+    pub best: bool,
+    #[serde(default)]
+    pub within: Within,
+}
+
+impl CustomStat {
+    pub fn check(&self) -> Result<(), Box<dyn Error>> {
+        let fail = |why: &str| Err(format!("stats: {}: {}", self.name, why).into());
+        match self.of {
+            Some(0) => fail("of must be at least 1"),
+            Some(n) if n < 3 && self.stat == Kind::Average => {
+                fail("an average needs at least 3 solves to trim")
+            }
+            None if self.best => fail("best needs `of` to know how many solves to take at once"),
+            _ => Ok(()),
+        }
+    }
+
+    /// Start keeping the stat over the solves of `variation` in `times`, oldest first, as
+    /// of `now`. DNFs are infinitely slow, as they are in the built-in averages.
+    pub fn start(&self, times: &[Time], variation: Variation, now: DateTime<Local>) -> Running {
+        let mut running = Running {
+            stat: self.clone(),
+            variation,
+            range: self.range(now),
+            values: VecDeque::new(),
+            latest: None,
+            best: None,
+            windows: Bag::default(),
+            before: None,
+        };
+        for time in times {
+            // Only the best window needs every window worked out on the way
+            match self.best {
+                true => running.push(time),
+                false => running.count(time),
+            }
+        }
+        running.latest = running.window();
+        running.before = None;
+        running
+    }
+
+    /// When the solves looked at as of `now` start, from local midnight, and when that
+    /// runs out; none for the whole session.
+    fn range(&self, now: DateTime<Local>) -> Option<(i64, i64)> {
+        let today = now.date_naive();
+        let (first, days) = match self.within {
+            Within::Session => return None,
+            Within::Day => (today, 1),
+            Within::Week => {
+                let monday = today.weekday().num_days_from_monday() as i64;
+                (today - Duration::days(monday), 7)
+            }
+        };
+        // A midnight skipped by a clock change has nothing after it in range
+        let midnight = |day: NaiveDate| {
+            let midnight = Local.from_local_datetime(&day.and_time(NaiveTime::MIN));
+            midnight.earliest().map_or(i64::MAX, |v| v.timestamp())
+        };
+        Some((midnight(first), midnight(first + Duration::days(days))))
+    }
+}
+
+/// A custom stat kept up to date a solve at a time, as the tally is, so timing one doesn't
+/// go back over the whole session.
+pub struct Running {
+    stat: CustomStat,
+    variation: Variation,
+    /// When the day or week looked at starts and ends
+    range: Option<(i64, i64)>,
+    /// The values in range, oldest first; only the latest window's when `of` is set
+    values: VecDeque<f32>,
+    latest: Option<OrderedFloat<f32>>,
+    best: Option<OrderedFloat<f32>>,
+    /// Every finite window's value, for the best, so one taken away leaves the next
+    windows: Bag,
+    /// What the newest solve changed, so it can be taken back
+    before: Option<Before>,
+}
+
+struct Before {
+    counted: bool,
+    /// The value it pushed out of the window
+    dropped: Option<f32>,
+    latest: Option<OrderedFloat<f32>>,
+    best: Option<OrderedFloat<f32>>,
+}
+
+impl Running {
+    pub fn value(&self) -> Option<OrderedFloat<f32>> {
+        match self.stat.best {
+            true => self.best,
+            false => self.latest,
+        }
+    }
+
+    /// Whether the day or week looked at is still going on at `now`.
+    pub fn current(&self, now: DateTime<Local>) -> bool {
+        self.range.is_none_or(|(_, end)| now.timestamp() < end)
+    }
+
+    /// Count `time` in as the newest solve.
+    pub fn push(&mut self, time: &Time) {
+        self.count(time);
+        if self.before.as_ref().is_some_and(|v| v.counted) {
+            self.latest = self.window();
+            if let Some(v) = self.latest.filter(|v| self.stat.best && v.is_finite()) {
+                self.windows.insert(v);
+            }
+            self.best = self.windows.first();
+        }
+    }
+
+    /// Take back the newest solve, returning whether it could be: only the one pushed last
+    /// is kept to take back.
+    pub fn pop(&mut self) -> bool {
+        let Some(before) = self.before.take() else {
+            return false;
+        };
+        if before.counted {
+            if let Some(v) = self.latest.filter(|v| self.stat.best && v.is_finite()) {
+                self.windows.remove(v);
+            }
+            self.values.pop_back();
+            if let Some(v) = before.dropped {
+                self.values.push_front(v);
+            }
+        }
+        self.latest = before.latest;
+        self.best = before.best;
+        true
+    }
+
+    /// Take `time` out from wherever it was, given the solves `before` and `after` it,
+    /// oldest first; `whole` says whether `before` runs back to the first solve. Returns
+    /// whether it could be, which it can't when a window needs older solves than those.
+    pub fn remove(&mut self, time: &Time, before: &[Time], whole: bool, after: &[Time]) -> bool {
+        self.before = None;
+        if !self.counts(time) {
+            return true;
+        }
+        let values = |times: &[Time]| -> Vec<f32> {
+            let counted = times.iter().filter(|v| self.counts(v));
+            counted.map(Time::value).collect()
+        };
+        let (older, newer) = (values(before), values(after));
+        let removed = time.value();
+        let Some(n) = self.stat.of else {
+            // Every value in range is kept, and their order doesn't matter to the stat
+            let at = self.values.iter().rposition(|v| v.to_bits() == removed.to_bits());
+            if let Some(at) = at {
+                self.values.remove(at);
+            }
+            self.latest = self.window();
+            return true;
+        };
+        if older.len() < n && !whole {
+            return false;
+        }
+        if self.stat.best {
+            // The windows it was in go, and those ending at the solves after it reach back
+            // one further instead
+            let k = newer.len().min(n - 1);
+            let mut old = older[older.len().saturating_sub(n - 1)..].to_vec();
+            old.push(removed);
+            old.extend(&newer[..k]);
+            for v in windows(self.stat.stat, &old, n) {
+                self.windows.remove(v);
+            }
+            let kept = older.len().min(n);
+            let mut new = older[older.len() - kept..].to_vec();
+            new.extend(&newer[..k]);
+            // A window of older solves alone is there already
+            let skip = (kept + 1).saturating_sub(n);
+            for v in windows(self.stat.stat, &new[skip..], n) {
+                self.windows.insert(v);
+            }
+            self.best = self.windows.first();
+        }
+        // The latest window had it when fewer than a window's worth came after it
+        if newer.len() < n {
+            let at = self.values.len().saturating_sub(newer.len() + 1);
+            let full = self.values.len() == n;
+            self.values.remove(at);
+            if full {
+                if let Some(v) = older.len().checked_sub(n - newer.len()).map(|i| older[i]) {
+                    self.values.push_front(v);
+                }
+            }
+        }
+        self.latest = self.window();
+        true
+    }
+
+    /// Whether `time` is one the stat looks at.
+    fn counts(&self, time: &Time) -> bool {
+        let since = self.range.map(|(start, _)| start);
+        time.variation == self.variation
+            && since.is_none_or(|since| time.date.is_some_and(|date| date >= since))
+    }
+
+    /// Take `time`'s value into the window without working the stat out.
+    fn count(&mut self, time: &Time) {
+        let counted = self.counts(time);
+        let mut dropped = None;
+        if counted {
+            self.values.push_back(time.value());
+            if self.stat.of.is_some_and(|n| self.values.len() > n) {
+                dropped = self.values.pop_front();
+            }
+        }
+        self.before = Some(Before {
+            counted,
+            dropped,
+            latest: self.latest,
+            best: self.best,
+        });
+    }
+
+    /// The stat over the latest window, if there are enough solves for one.
+    fn window(&mut self) -> Option<OrderedFloat<f32>> {
+        let n = self.stat.of.unwrap_or(self.values.len());
+        if n == 0 || self.values.len() < n || (self.stat.stat == Kind::Average && n < 3) {
+            return None;
+        }
+        Some(stat(self.stat.stat, self.values.make_contiguous()))
+    }
+}
+
+fn stat(kind: Kind, values: &[f32]) -> OrderedFloat<f32> {
+    match kind {
+        Kind::Average => trimmed_mean(values),
+        Kind::Mean => mean(values),
+        Kind::Median => median(values),
+    }
+}
+
+/// The finite values of each window of `n` over `values` in turn.
+fn windows(kind: Kind, values: &[f32], n: usize) -> impl Iterator<Item = OrderedFloat<f32>> + '_ {
+    values
+        .windows(n)
+        .map(move |v| stat(kind, v))
+        .filter(|v| v.is_finite())
+}
+
+fn median(values: &[f32]) -> OrderedFloat<f32> {
+    let mut sorted: Vec<OrderedFloat<f32>> = values.iter().copied().map(OrderedFloat).collect();
+    sorted.sort();
+    let n = sorted.len();
+    match n % 2 {
+        1 => sorted[n / 2],
+        _ => (sorted[n / 2 - 1] + sorted[n / 2]) / 2.0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::Penalty;
+
+    fn stat(stat: Kind, of: Option<usize>, best: bool) -> CustomStat {
+        CustomStat {
+            name: String::new(),
+            stat,
+            of,
+            best,
+            within: Within::Session,
+        }
+    }
+
+    #[test]
+    fn running_matches_going_over_every_solve() {
+        let now = Local::now();
+        let times: Vec<Time> = (0..60)
+            .map(|i| {
+                let mut time = Time::from(8.0 + (i * 7 % 11) as f32);
+                if i % 9 == 4 {
+                    time.penalty = Penalty::Dnf;
+                }
+                time
+            })
+            .collect();
+        let stats = [
+            stat(Kind::Average, Some(5), false),
+            stat(Kind::Mean, Some(3), true),
+            stat(Kind::Median, None, false),
+        ];
+        for stat in &stats {
+            let mut running = stat.start(&[], Variation::Normal, now);
+            for (i, time) in times.iter().enumerate() {
+                // A penalty given and taken back again leaves no trace
+                let mut dnf = time.clone();
+                dnf.penalty = Penalty::Dnf;
+                running.push(&dnf);
+                assert!(running.pop());
+                running.push(time);
+                let fresh = stat.start(&times[..=i], Variation::Normal, now);
+                assert_eq!(running.value(), fresh.value(), "{:?} after {}", stat, i);
+            }
+        }
+    }
+
+    #[test]
+    fn running_takes_out_any_solve() {
+        let now = Local::now();
+        let mut times: Vec<Time> = (0..80)
+            .map(|i| {
+                let mut time = Time::from(8.0 + (i * 5 % 13) as f32);
+                if i % 7 == 3 {
+                    time.penalty = Penalty::Dnf;
+                }
+                time
+            })
+            .collect();
+        let stats = [
+            stat(Kind::Average, Some(5), true),
+            stat(Kind::Mean, Some(3), true),
+            stat(Kind::Median, None, false),
+        ];
+        let mut running: Vec<Running> = stats
+            .iter()
+            .map(|v| v.start(&times, Variation::Normal, now))
+            .collect();
+        for at in [79, 0, 40, 41, 3, 70, 20] {
+            let at = at.min(times.len() - 1);
+            let removed = times.remove(at);
+            for (stat, running) in stats.iter().zip(&mut running) {
+                let (before, after) = times.split_at(at);
+                assert!(running.remove(&removed, before, true, after));
+                let fresh = stat.start(&times, Variation::Normal, now);
+                assert_eq!(running.value(), fresh.value(), "{:?} without {}", stat, at);
+            }
+        }
+    }
+}
@@ -0,0 +1,113 @@
+//! Algorithm drills: one alg done over and over for a fixed time, pressing space after
+//! each rep, to see how fast it gets executed.
+
+use std::time::{Duration, Instant};
+
+/// How long a drill runs unless told otherwise.
+pub const DEFAULT_LENGTH: Duration = Duration::from_secs(60);
+
+/// Algs to drill by name, in WCA notation.
+pub const ALGS: [(&str, &str); 16] = [
+    ("sexy", "R U R' U'"),
+    ("sledge", "R' F R F'"),
+    ("sune", "R U R' U R U2 R'"),
+    ("antisune", "R U2 R' U' R U' R'"),
+    ("aa", "x R' U R' D2 R U' R' D2 R2 x'"),
+    ("ab", "x R2 D2 R U R' D2 R U' R x'"),
+    ("h", "M2 U M2 U2 M2 U M2"),
+    ("ja", "x R2 F R F' R U2 r' U r U2 x'"),
+    ("jb", "R U R' F' R U R' U' R' F R2 U' R'"),
+    ("t", "R U R' U' R' F R2 U' R' U' R U R' F'"),
+    ("ua", "M2 U M U2 M' U M2"),
+    ("ub", "M2 U' M U2 M' U' M2"),
+    ("y", "F R U' R' U' R U R' F' R U R' U' R' F R F'"),
+    ("z", "M' U M2 U M2 U M' U2 M2"),
+    ("ra", "R U' R' U' R U R D R' U' R D' R' U2 R'"),
+    ("rb", "R2 F R U R U' R' F' R U2 R' U2 R"),
+];
+
+/// The alg called `name`, ignoring case.
+pub fn named(name: &str) -> Option<&'static str> {
+    ALGS.iter()
+        .find(|(v, _)| v.eq_ignore_ascii_case(name))
+        .map(|(_, alg)| *alg)
+}
+
+pub struct Drill {
+    /// What the alg was picked by, if it has a name
+    pub name: Option<String>,
+    pub alg: String,
+    pub length: Duration,
+    /// When the first rep began
+    start: Option<Instant>,
+    /// When the rep under way began
+    rep_start: Option<Instant>,
+    /// Seconds each finished rep took, oldest first
+    pub reps: Vec<f32>,
+    /// Whether time has run out
+    pub finished: bool,
+}
+
+impl Drill {
+    pub fn new(name: Option<String>, alg: String, length: Duration) -> Self {
+        Self {
+            name,
+            alg,
+            length,
+            start: None,
+            rep_start: None,
+            reps: vec![],
+            finished: false,
+        }
+    }
+
+    /// Start the drill on the first press at `at`, and finish a rep on each after.
+    pub fn press(&mut self, at: Instant) {
+        if self.finished {
+            return;
+        }
+        match self.rep_start {
+            Some(rep) => self.reps.push((at - rep).as_secs_f32()),
+            None => self.start = Some(at),
+        }
+        self.rep_start = Some(at);
+    }
+
+    /// End the drill once its time is up, returning whether that just happened. A rep
+    /// still going then doesn't count.
+    pub fn poll(&mut self) -> bool {
+        if self.finished || !self.running() || !self.left().is_zero() {
+            return false;
+        }
+        self.finished = true;
+        self.rep_start = None;
+        true
+    }
+
+    pub fn running(&self) -> bool {
+        self.start.is_some() && !self.finished
+    }
+
+    /// Time before the drill ends.
+    pub fn left(&self) -> Duration {
+        match self.start {
+            Some(start) => self.length.saturating_sub(start.elapsed()),
+            None => self.length,
+        }
+    }
+
+    /// Mean seconds a rep took.
+    pub fn mean(&self) -> Option<f32> {
+        let n = self.reps.len();
+        (n > 0).then(|| self.reps.iter().sum::<f32>() / n as f32)
+    }
+
+    pub fn best(&self) -> Option<f32> {
+        self.reps.iter().copied().reduce(f32::min)
+    }
+
+    /// What the alg is called in messages: its name, or the alg itself.
+    pub fn title(&self) -> &str {
+        self.name.as_deref().unwrap_or(&self.alg)
+    }
+}
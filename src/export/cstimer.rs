@@ -0,0 +1,52 @@
+use crate::app::{Penalty, Time};
+use serde_json::{json, Map, Value};
+use std::{error::Error, fs, path::Path};
+
+/// Write `times` as a csTimer export holding a single session called `name`.
+pub fn write(path: &Path, name: &str, times: &[Time]) -> Result<(), Box<dyn Error>> {
+    let solves: Vec<Value> = times.iter().map(solve).collect();
+
+    // csTimer keeps a [count, dnfs, mean ms] summary alongside each session name
+    let dnfs = times.iter().filter(|v| v.penalty == Penalty::Dnf).count();
+    let solved: Vec<f32> = times
+        .iter()
+        .filter(|v| v.penalty != Penalty::Dnf)
+        .map(|v| v.value())
+        .collect();
+    let mean = match solved.len() {
+        0 => -1.0,
+        n => solved.iter().sum::<f32>() * 1000.0 / n as f32,
+    };
+    let session_data = json!({
+        "1": {
+            "name": name,
+            "opt": {},
+            "rank": 1,
+            "stat": [times.len(), dnfs, mean.round() as i64],
+        }
+    });
+
+    let mut root = Map::new();
+    root.insert("session1".to_string(), Value::Array(solves));
+    root.insert(
+        "properties".to_string(),
+        json!({ "sessionData": session_data.to_string() }),
+    );
+    fs::write(path, Value::Object(root).to_string())?;
+    Ok(())
+}
+
+fn solve(time: &Time) -> Value {
+    let penalty = match time.penalty {
+        Penalty::None => 0,
+        Penalty::PlusTwo => 2000,
+        Penalty::Dnf => -1,
+    };
+    let ms = (time.time * 1000.0).round() as i64;
+    json!([
+        [penalty, ms],
+        time.scramble.trim(),
+        "",
+        time.date.unwrap_or(0)
+    ])
+}
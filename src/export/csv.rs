@@ -0,0 +1,44 @@
+use crate::app::Time;
+use chrono::{TimeZone, Utc};
+use std::{error::Error, fs, path::Path};
+
+const HEADER: &str = "index,time,penalty,ao5,ao12,scramble,timestamp";
+
+/// Write the times table as CSV, oldest solve first.
+pub fn write(path: &Path, times: &[Time]) -> Result<(), Box<dyn Error>> {
+    let mut out = String::from(HEADER);
+    out.push('\n');
+    for (i, t) in times.iter().enumerate() {
+        let avg = |v: Option<ordered_float::OrderedFloat<f32>>| match v {
+            Some(v) if v.is_infinite() => "DNF".to_string(),
+            Some(v) => format!("{:.3}", v),
+            None => String::new(),
+        };
+        let timestamp = t
+            .date
+            .and_then(|v| Utc.timestamp_opt(v, 0).single())
+            .map(|v| v.to_rfc3339())
+            .unwrap_or_default();
+        let fields = [
+            (i + 1).to_string(),
+            format!("{:.3}", t.time),
+            t.penalty.as_str().to_string(),
+            avg(t.ao5),
+            avg(t.ao12),
+            escape(t.scramble.trim()),
+            timestamp,
+        ];
+        out += &fields.join(",");
+        out.push('\n');
+    }
+    fs::write(path, out)?;
+    Ok(())
+}
+
+fn escape(field: &str) -> String {
+    if field.contains([',', '"', '\n']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
@@ -0,0 +1,89 @@
+use crate::app::{Penalty, Time, Variation};
+use crate::scramble::Event;
+use serde_json::{json, Value};
+use std::{error::Error, fs, path::Path};
+
+const SESSION_ID: &str = "cube-tui";
+
+/// Write `times` of `event` in the `{sessions, solves}` JSON layout CubeDesk imports.
+pub fn write(path: &Path, name: &str, event: Event, times: &[Time]) -> Result<(), Box<dyn Error>> {
+    let solves = times
+        .iter()
+        .enumerate()
+        .map(|(i, v)| Ok(solve(i, v, cube_type(event, v.variation)?)))
+        .collect::<Result<Vec<Value>, Box<dyn Error>>>()?;
+    let root = json!({
+        "sessions": [{ "id": SESSION_ID, "name": name, "order": 0 }],
+        "solves": solves,
+    });
+    fs::write(path, serde_json::to_string_pretty(&root)?)?;
+    Ok(())
+}
+
+/// CubeDesk's id for solves of `event` done as `variation`.
+fn cube_type(event: Event, variation: Variation) -> Result<&'static str, Box<dyn Error>> {
+    let id = match (event, variation) {
+        (Event::Cube3, Variation::OneHanded) => Some("333oh"),
+        (_, Variation::Normal) => match event {
+            Event::Cube2 => Some("222"),
+            Event::Cube3 => Some("333"),
+            Event::Cube4 => Some("444"),
+            Event::Cube5 => Some("555"),
+            Event::Cube6 => Some("666"),
+            Event::Cube7 => Some("777"),
+            Event::Pyraminx => Some("pyram"),
+            Event::Megaminx => Some("minx"),
+            Event::Skewb => Some("skewb"),
+            Event::Square1 => Some("sq1"),
+            Event::Clock => Some("clock"),
+            Event::Cube3Blind => Some("333bl"),
+            _ => None,
+        },
+        _ => None,
+    };
+    let name = match variation {
+        Variation::Normal => event.to_string(),
+        v => format!("{} {}", event, v),
+    };
+    id.ok_or_else(|| format!("CubeDesk has no cube type for {} solves", name).into())
+}
+
+fn solve(index: usize, time: &Time, cube_type: &str) -> Value {
+    let plus_two = time.penalty == Penalty::PlusTwo;
+    let total = if plus_two { time.time + 2.0 } else { time.time };
+    // CubeDesk wants both ends of the solve; only the finish is known here
+    let ended_at = time.date.unwrap_or(0) * 1000;
+    let started_at = ended_at - (time.time * 1000.0).round() as i64;
+    json!({
+        "id": format!("{}-{}", SESSION_ID, index),
+        "session_id": SESSION_ID,
+        "cube_type": cube_type,
+        "raw_time": time.time,
+        "time": total,
+        "scramble": time.scramble.trim(),
+        "started_at": started_at,
+        "ended_at": ended_at,
+        "plus_two": plus_two,
+        "dnf": time.penalty == Penalty::Dnf,
+        "from_timer": true,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cube_type_follows_the_event() {
+        let known = [
+            (Event::Cube4, Variation::Normal, "444"),
+            (Event::Cube3, Variation::OneHanded, "333oh"),
+            (Event::Cube3Blind, Variation::Normal, "333bl"),
+        ];
+        for (event, variation, id) in known {
+            assert_eq!(cube_type(event, variation).unwrap(), id);
+        }
+        assert!(cube_type(Event::Fmc, Variation::Normal).is_err());
+        assert!(cube_type(Event::Cube2, Variation::Feet).is_err());
+    }
+}
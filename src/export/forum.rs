@@ -0,0 +1,48 @@
+use crate::app::Time;
+use crate::format::TimeFormat;
+use ordered_float::OrderedFloat;
+use std::fmt::Write;
+
+/// An average as it's posted on forums: the result with every time, the trimmed ones in
+/// brackets, then each time again with its scramble.
+///
+/// `name` is how the average is written, `ao5` or `mo3`, and `trim` how many solves are
+/// dropped at each end.
+pub fn average(
+    name: &str,
+    value: OrderedFloat<f32>,
+    set: &[Time],
+    trim: usize,
+    format: &TimeFormat,
+) -> String {
+    // Ties go to the earlier solve at the fast end and the later one at the slow end
+    let mut order: Vec<usize> = (0..set.len()).collect();
+    order.sort_by_key(|i| OrderedFloat(set[*i].value()));
+    let trimmed = |i: usize| {
+        let at = order.iter().position(|v| *v == i).unwrap_or_default();
+        at < trim || at >= set.len() - trim
+    };
+    let times: Vec<String> = set
+        .iter()
+        .enumerate()
+        .map(|(i, t)| match trimmed(i) {
+            true => format!("({})", format.single(t)),
+            false => format.single(t),
+        })
+        .collect();
+
+    let mut out = format!(
+        "{}: {} = {}\n",
+        name,
+        format.average(Some(value), "DNF"),
+        times.join(", ")
+    );
+    for (i, (time, t)) in times.iter().zip(set).enumerate() {
+        let _ = write!(out, "\n{}. {}", i + 1, time);
+        let scramble = t.scramble.trim().replace('\n', " ");
+        if !scramble.is_empty() {
+            let _ = write!(out, "   {}", scramble);
+        }
+    }
+    out
+}
@@ -0,0 +1,165 @@
+use crate::app::{Penalty, Time};
+use crate::session::Session;
+use chrono::{Local, TimeZone};
+use ordered_float::OrderedFloat;
+use std::{error::Error, fmt::Write, fs, path::Path};
+
+/// Write a Markdown report of `session`: summary, bests, best averages, attempts and every
+/// solve.
+///
+/// `all` is the full history, since the session only keeps its newest solves loaded.
+pub fn write(path: &Path, session: &Session, all: &[Time]) -> Result<(), Box<dyn Error>> {
+    let times = &session.times;
+    let mut out = String::new();
+    writeln!(out, "# {} ({})\n", session.meta.name, session.meta.event)?;
+    writeln!(
+        out,
+        "Session started {}, report generated {}.\n",
+        date(Some(session.meta.created)),
+        Local::now().format("%Y-%m-%d %H:%M")
+    )?;
+
+    let worst = all
+        .iter()
+        .map(|v| OrderedFloat(v.value()))
+        .max()
+        .map(|v| v.0);
+    writeln!(out, "## Summary\n")?;
+    writeln!(out, "| Stat | Value |\n| --- | --- |")?;
+    writeln!(out, "| Solves | {} |", all.len())?;
+    writeln!(out, "| Mean | {} |", stat(times.rollingavg))?;
+    writeln!(out, "| Best | {} |", stat(times.pbsingle))?;
+    writeln!(out, "| Worst | {} |", stat(worst.map(OrderedFloat)))?;
+    writeln!(out, "| ao50 | {} |", stat(times.ao50))?;
+    writeln!(out, "| ao100 | {} |", stat(times.ao100))?;
+    writeln!(out, "| ao1000 | {} |\n", stat(times.ao1000))?;
+
+    writeln!(out, "## Personal bests\n")?;
+    writeln!(out, "| Stat | Time | Solve |\n| --- | --- | --- |")?;
+    let single = best_index(all, |v| Some(OrderedFloat(v.value())));
+    let mo3 = best_index(all, |v| v.mo3);
+    let ao5 = best_index(all, |v| v.ao5);
+    let ao12 = best_index(all, |v| v.ao12);
+    for (name, best, value) in [
+        ("Single", single, times.pbsingle),
+        ("mo3", mo3, times.pbmo3),
+        ("ao5", ao5, times.pbao5),
+        ("ao12", ao12, times.pbao12),
+        ("ao50", best_index(all, |v| v.ao50), times.pbao50),
+        ("ao100", best_index(all, |v| v.ao100), times.pbao100),
+        ("ao1000", best_index(all, |v| v.ao1000), times.pbao1000),
+    ] {
+        let solve = best.map(|v| (v + 1).to_string()).unwrap_or_default();
+        writeln!(out, "| {} | {} | {} |", name, stat(value), solve)?;
+    }
+    writeln!(out)?;
+
+    for (name, best, n) in [("ao5", ao5, 5), ("ao12", ao12, 12)] {
+        if let Some(end) = best {
+            writeln!(out, "## Best {}\n", name)?;
+            average(&mut out, &all[end + 1 - n..=end], end + 1 - n)?;
+        }
+    }
+
+    if !session.attempts.is_empty() {
+        writeln!(out, "## Attempts\n")?;
+        for (i, record) in session.attempts.iter().enumerate() {
+            let result = stat(Some(OrderedFloat(record.value())));
+            writeln!(
+                out,
+                "### {}. {} {}: {} ({})\n",
+                i + 1,
+                record.event,
+                record.format,
+                result,
+                date(Some(record.date))
+            )?;
+            writeln!(out, "| # | Time | Scramble |\n| --- | --- | --- |")?;
+            for (j, t) in record.solves.iter().enumerate() {
+                writeln!(
+                    out,
+                    "| {} | {} | `{}` |",
+                    j + 1,
+                    single_time(t),
+                    t.scramble.trim().replace('\n', " ")
+                )?;
+            }
+            writeln!(out)?;
+        }
+    }
+
+    writeln!(out, "## Solves\n")?;
+    writeln!(
+        out,
+        "| # | Time | mo3 | ao5 | ao12 | Date |\n| --- | --- | --- | --- | --- | --- |"
+    )?;
+    for (i, t) in all.iter().enumerate() {
+        writeln!(
+            out,
+            "| {} | {} | {} | {} | {} | {} |",
+            i + 1,
+            single_time(t),
+            stat(t.mo3),
+            stat(t.ao5),
+            stat(t.ao12),
+            date(t.date)
+        )?;
+    }
+
+    fs::write(path, out)?;
+    Ok(())
+}
+
+/// List the solves of an average with the trimmed best and worst in brackets.
+fn average(out: &mut String, set: &[Time], first: usize) -> Result<(), Box<dyn Error>> {
+    let key = |i: &usize| OrderedFloat(set[*i].value());
+    let best = (0..set.len()).min_by_key(key);
+    let worst = (0..set.len()).max_by_key(key);
+    writeln!(out, "| # | Time | Scramble |\n| --- | --- | --- |")?;
+    for (i, t) in set.iter().enumerate() {
+        let time = match Some(i) == best || Some(i) == worst {
+            true => format!("({})", single_time(t)),
+            false => single_time(t),
+        };
+        writeln!(
+            out,
+            "| {} | {} | `{}` |",
+            first + i + 1,
+            time,
+            t.scramble.trim().replace('\n', " ")
+        )?;
+    }
+    writeln!(out)?;
+    Ok(())
+}
+
+/// Where the best of `f` is, never counting a DNF.
+fn best_index(times: &[Time], f: fn(&Time) -> Option<OrderedFloat<f32>>) -> Option<usize> {
+    times
+        .iter()
+        .enumerate()
+        .filter_map(|(i, v)| Some((f(v).filter(|v| v.is_finite())?, i)))
+        .min()
+        .map(|v| v.1)
+}
+
+fn single_time(t: &Time) -> String {
+    match t.penalty {
+        Penalty::Dnf => format!("DNF({:.2})", t.time),
+        _ => format!("{:.2}{}", t.time, t.penalty.as_str()),
+    }
+}
+
+fn stat(v: Option<OrderedFloat<f32>>) -> String {
+    match v {
+        Some(v) if v.is_infinite() => "DNF".to_string(),
+        Some(v) => format!("{:.2}", v),
+        None => "-".to_string(),
+    }
+}
+
+fn date(v: Option<i64>) -> String {
+    v.and_then(|v| Local.timestamp_opt(v, 0).single())
+        .map(|v| v.format("%Y-%m-%d %H:%M").to_string())
+        .unwrap_or_else(|| "-".to_string())
+}
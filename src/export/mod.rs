@@ -0,0 +1,25 @@
+pub mod cstimer;
+pub mod csv;
+pub mod cubedesk;
+pub mod forum;
+pub mod json;
+pub mod markdown;
+
+use crate::session::Session;
+use std::{error::Error, path::Path};
+
+pub const FORMATS: [&str; 6] = ["csv", "cstimer", "cubedesk", "json", "markdown", "md"];
+
+/// Export every solve in `session` as `format`, returning how many were written.
+pub fn write(format: &str, path: &Path, session: &mut Session) -> Result<usize, Box<dyn Error>> {
+    let times = session.all()?;
+    match format {
+        "csv" => csv::write(path, &times)?,
+        "cstimer" => cstimer::write(path, &session.meta.name, &times)?,
+        "cubedesk" => cubedesk::write(path, &session.meta.name, session.meta.event, &times)?,
+        "json" => json::write(path, &times)?,
+        "markdown" | "md" => markdown::write(path, session, &times)?,
+        _ => return Err(format!("unknown export format: {}", format).into()),
+    }
+    Ok(times.len())
+}
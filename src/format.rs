@@ -0,0 +1,112 @@
+use super::app::{Penalty, Time};
+use ordered_float::OrderedFloat;
+use serde::Deserialize;
+use std::error::Error;
+
+/// The `[display]` table: how times are written in the Timer panel, table and stats.
+#[derive(Deserialize, Clone, Copy, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct TimeFormat {
+    /// Decimal places of finished times, averages and bests; by default the Timer panel
+    /// shows three and everything else two
+    pub decimals: Option<usize>,
+    /// Decimal places of the timer while it runs
+    pub timer_decimals: usize,
+    /// Write a minute or more as `1:02.45` rather than `62.45`
+    pub minutes: bool,
+}
+
+impl Default for TimeFormat {
+    fn default() -> Self {
+        Self {
+            decimals: None,
+            timer_decimals: 1,
+            minutes: false,
+        }
+    }
+}
+
+impl TimeFormat {
+    pub fn check(&self) -> Result<(), Box<dyn Error>> {
+        if self.decimals.unwrap_or(0) > 3 || self.timer_decimals > 3 {
+            return Err("display: decimals must be 0 to 3".into());
+        }
+        Ok(())
+    }
+
+    /// `secs` to `decimals` places, with minutes split off if they're on.
+    pub fn seconds(&self, secs: f32, decimals: usize) -> String {
+        // Round first so 59.999 comes out as 1:00.00 rather than 60.00 or 0:60.00
+        let scale = 10f32.powi(decimals as i32);
+        let rounded = (secs * scale).round() / scale;
+        if !self.minutes || rounded < 60.0 {
+            return format!("{:.*}", decimals, secs);
+        }
+        let secs = rounded;
+        let minutes = (secs / 60.0).floor();
+        let rest = secs - minutes * 60.0;
+        // Seconds are padded to two digits, plus the point and decimals if any
+        let width = if decimals == 0 { 2 } else { decimals + 3 };
+        format!("{}:{:0width$.*}", minutes, decimals, rest, width = width)
+    }
+
+    /// A finished time in the Timer panel.
+    pub fn timer(&self, secs: f32, penalty: Penalty) -> String {
+        // A DNF typed in without a time has none to show
+        if penalty == Penalty::Dnf && secs == 0.0 {
+            return "DNF".to_string();
+        }
+        let time = self.seconds(secs, self.decimals.unwrap_or(3));
+        match penalty {
+            Penalty::None => time,
+            Penalty::PlusTwo => format!("{}+", time),
+            Penalty::Dnf => format!("DNF({})", time),
+        }
+    }
+
+    /// The running timer.
+    pub fn running(&self, secs: f32) -> String {
+        self.seconds(secs, self.timer_decimals)
+    }
+
+    /// Seconds as the table and stats write them.
+    pub fn time(&self, secs: f32) -> String {
+        self.seconds(secs, self.decimals.unwrap_or(2))
+    }
+
+    /// A solve's time as it counts, marked when a +2 is in it.
+    pub fn single(&self, t: &Time) -> String {
+        // A fewest-moves result is the solution's length
+        if let Some(moves) = t.moves.filter(|_| t.penalty != Penalty::Dnf) {
+            return moves.to_string();
+        }
+        if let Some(result) = t.multiblind {
+            if !result.counts() || t.penalty == Penalty::Dnf {
+                return "DNF".to_string();
+            }
+            // Cubes first, then whole seconds in minutes, as the WCA writes it
+            let secs = t.value() as u32;
+            return format!(
+                "{}/{} {}:{:02}",
+                result.solved,
+                result.attempted,
+                secs / 60,
+                secs % 60
+            );
+        }
+        match t.penalty {
+            Penalty::None => self.time(t.time),
+            Penalty::PlusTwo => format!("{}+", self.time(t.value())),
+            Penalty::Dnf => "DNF".to_string(),
+        }
+    }
+
+    /// An average or best, which is infinite when DNFs sank it.
+    pub fn average(&self, v: Option<OrderedFloat<f32>>, none: &str) -> String {
+        match v {
+            Some(v) if v.is_infinite() => "DNF".to_string(),
+            Some(v) => self.time(v.0),
+            None => none.to_string(),
+        }
+    }
+}
@@ -0,0 +1,4 @@
+//! Timers and smart cubes plugged in to time solves in place of the keyboard.
+
+pub mod smartcube;
+pub mod stackmat;
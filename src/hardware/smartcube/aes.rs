@@ -0,0 +1,162 @@
+//! AES-128 on single blocks, which is all GAN cubes need to have their packets opened.
+//!
+//! Blocks are held as the standard lays them out, a column of four bytes after another.
+
+/// The substitution box, built from its definition: the inverse in GF(2^8), then an affine
+/// map.
+const SBOX: [u8; 256] = sbox();
+const INV_SBOX: [u8; 256] = invert(&SBOX);
+
+/// Multiply by x in GF(2^8), reducing by the AES polynomial.
+const fn xtime(a: u8) -> u8 {
+    (a << 1) ^ if a & 0x80 != 0 { 0x1b } else { 0 }
+}
+
+const fn mul(mut a: u8, mut b: u8) -> u8 {
+    let mut product = 0;
+    while b != 0 {
+        if b & 1 != 0 {
+            product ^= a;
+        }
+        a = xtime(a);
+        b >>= 1;
+    }
+    product
+}
+
+const fn sbox() -> [u8; 256] {
+    let mut sbox = [0; 256];
+    sbox[0] = 0x63;
+    // `p` runs through every nonzero element as powers of 3 while `q` runs through their
+    // inverses as powers of 3's inverse
+    let (mut p, mut q) = (1u8, 1u8);
+    loop {
+        p ^= xtime(p);
+        q ^= q << 1;
+        q ^= q << 2;
+        q ^= q << 4;
+        if q & 0x80 != 0 {
+            q ^= 0x09;
+        }
+        let affine = q ^ q.rotate_left(1) ^ q.rotate_left(2) ^ q.rotate_left(3) ^ q.rotate_left(4);
+        sbox[p as usize] = affine ^ 0x63;
+        if p == 1 {
+            return sbox;
+        }
+    }
+}
+
+const fn invert(sbox: &[u8; 256]) -> [u8; 256] {
+    let mut inverse = [0; 256];
+    let mut i = 0;
+    while i < 256 {
+        inverse[sbox[i] as usize] = i as u8;
+        i += 1;
+    }
+    inverse
+}
+
+pub struct Aes128 {
+    /// The key for each of the ten rounds, and the one added first
+    keys: [[u8; 16]; 11],
+}
+
+impl Aes128 {
+    pub fn new(key: &[u8; 16]) -> Self {
+        let mut keys = [[0; 16]; 11];
+        keys[0] = *key;
+        let mut rcon = 1;
+        for round in 1..11 {
+            let last = keys[round - 1];
+            let mut word = [last[13], last[14], last[15], last[12]].map(|v| SBOX[v as usize]);
+            word[0] ^= rcon;
+            rcon = xtime(rcon);
+            for i in 0..16 {
+                // Each word of the key is the one before it and the same word a round back
+                let before = if i < 4 { word[i] } else { keys[round][i - 4] };
+                keys[round][i] = last[i] ^ before;
+            }
+        }
+        Self { keys }
+    }
+
+    /// Cubes are only listened to, so only the tests seal anything.
+    #[cfg(test)]
+    pub fn encrypt(&self, block: &mut [u8; 16]) {
+        add(block, &self.keys[0]);
+        for round in 1..11 {
+            for v in block.iter_mut() {
+                *v = SBOX[*v as usize];
+            }
+            shift_rows(block);
+            if round < 10 {
+                mix_columns(block, [2, 3, 1, 1]);
+            }
+            add(block, &self.keys[round]);
+        }
+    }
+
+    pub fn decrypt(&self, block: &mut [u8; 16]) {
+        for round in (1..11).rev() {
+            add(block, &self.keys[round]);
+            if round < 10 {
+                mix_columns(block, [14, 11, 13, 9]);
+            }
+            // Shifting each row back by three more is shifting it back to where it was
+            for _ in 0..3 {
+                shift_rows(block);
+            }
+            for v in block.iter_mut() {
+                *v = INV_SBOX[*v as usize];
+            }
+        }
+        add(block, &self.keys[0]);
+    }
+}
+
+fn add(block: &mut [u8; 16], key: &[u8; 16]) {
+    for (v, k) in block.iter_mut().zip(key) {
+        *v ^= k;
+    }
+}
+
+/// Rotate row `r` left by `r` bytes.
+fn shift_rows(block: &mut [u8; 16]) {
+    let old = *block;
+    for c in 0..4 {
+        for r in 1..4 {
+            block[c * 4 + r] = old[(c + r) % 4 * 4 + r];
+        }
+    }
+}
+
+/// Multiply each column by the circulant matrix whose first row is `row`.
+fn mix_columns(block: &mut [u8; 16], row: [u8; 4]) {
+    for column in block.chunks_exact_mut(4) {
+        let old = [column[0], column[1], column[2], column[3]];
+        for (r, v) in column.iter_mut().enumerate() {
+            *v = (0..4).fold(0, |acc, i| acc ^ mul(row[(i + 4 - r) % 4], old[i]));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_the_standards_example() {
+        // FIPS-197, appendix C.1
+        let key = std::array::from_fn(|i| i as u8);
+        let aes = Aes128::new(&key);
+        let mut block = std::array::from_fn(|i| (i * 0x11) as u8);
+        aes.encrypt(&mut block);
+        let expected = [
+            0x69, 0xc4, 0xe0, 0xd8, 0x6a, 0x7b, 0x04, 0x30, 0xd8, 0xcd, 0xb7, 0x80, 0x70, 0xb4,
+            0xc5, 0x5a,
+        ];
+        assert_eq!(block, expected);
+        aes.decrypt(&mut block);
+        assert_eq!(block, std::array::from_fn(|i| (i * 0x11) as u8));
+    }
+}
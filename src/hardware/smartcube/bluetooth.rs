@@ -0,0 +1,135 @@
+//! Bluetooth LE through BlueZ's `gatttool`, enough to find a characteristic and listen to
+//! its notifications.
+
+use std::{
+    error::Error,
+    io,
+    process::{Child, Command, Stdio},
+};
+
+/// Find the first of `uuids` the device at `address` has, and start listening to it,
+/// returning the one found and the `gatttool` printing its notifications.
+pub fn subscribe(address: &str, uuids: &[&str]) -> Result<(String, Child), Box<dyn Error>> {
+    // Cubes use either kind of address, and gatttool can't tell which without being told
+    let mut failed = String::new();
+    for kind in ["public", "random"] {
+        let output = Command::new("gatttool")
+            .args(["-b", address, "-t", kind, "--characteristics"])
+            .stdin(Stdio::null())
+            .output()
+            .map_err(spawn_error)?;
+        if !output.status.success() {
+            failed = String::from_utf8_lossy(&output.stderr).trim().to_string();
+            continue;
+        }
+        let listing = String::from_utf8_lossy(&output.stdout);
+        let Some((uuid, handle, end)) = find(&listing, uuids) else {
+            return Err(format!("{} has none of the characteristics expected", address).into());
+        };
+        // Notifications are turned on by writing 1 to the characteristic's client
+        // configuration descriptor, one of those between its value and the next
+        let output = Command::new("gatttool")
+            .args(["-b", address, "-t", kind, "--char-desc"])
+            .args(["-s", &format!("0x{:04x}", handle + 1)])
+            .args(["-e", &format!("0x{:04x}", end)])
+            .stdin(Stdio::null())
+            .output()
+            .map_err(spawn_error)?;
+        let descriptors = String::from_utf8_lossy(&output.stdout);
+        let Some(config) = client_config(&descriptors) else {
+            return Err(format!("{} can't send notifications for {}", address, uuid).into());
+        };
+        let config = format!("0x{:04x}", config);
+        let child = Command::new("gatttool")
+            .args(["-b", address, "-t", kind, "--char-write-req", "-a", &config])
+            .args(["-n", "0100", "--listen"])
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .map_err(spawn_error)?;
+        return Ok((uuid, child));
+    }
+    Err(format!("can't connect to {}: {}", address, failed).into())
+}
+
+fn spawn_error(err: io::Error) -> String {
+    match err.kind() {
+        io::ErrorKind::NotFound => "gatttool isn't installed".to_string(),
+        _ => format!("gatttool: {}", err),
+    }
+}
+
+/// The first of `uuids` in a `--characteristics` listing, its value's handle, and the
+/// last handle before the next characteristic.
+fn find(listing: &str, uuids: &[&str]) -> Option<(String, u16, u16)> {
+    let hex = |v: &str| u16::from_str_radix(v, 16).ok();
+    let found: Vec<(String, u16, u16)> = listing
+        .lines()
+        .filter_map(|line| {
+            let field = |name: &str| line.split(name).nth(1)?.split(',').next();
+            let declaration = hex(field("handle = 0x")?)?;
+            let handle = hex(field("char value handle = 0x")?)?;
+            let uuid = field("uuid = ")?.trim().to_lowercase();
+            Some((uuid, declaration, handle))
+        })
+        .collect();
+    let (uuid, _, handle) = uuids
+        .iter()
+        .find_map(|uuid| found.iter().find(|v| v.0 == *uuid))?;
+    let next = found.iter().map(|v| v.1).filter(|v| v > handle).min();
+    Some((uuid.clone(), *handle, next.map_or(0xffff, |v| v - 1)))
+}
+
+/// The handle of the client configuration descriptor in a `--char-desc` listing.
+fn client_config(listing: &str) -> Option<u16> {
+    listing.lines().find_map(|line| {
+        let (handle, uuid) = line.strip_prefix("handle = 0x")?.split_once(", uuid = ")?;
+        let config = uuid.trim().to_lowercase() == "00002902-0000-1000-8000-00805f9b34fb";
+        config.then(|| u16::from_str_radix(handle, 16).ok())?
+    })
+}
+
+/// The bytes of a notification line, as the hex `gatttool` prints them.
+pub fn value(line: &str) -> Option<&str> {
+    let (head, bytes) = line.split_once("value:")?;
+    head.starts_with("Notification handle").then_some(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn find_takes_the_first_uuid_listed() {
+        let listing = "\
+handle = 0x0002, char properties = 0x02, char value handle = 0x0003, uuid = 00002a00-0000-1000-8000-00805f9b34fb
+handle = 0x0010, char properties = 0x10, char value handle = 0x0011, uuid = 0000FFF6-0000-1000-8000-00805f9b34fb
+handle = 0x0013, char properties = 0x08, char value handle = 0x0014, uuid = 0000fff5-0000-1000-8000-00805f9b34fb
+";
+        let uuids = [
+            "28be4cb6-cd67-11e9-a32f-2a2ae2dbcce4",
+            "0000fff6-0000-1000-8000-00805f9b34fb",
+        ];
+        let (uuid, handle, end) = find(listing, &uuids).unwrap();
+        assert_eq!((uuid.as_str(), handle, end), (uuids[1], 0x11, 0x12));
+        assert!(find(listing, &uuids[..1]).is_none());
+    }
+
+    #[test]
+    fn client_config_is_found_by_uuid() {
+        let listing = "\
+handle = 0x0012, uuid = 00002901-0000-1000-8000-00805f9b34fb
+handle = 0x0013, uuid = 00002902-0000-1000-8000-00805f9b34fb
+";
+        assert_eq!(client_config(listing), Some(0x13));
+        assert_eq!(client_config(""), None);
+    }
+
+    #[test]
+    fn value_is_a_notifications_bytes() {
+        let line = "Notification handle = 0x0011 value: 0a 1b ff ";
+        assert_eq!(value(line), Some(" 0a 1b ff "));
+        assert_eq!(value("Characteristic value was written successfully"), None);
+    }
+}
@@ -0,0 +1,252 @@
+//! GAN cubes, whose notifications are AES-128 encrypted with a key fixed for the make and
+//! salted with the cube's own Bluetooth address.
+//!
+//! Each 16-byte block is decrypted and then XORed with a fixed IV, the last 16 bytes of a
+//! longer packet first and then the first 16. What's inside depends on the generation,
+//! told apart by which characteristic the cube notifies on: Gen2 cubes send the serial
+//! number of the latest turn with the last seven turns, Gen3 and Gen4 cubes one turn at a
+//! time.
+
+use super::aes::Aes128;
+use super::SmartCube;
+
+const KEY: [u8; 16] = [
+    0x01, 0x02, 0x42, 0x28, 0x31, 0x91, 0x16, 0x07, 0x20, 0x05, 0x18, 0x54, 0x42, 0x11, 0x12, 0x53,
+];
+const IV: [u8; 16] = [
+    0x11, 0x03, 0x32, 0x28, 0x21, 0x01, 0x76, 0x27, 0x20, 0x95, 0x78, 0x14, 0x32, 0x12, 0x02, 0x43,
+];
+const GEN2: &str = "28be4cb6-cd67-11e9-a32f-2a2ae2dbcce4";
+const GEN3: &str = "8653000b-43e6-47b7-9cb0-5fc21d4ae340";
+const GEN4: &str = "0000fff6-0000-1000-8000-00805f9b34fb";
+/// Gen2 turns give a face by its place here.
+const FACES: &str = "URFDLB";
+/// Gen3 and Gen4 turns give a face as one bit of six, in this order of `FACES`.
+const FACE_BITS: [u32; 6] = [2, 32, 8, 1, 16, 4];
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Generation {
+    Two,
+    Three,
+    Four,
+}
+
+pub struct Gan {
+    aes: Aes128,
+    iv: [u8; 16],
+    generation: Generation,
+    /// The serial number of the last turn heard
+    serial: Option<u32>,
+}
+
+impl Gan {
+    /// A cube at the Bluetooth `address`, as `AB:CD:EF:01:23:45`.
+    pub fn new(address: &str) -> Option<Self> {
+        let bytes: Vec<u8> = address
+            .split([':', '-'])
+            .map(|v| u8::from_str_radix(v, 16).ok())
+            .collect::<Option<_>>()?;
+        if bytes.len() != 6 {
+            return None;
+        }
+        let (mut key, mut iv) = (KEY, IV);
+        // The address is salted in lowest byte first; the sum wraps at 255, not 256
+        for (i, salt) in bytes.iter().rev().enumerate() {
+            key[i] = ((key[i] as u16 + *salt as u16) % 0xff) as u8;
+            iv[i] = ((iv[i] as u16 + *salt as u16) % 0xff) as u8;
+        }
+        Some(Self {
+            aes: Aes128::new(&key),
+            iv,
+            generation: Generation::Two,
+            serial: None,
+        })
+    }
+
+    fn decrypt(&self, data: &mut [u8]) -> bool {
+        if data.len() < 16 {
+            return false;
+        }
+        let end = data.len() - 16;
+        if end > 0 {
+            self.decrypt_block(&mut data[end..]);
+        }
+        self.decrypt_block(&mut data[..16]);
+        true
+    }
+
+    fn decrypt_block(&self, data: &mut [u8]) {
+        let mut block = [0; 16];
+        block.copy_from_slice(&data[..16]);
+        self.aes.decrypt(&mut block);
+        for (v, (b, iv)) in data.iter_mut().zip(block.iter().zip(self.iv)) {
+            *v = b ^ iv;
+        }
+    }
+
+    /// Take `serial` as the latest turn's, returning how many turns it is past the last.
+    fn advance(&mut self, serial: u32, wrap: u32) -> u32 {
+        let since = match self.serial {
+            Some(last) => serial.wrapping_sub(last) % wrap,
+            None => 1,
+        };
+        self.serial = Some(serial);
+        since
+    }
+}
+
+/// `len` bits of `data` from bit `start`, the highest bit of each byte first.
+fn bits(data: &[u8], start: usize, len: usize) -> u32 {
+    (start..start + len).fold(0, |acc, i| {
+        let bit = data.get(i / 8).map_or(0, |v| v >> (7 - i % 8) & 1);
+        acc << 1 | bit as u32
+    })
+}
+
+/// The 16-bit little-endian number at byte `at`.
+fn word(data: &[u8], at: usize) -> u32 {
+    bits(data, at * 8, 8) | bits(data, at * 8 + 8, 8) << 8
+}
+
+fn turn(face: Option<usize>, counterclockwise: u32) -> Option<String> {
+    let face = FACES.chars().nth(face?)?;
+    let suffix = ["", "'"][counterclockwise.min(1) as usize];
+    Some(format!("{}{}", face, suffix))
+}
+
+impl SmartCube for Gan {
+    fn decode(&mut self, line: &str) -> Vec<String> {
+        let Some(mut data) = super::hex(line) else {
+            return vec![];
+        };
+        if !self.decrypt(&mut data) {
+            return vec![];
+        }
+        match self.generation {
+            Generation::Two => {
+                if bits(&data, 0, 4) != 0x02 {
+                    return vec![];
+                }
+                // Only the turns since the last packet are new, the oldest of them first
+                let since = self.advance(bits(&data, 4, 8), 256).min(7) as usize;
+                (0..since)
+                    .rev()
+                    .filter_map(|i| {
+                        let face = bits(&data, 12 + 5 * i, 4) as usize;
+                        turn(Some(face), bits(&data, 16 + 5 * i, 1))
+                    })
+                    .collect()
+            }
+            generation => {
+                // Gen3 packets start with a magic byte the Gen4 ones leave out
+                let at = match generation {
+                    Generation::Three if bits(&data, 0, 8) != 0x55 => return vec![],
+                    Generation::Three => 8,
+                    _ => 0,
+                };
+                if bits(&data, at, 8) != 0x01 {
+                    return vec![];
+                }
+                // A repeat of the last turn's packet is no new turn
+                let serial = word(&data, at / 8 + 6);
+                if self.advance(serial, 1 << 16) == 0 {
+                    return vec![];
+                }
+                let direction = bits(&data, at + 64, 2);
+                let face = bits(&data, at + 66, 6);
+                let face = FACE_BITS.iter().position(|&v| v == face);
+                turn(face, direction).into_iter().collect()
+            }
+        }
+    }
+
+    fn characteristics(&self) -> &[&str] {
+        &[GEN2, GEN3, GEN4]
+    }
+
+    fn found(&mut self, uuid: &str) {
+        self.generation = match uuid {
+            GEN3 => Generation::Three,
+            GEN4 => Generation::Four,
+            _ => Generation::Two,
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ADDRESS: &str = "AB:12:CD:34:EF:56";
+
+    /// `plain` sealed as the cube would send it, undoing `Gan::decrypt`.
+    fn seal(cube: &Gan, plain: &[u8]) -> String {
+        let mut data = plain.to_vec();
+        let mut encrypt = |range: std::ops::Range<usize>| {
+            let mut block = [0; 16];
+            for (i, b) in block.iter_mut().enumerate() {
+                *b = data[range.start + i] ^ cube.iv[i];
+            }
+            cube.aes.encrypt(&mut block);
+            data[range].copy_from_slice(&block);
+        };
+        encrypt(0..16);
+        let end = plain.len() - 16;
+        if end > 0 {
+            encrypt(end..end + 16);
+        }
+        data.iter().map(|v| format!("{:02x}", v)).collect()
+    }
+
+    /// Write `len` bits of `value` into `data` from bit `start`, highest first.
+    fn put(data: &mut [u8], start: usize, len: usize, value: u32) {
+        for i in 0..len {
+            let bit = (value >> (len - 1 - i) & 1) as u8;
+            data[(start + i) / 8] |= bit << (7 - (start + i) % 8);
+        }
+    }
+
+    /// A Gen2 turn packet with serial `serial` and the last turns, newest first.
+    fn gen2(serial: u32, turns: &[(u32, u32)]) -> Vec<u8> {
+        let mut data = vec![0; 20];
+        put(&mut data, 0, 4, 0x02);
+        put(&mut data, 4, 8, serial);
+        for (i, &(face, direction)) in turns.iter().enumerate() {
+            put(&mut data, 12 + 5 * i, 4, face);
+            put(&mut data, 16 + 5 * i, 1, direction);
+        }
+        data
+    }
+
+    #[test]
+    fn gen2_turns_since_the_last_packet() {
+        let mut cube = Gan::new(ADDRESS).unwrap();
+        cube.found(GEN2);
+        let first = seal(&cube, &gen2(10, &[(1, 0)]));
+        assert_eq!(cube.decode(&first), ["R"]);
+        // Two turns made before the cube got a packet out
+        let next = seal(&cube, &gen2(12, &[(0, 1), (2, 0), (1, 0)]));
+        assert_eq!(cube.decode(&next), ["F", "U'"]);
+        assert!(cube.decode(&next).is_empty());
+    }
+
+    #[test]
+    fn gen4_turns() {
+        let mut cube = Gan::new(ADDRESS).unwrap();
+        cube.found(GEN4);
+        let mut data = vec![0; 20];
+        put(&mut data, 0, 8, 0x01);
+        put(&mut data, 48, 8, 7);
+        put(&mut data, 64, 2, 1);
+        put(&mut data, 66, 6, 16);
+        let packet = seal(&cube, &data);
+        assert_eq!(cube.decode(&packet), ["L'"]);
+        assert!(cube.decode(&packet).is_empty());
+    }
+
+    #[test]
+    fn needs_a_whole_address() {
+        assert!(Gan::new("AB:12:CD:34:EF").is_none());
+        assert!(Gan::new("not an address").is_none());
+    }
+}
@@ -0,0 +1,45 @@
+//! Giiker cubes, which notify their whole state after every turn as 20 bytes, with the last
+//! few turns at the end. Some models scramble the first 18 with a fixed key, marking it by
+//! `0xa7` in byte 18 and choosing offsets into the key with byte 19.
+
+use super::{hex, SmartCube};
+
+const KEY: [u8; 36] = [
+    176, 81, 104, 224, 86, 137, 237, 119, 38, 26, 193, 161, 210, 126, 150, 81, 93, 13, 236, 249,
+    89, 235, 88, 24, 113, 81, 214, 131, 130, 199, 2, 169, 39, 165, 171, 41,
+];
+/// Each turn is a face numbered from 1 in this order, then how far it turned.
+const FACES: &str = "BDLURF";
+/// Where the state is notified.
+const DATA: &str = "0000aadc-0000-1000-8000-00805f9b34fb";
+
+pub struct Giiker;
+
+impl SmartCube for Giiker {
+    fn decode(&mut self, line: &str) -> Vec<String> {
+        let Some(mut data) = hex(line).filter(|v| v.len() == 20) else {
+            return vec![];
+        };
+        if data[18] == 0xa7 {
+            let (k1, k2) = ((data[19] >> 4) as usize, (data[19] & 0xf) as usize);
+            for (i, byte) in data.iter_mut().take(18).enumerate() {
+                *byte = byte.wrapping_add(KEY[i + k1]).wrapping_add(KEY[i + k2]);
+            }
+        }
+        // Byte 16 is the turn just made
+        let face = FACES.chars().nth((data[16] >> 4).wrapping_sub(1) as usize);
+        let amount = match data[16] & 0xf {
+            1 => "",
+            2 | 9 => "2",
+            3 => "'",
+            _ => return vec![],
+        };
+        face.map(|v| format!("{}{}", v, amount))
+            .into_iter()
+            .collect()
+    }
+
+    fn characteristics(&self) -> &[&str] {
+        &[DATA]
+    }
+}
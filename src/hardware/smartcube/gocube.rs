@@ -0,0 +1,36 @@
+//! GoCube and Rubik's Connected cubes, whose messages are framed as `0x2a`, a length, a
+//! type, the payload, a checksum and `\r\n`. Turn messages carry a byte per turn, then a
+//! byte for the turned center's orientation.
+
+use super::{hex, SmartCube};
+
+/// Turn messages' type.
+const TURN: u8 = 0x01;
+/// Each turn byte is a face in this order, doubled, plus one when it's counterclockwise.
+const FACES: &str = "BFUDRL";
+/// Where messages are notified, the transmitting side of a Nordic UART.
+const TX: &str = "6e400003-b5a3-f393-e0a9-e50e24dc4179";
+
+pub struct GoCube;
+
+impl SmartCube for GoCube {
+    fn decode(&mut self, line: &str) -> Vec<String> {
+        let Some(data) = hex(line) else {
+            return vec![];
+        };
+        if data.len() < 6 || data[0] != 0x2a || !data.ends_with(b"\r\n") || data[2] != TURN {
+            return vec![];
+        }
+        data[3..data.len() - 3]
+            .chunks_exact(2)
+            .filter_map(|v| {
+                let face = FACES.chars().nth((v[0] >> 1) as usize)?;
+                Some(format!("{}{}", face, ["", "'"][(v[0] & 1) as usize]))
+            })
+            .collect()
+    }
+
+    fn characteristics(&self) -> &[&str] {
+        &[TX]
+    }
+}
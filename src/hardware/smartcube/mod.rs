@@ -0,0 +1,294 @@
+//! Smart cubes, which report each turn as it's made.
+//!
+//! A cube is reached over Bluetooth by its address, through BlueZ, or through an external
+//! bridge command that connects to it and prints what it hears, a line at a time. Either
+//! way a protocol turns what the cube sent into turns: GAN, Giiker, GoCube and Rubik's
+//! Connected cubes' notifications, as bytes in hex, or a bridge's own WCA notation.
+
+mod aes;
+mod bluetooth;
+mod gan;
+mod giiker;
+mod gocube;
+
+use crate::scramble::{moves::Move, Net};
+use std::{
+    error::Error,
+    io::{BufRead, BufReader, Read},
+    process::{Child, Command, Stdio},
+    sync::{
+        mpsc::{self, Receiver, Sender, TryRecvError},
+        Arc, Mutex, MutexGuard,
+    },
+    thread,
+    time::Instant,
+};
+
+/// A make of smart cube, by what it sends.
+pub trait SmartCube: Send {
+    /// The turns in one line from the bridge, or one notification in hex, oldest first, in
+    /// WCA notation.
+    fn decode(&mut self, line: &str) -> Vec<String>;
+
+    /// The characteristics the cube might notify its turns on, as UUIDs in the order to
+    /// try them, for reaching it over Bluetooth.
+    fn characteristics(&self) -> &[&str] {
+        &[]
+    }
+
+    /// Told which of the characteristics the cube had, before anything is decoded.
+    fn found(&mut self, _uuid: &str) {}
+}
+
+/// Turns already in WCA notation, one or more to a line.
+struct Notation;
+
+impl SmartCube for Notation {
+    fn decode(&mut self, line: &str) -> Vec<String> {
+        line.split_whitespace().map(str::to_string).collect()
+    }
+}
+
+/// Protocols by the name the config gives them.
+pub const PROTOCOLS: [&str; 5] = ["notation", "gan", "giiker", "gocube", "rubiks-connected"];
+
+/// The protocol called `name`, for a cube at the Bluetooth `address` if it's known.
+pub fn protocol(name: &str, address: Option<&str>) -> Result<Box<dyn SmartCube>, Box<dyn Error>> {
+    Ok(match name {
+        "notation" => Box::new(Notation),
+        // The address is part of the key
+        "gan" => {
+            let address = address.ok_or("a GAN cube needs its address set")?;
+            let cube = gan::Gan::new(address).ok_or_else(|| format!("bad address: {}", address))?;
+            Box::new(cube)
+        }
+        "giiker" => Box::new(giiker::Giiker),
+        // Made by the same company, and they speak the same protocol
+        "gocube" | "rubiks-connected" => Box::new(gocube::GoCube),
+        _ => {
+            let names = PROTOCOLS.join(", ");
+            return Err(format!("unknown smart_cube protocol: {} (pick {})", name, names).into());
+        }
+    })
+}
+
+/// Bytes printed in hex, with or without spaces or colons between them.
+fn hex(line: &str) -> Option<Vec<u8>> {
+    let digits: Vec<u8> = line
+        .bytes()
+        .filter(|v| !v.is_ascii_whitespace() && *v != b':')
+        .collect();
+    if !digits.len().is_multiple_of(2) {
+        return None;
+    }
+    digits
+        .chunks(2)
+        .map(|v| u8::from_str_radix(std::str::from_utf8(v).ok()?, 16).ok())
+        .collect()
+}
+
+/// A turn as the cube reported it.
+pub struct Turn {
+    /// When the cube's report of it arrived
+    pub at: Instant,
+    pub text: String,
+    pub mv: Move,
+}
+
+/// Where a smart cube is between solves.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Stage {
+    /// Waiting for the cube to be turned into the scramble
+    Scrambling,
+    /// Scrambled, so the next turn starts the solve
+    Scrambled,
+    /// Being solved since the first turn
+    Solving(Instant),
+}
+
+/// A smart cube reached over Bluetooth or through its bridge, with its turns read on a
+/// thread of their own.
+pub struct Bridge {
+    /// Each turn, or why the cube can't be heard from any more
+    turns: Receiver<Result<Turn, String>>,
+    /// The cube as it is in the hand, assuming it was solved when the bridge started
+    pub state: Net,
+    pub stage: Stage,
+    /// The turns of the solve under way
+    pub solution: Vec<String>,
+    /// Why the cube can't be heard from, once it can't
+    pub lost: Option<String>,
+    /// The bridge command or `gatttool`, stopped along with the bridge
+    process: Arc<Mutex<Process>>,
+}
+
+impl Bridge {
+    /// Run the bridge `command` with `sh -c`, reading what it prints as `cube` speaks.
+    pub fn listen(command: &str, cube: Box<dyn SmartCube>) -> Result<Self, Box<dyn Error>> {
+        let mut child = Command::new("sh")
+            .args(["-c", command])
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .map_err(|err| format!("{}: {}", command, err))?;
+        let Some(output) = child.stdout.take() else {
+            stop(child);
+            return Err("the smart cube command has no output".into());
+        };
+        let (sender, turns) = mpsc::channel();
+        thread::spawn(move || {
+            read(output, cube, |line| Some(line), &sender);
+            _ = sender.send(Err("the bridge stopped".to_string()));
+        });
+        let bridge = Self::new(turns);
+        lock(&bridge.process).hold(child);
+        Ok(bridge)
+    }
+
+    /// Connect to the cube at the Bluetooth `address` and listen to it as `cube` speaks.
+    /// Connecting takes a few seconds, so it's done on the thread that reads it.
+    pub fn connect(address: &str, mut cube: Box<dyn SmartCube>) -> Result<Self, Box<dyn Error>> {
+        if cube.characteristics().is_empty() {
+            return Err("this protocol is for bridges; set a command to reach the cube".into());
+        }
+        let address = address.to_string();
+        let (sender, turns) = mpsc::channel();
+        let bridge = Self::new(turns);
+        let process = Arc::clone(&bridge.process);
+        thread::spawn(move || {
+            let lost = match bluetooth::subscribe(&address, cube.characteristics()) {
+                Ok((uuid, mut child)) => match child.stdout.take() {
+                    Some(output) => {
+                        lock(&process).hold(child);
+                        cube.found(&uuid);
+                        read(output, cube, bluetooth::value, &sender);
+                        "lost the connection".to_string()
+                    }
+                    None => {
+                        stop(child);
+                        "gatttool has no output".to_string()
+                    }
+                },
+                Err(err) => err.to_string(),
+            };
+            _ = sender.send(Err(lost));
+        });
+        Ok(bridge)
+    }
+
+    fn new(turns: Receiver<Result<Turn, String>>) -> Self {
+        Self {
+            turns,
+            state: Net::solved(3),
+            stage: Stage::Scrambling,
+            solution: vec![],
+            lost: None,
+            process: Arc::default(),
+        }
+    }
+
+    /// The turns made since the last call, oldest first.
+    pub fn poll(&mut self) -> Vec<Turn> {
+        let mut turns = vec![];
+        loop {
+            match self.turns.try_recv() {
+                Ok(Ok(v)) => turns.push(v),
+                Ok(Err(err)) => self.lost = Some(err),
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => {
+                    self.lost.get_or_insert_with(|| "stopped".to_string());
+                    break;
+                }
+            }
+        }
+        turns
+    }
+
+    /// Go back to waiting for the scramble, dropping any solve under way.
+    pub fn restart(&mut self) {
+        self.stage = Stage::Scrambling;
+        self.solution.clear();
+    }
+
+    /// Take the cube in hand to be solved, for when it lost track of a turn.
+    pub fn reset(&mut self) {
+        self.state = Net::solved(3);
+        self.restart();
+    }
+
+    /// What the cube is waiting for, for the Timer panel.
+    pub fn status(&self) -> String {
+        match self.stage {
+            _ if self.lost.is_some() => self.lost.clone().unwrap_or_default(),
+            Stage::Scrambling => "turn it to the scramble".to_string(),
+            Stage::Scrambled => "scrambled, turn to start".to_string(),
+            Stage::Solving(_) => format!("{} turns", self.solution.len()),
+        }
+    }
+}
+
+impl Drop for Bridge {
+    fn drop(&mut self) {
+        lock(&self.process).close();
+    }
+}
+
+/// The process a bridge hears the cube through. Connecting finishes on another thread,
+/// maybe after the bridge is gone, so it's told to stop once it's held if so.
+#[derive(Default)]
+struct Process {
+    child: Option<Child>,
+    closed: bool,
+}
+
+impl Process {
+    fn hold(&mut self, child: Child) {
+        match self.closed {
+            true => stop(child),
+            false => self.child = Some(child),
+        }
+    }
+
+    fn close(&mut self) {
+        self.closed = true;
+        if let Some(child) = self.child.take() {
+            stop(child);
+        }
+    }
+}
+
+/// The process, even if a thread panicked holding it.
+fn lock(process: &Mutex<Process>) -> MutexGuard<'_, Process> {
+    process.lock().unwrap_or_else(|err| err.into_inner())
+}
+
+/// Kill `child` and reap it, so it isn't left running or as a zombie.
+fn stop(mut child: Child) {
+    _ = child.kill();
+    _ = child.wait();
+}
+
+/// Send the turns in each line of `output` that `payload` finds something for, until it
+/// ends or nothing is listening.
+fn read(
+    output: impl Read,
+    mut cube: Box<dyn SmartCube>,
+    payload: fn(&str) -> Option<&str>,
+    sender: &Sender<Result<Turn, String>>,
+) {
+    for line in BufReader::new(output).lines().map_while(Result::ok) {
+        let at = Instant::now();
+        let Some(payload) = payload(&line) else {
+            continue;
+        };
+        for text in cube.decode(payload) {
+            let Some(mv) = Move::parse(&text) else {
+                continue;
+            };
+            if sender.send(Ok(Turn { at, text, mv })).is_err() {
+                return;
+            }
+        }
+    }
+}
@@ -0,0 +1,175 @@
+//! The stackmat's signal decoded from sound, for timers plugged into a microphone or line
+//! in rather than a serial adapter.
+//!
+//! The audio jack carries the same 1200 baud serial bytes as a square wave: the line rests
+//! at one level between packets, each byte starts by leaving it, then its eight bits follow
+//! lowest first (at the resting level for a 1) and a stop bit returns to rest. Sound cards
+//! don't keep a level up for long, so the decoder only goes by which way the wave last
+//! swung, and takes whichever level it rests at longest to be the resting one.
+
+use std::{
+    collections::VecDeque,
+    io::{self, Read},
+};
+
+const BAUD: f32 = 1200.0;
+/// How far the signal must swing, as a share of its recent peak, to count as a new level.
+const SWING: f32 = 0.25;
+
+/// A byte part way through being read.
+struct Frame {
+    /// Samples since the start bit began
+    at: usize,
+    /// Data bits read so far
+    bits: u32,
+    byte: u8,
+}
+
+/// Reads the bytes sent down the line from raw signed 16-bit little-endian mono audio.
+pub struct Decoder<R> {
+    audio: R,
+    per_bit: f32,
+    /// Recent loudest sample, falling away slowly so the threshold follows the volume
+    peak: f32,
+    /// Which way the wave last swung, high or low
+    level: bool,
+    /// Samples the level has held for
+    run: usize,
+    /// The level between bytes, once a long enough rest has been heard
+    idle: Option<bool>,
+    frame: Option<Frame>,
+    /// Half a sample left over from the last read
+    carry: Vec<u8>,
+    decoded: VecDeque<u8>,
+}
+
+impl<R: Read> Decoder<R> {
+    pub fn new(audio: R, rate: u32) -> Self {
+        Self {
+            audio,
+            per_bit: rate as f32 / BAUD,
+            peak: 0.0,
+            level: false,
+            run: 0,
+            idle: None,
+            frame: None,
+            carry: vec![],
+            decoded: VecDeque::new(),
+        }
+    }
+
+    fn sample(&mut self, sample: f32) {
+        // Falls to a third in about a second at the usual rates
+        self.peak = sample.abs().max(self.peak * 0.99997);
+        let threshold = self.peak * SWING;
+        let level = match sample {
+            v if v > threshold => true,
+            v if v < -threshold => false,
+            _ => self.level,
+        };
+        if level == self.level {
+            self.run += 1;
+        } else {
+            self.level = level;
+            self.run = 1;
+        }
+        // Nothing in a byte holds a level for that long, so it's the line at rest
+        if self.run as f32 > self.per_bit * 10.0 {
+            self.idle = Some(level);
+        }
+        let Some(idle) = self.idle else {
+            return;
+        };
+
+        let Some(frame) = self.frame.as_mut() else {
+            if level != idle && self.run == 1 {
+                self.frame = Some(Frame {
+                    at: 0,
+                    bits: 0,
+                    byte: 0,
+                });
+            }
+            return;
+        };
+        frame.at += 1;
+        // Each bit is read at its middle, after the start bit
+        if (frame.at as f32) < (frame.bits as f32 + 1.5) * self.per_bit {
+            return;
+        }
+        if frame.bits < 8 {
+            if level == idle {
+                frame.byte |= 1 << frame.bits;
+            }
+            frame.bits += 1;
+            return;
+        }
+        // A stop bit off the resting level means the byte was misread
+        if level == idle {
+            self.decoded.push_back(frame.byte);
+        }
+        self.frame = None;
+    }
+}
+
+impl<R: Read> Read for Decoder<R> {
+    fn read(&mut self, out: &mut [u8]) -> io::Result<usize> {
+        while self.decoded.is_empty() {
+            let mut raw = [0; 4096];
+            let n = self.audio.read(&mut raw)?;
+            if n == 0 {
+                return Ok(0);
+            }
+            let mut bytes = std::mem::take(&mut self.carry);
+            bytes.extend_from_slice(&raw[..n]);
+            let samples = bytes.chunks_exact(2);
+            self.carry = samples.remainder().to_vec();
+            for sample in samples {
+                self.sample(i16::from_le_bytes([sample[0], sample[1]]) as f32);
+            }
+        }
+        let n = out.len().min(self.decoded.len());
+        for (slot, byte) in out.iter_mut().zip(self.decoded.drain(..n)) {
+            *slot = byte;
+        }
+        Ok(n)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RATE: u32 = 44100;
+
+    /// `bytes` sent as the timer's jack would at `RATE`, resting at `rest` between them.
+    fn signal(bytes: &[u8], rest: i16) -> Vec<u8> {
+        let mut bits = vec![true; 40];
+        for byte in bytes {
+            bits.push(false);
+            bits.extend((0..8).map(|i| byte >> i & 1 == 1));
+            bits.extend([true; 3]);
+        }
+        let per_bit = RATE as f32 / BAUD;
+        let samples = (bits.len() as f32 * per_bit) as usize;
+        (0..samples)
+            .map(|i| match bits[(i as f32 / per_bit) as usize] {
+                true => rest,
+                false => -rest,
+            })
+            .flat_map(i16::to_le_bytes)
+            .collect()
+    }
+
+    #[test]
+    fn decodes_bytes_at_either_resting_level() {
+        let sent = b"S12345O\n\r";
+        for rest in [8000, -8000] {
+            let mut decoded = vec![];
+            let audio = signal(sent, rest);
+            Decoder::new(audio.as_slice(), RATE)
+                .read_to_end(&mut decoded)
+                .unwrap();
+            assert_eq!(decoded, sent);
+        }
+    }
+}
@@ -0,0 +1,232 @@
+//! Speed Stacks timers, read from a serial port such as a stackmat-to-USB cable, or heard
+//! through the sound card from the timer's audio jack.
+//!
+//! The timer sends what it shows over and over at 1200 baud, as a status byte, the time
+//! as digits (one for minutes, two for seconds and two decimals, or three on Gen4 timers),
+//! a checksum of 64 plus the digits' sum and then `\n\r`.
+
+mod audio;
+
+use std::{
+    error::Error,
+    fs::File,
+    io::{self, BufRead, BufReader, Read},
+    path::Path,
+    process::{Command, Stdio},
+    sync::mpsc::{self, Receiver},
+    thread,
+    time::{Duration, Instant},
+};
+
+/// How long the timer can go quiet before it counts as unplugged or switched off.
+const SIGNAL_TIMEOUT: Duration = Duration::from_secs(1);
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum State {
+    /// Reset to zero
+    Idle,
+    /// Both hands on long enough that letting go starts it
+    Ready,
+    /// Both hands on, not yet for long enough
+    HandsOn,
+    LeftHand,
+    RightHand,
+    Running,
+    Stopped,
+}
+
+impl State {
+    fn from_byte(byte: u8) -> Option<State> {
+        Some(match byte {
+            b'I' => State::Idle,
+            b'A' => State::Ready,
+            b'C' => State::HandsOn,
+            b'L' => State::LeftHand,
+            b'R' => State::RightHand,
+            b' ' => State::Running,
+            b'S' => State::Stopped,
+            _ => return None,
+        })
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            State::Idle => "reset",
+            State::Ready => "ready",
+            State::HandsOn => "hands on",
+            State::LeftHand => "left hand",
+            State::RightHand => "right hand",
+            State::Running => "running",
+            State::Stopped => "stopped",
+        }
+    }
+}
+
+/// One reading of the timer's display.
+#[derive(Clone, Copy, Debug)]
+pub struct Packet {
+    pub state: State,
+    pub time: Duration,
+}
+
+impl Packet {
+    /// Read a packet with its `\n\r` taken off, if it's whole and its checksum matches.
+    fn parse(line: &[u8]) -> Option<Packet> {
+        let (&status, rest) = line.split_first()?;
+        let (&check, digits) = rest.split_last()?;
+        if !(5..=6).contains(&digits.len()) || !digits.iter().all(u8::is_ascii_digit) {
+            return None;
+        }
+        let digits: Vec<u64> = digits.iter().map(|v| (v - b'0') as u64).collect();
+        if digits.iter().sum::<u64>() + 64 != check as u64 {
+            return None;
+        }
+        let secs = digits[0] * 60 + digits[1] * 10 + digits[2];
+        let fraction = digits[3..].iter().fold(0, |acc, v| acc * 10 + v);
+        let millis = fraction * 10u64.pow(6 - digits.len() as u32);
+        Some(Packet {
+            state: State::from_byte(status)?,
+            time: Duration::from_secs(secs) + Duration::from_millis(millis),
+        })
+    }
+}
+
+/// A timer being read on a thread of its own.
+pub struct Stackmat {
+    packets: Receiver<Packet>,
+    last: Option<(Instant, State)>,
+}
+
+impl Stackmat {
+    /// A timer on a serial port.
+    pub fn open(path: &Path) -> Result<Self, Box<dyn Error>> {
+        let port = open_port(path).map_err(|err| format!("{}: {}", path.display(), err))?;
+        Ok(Self::read(port))
+    }
+
+    /// A timer plugged into the sound card, heard through the raw signed 16-bit mono
+    /// audio `command` writes at `rate` samples a second.
+    pub fn listen(command: &str, rate: u32) -> Result<Self, Box<dyn Error>> {
+        // A few samples a bit are needed to find its middle
+        if rate < 8000 {
+            return Err("stackmat_audio needs a rate of at least 8000".into());
+        }
+        let child = Command::new("sh")
+            .args(["-c", command])
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .map_err(|err| format!("{}: {}", command, err))?;
+        let audio = child.stdout.ok_or("the audio command has no output")?;
+        Ok(Self::read(audio::Decoder::new(audio, rate)))
+    }
+
+    /// Read packets from `source` on a thread of their own.
+    fn read(source: impl Read + Send + 'static) -> Self {
+        let (sender, packets) = mpsc::channel();
+        thread::spawn(move || {
+            let mut reader = BufReader::new(source);
+            let mut line = vec![];
+            // Stops when the timer goes away or the app stops listening
+            while reader.read_until(b'\r', &mut line).is_ok_and(|v| v > 0) {
+                let packet = line
+                    .strip_suffix(b"\n\r")
+                    .and_then(Packet::parse)
+                    .map(|v| sender.send(v));
+                if let Some(Err(_)) = packet {
+                    return;
+                }
+                line.clear();
+            }
+        });
+        Self {
+            packets,
+            last: None,
+        }
+    }
+
+    /// The packets read since the last call, oldest first.
+    pub fn poll(&mut self) -> Vec<Packet> {
+        let packets: Vec<Packet> = self.packets.try_iter().collect();
+        if let Some(packet) = packets.last() {
+            self.last = Some((Instant::now(), packet.state));
+        }
+        packets
+    }
+
+    /// What the timer last showed, unless it's gone quiet.
+    pub fn state(&self) -> Option<State> {
+        self.last
+            .filter(|(at, _)| at.elapsed() < SIGNAL_TIMEOUT)
+            .map(|(_, state)| state)
+    }
+}
+
+/// Open a serial port raw at the timer's 1200 baud.
+#[cfg(unix)]
+fn open_port(path: &Path) -> io::Result<File> {
+    use std::{fs::OpenOptions, os::unix::fs::OpenOptionsExt, os::unix::io::AsRawFd};
+
+    let file = OpenOptions::new()
+        .read(true)
+        .custom_flags(libc::O_NOCTTY)
+        .open(path)?;
+    let fd = file.as_raw_fd();
+    // SAFETY: `fd` is open for as long as `file` is, and `termios` is plain data
+    unsafe {
+        let mut termios: libc::termios = std::mem::zeroed();
+        if libc::tcgetattr(fd, &mut termios) != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        libc::cfmakeraw(&mut termios);
+        termios.c_cflag |= libc::CLOCAL | libc::CREAD;
+        libc::cfsetispeed(&mut termios, libc::B1200);
+        if libc::tcsetattr(fd, libc::TCSANOW, &termios) != 0 {
+            return Err(io::Error::last_os_error());
+        }
+    }
+    Ok(file)
+}
+
+#[cfg(not(unix))]
+fn open_port(_: &Path) -> io::Result<File> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "stackmat timers can only be read on Unix",
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A packet as the timer sends it, without the `\n\r`.
+    fn frame(status: u8, digits: &str) -> Vec<u8> {
+        let sum: u32 = digits.bytes().map(|v| (v - b'0') as u32).sum();
+        let mut line = vec![status];
+        line.extend(digits.bytes());
+        line.push((64 + sum) as u8);
+        line
+    }
+
+    #[test]
+    fn parse_reads_five_and_six_digit_packets() {
+        let packet = Packet::parse(&frame(b'S', "12345")).unwrap();
+        assert_eq!(packet.state, State::Stopped);
+        assert_eq!(packet.time, Duration::from_millis(83_450));
+        let packet = Packet::parse(&frame(b' ', "012345")).unwrap();
+        assert_eq!(packet.state, State::Running);
+        assert_eq!(packet.time, Duration::from_millis(12_345));
+    }
+
+    #[test]
+    fn parse_refuses_damaged_packets() {
+        let mut line = frame(b'S', "12345");
+        line[3] = b'4';
+        assert!(Packet::parse(&line).is_none());
+        assert!(Packet::parse(&frame(b'S', "1234")).is_none());
+        assert!(Packet::parse(&frame(b'X', "12345")).is_none());
+        assert!(Packet::parse(&[]).is_none());
+    }
+}
@@ -0,0 +1,127 @@
+use super::Session;
+use crate::app::{Penalty, Time};
+use serde_json::Value;
+use std::{error::Error, fs, path::Path};
+
+/// Parse a csTimer export file into its sessions, ordered by session number.
+pub fn read(path: &Path) -> Result<Vec<Session>, Box<dyn Error>> {
+    parse(&fs::read_to_string(path)?)
+}
+
+fn parse(data: &str) -> Result<Vec<Session>, Box<dyn Error>> {
+    let root: Value = serde_json::from_str(data)?;
+    let root = root.as_object().ok_or("not a csTimer export")?;
+
+    // Session names live in a JSON string nested inside `properties`
+    let names: Value = root
+        .get("properties")
+        .and_then(|v| v.get("sessionData"))
+        .and_then(|v| v.as_str())
+        .and_then(|v| serde_json::from_str(v).ok())
+        .unwrap_or(Value::Null);
+
+    let mut sessions: Vec<(u32, Session)> = vec![];
+    for (key, solves) in root {
+        let number = match key
+            .strip_prefix("session")
+            .and_then(|v| v.parse::<u32>().ok())
+        {
+            Some(v) => v,
+            None => continue,
+        };
+        let name = names
+            .get(number.to_string())
+            .and_then(|v| v.get("name"))
+            .map(|v| match v {
+                Value::String(s) => s.clone(),
+                other => other.to_string(),
+            })
+            .unwrap_or_else(|| number.to_string());
+        let times = solves
+            .as_array()
+            .ok_or_else(|| format!("{} is not a list of solves", key))?
+            .iter()
+            .filter_map(parse_solve)
+            .collect();
+        sessions.push((
+            number,
+            Session {
+                id: number.to_string(),
+                name,
+                times,
+            },
+        ));
+    }
+    sessions.sort_by_key(|v| v.0);
+    Ok(sessions.into_iter().map(|v| v.1).collect())
+}
+
+/// A solve is `[[penalty, ms], scramble, comment, unix_seconds, ...]`.
+fn parse_solve(solve: &Value) -> Option<Time> {
+    let result = solve.get(0)?;
+    let penalty = match result.get(0)?.as_i64()? {
+        0 => Penalty::None,
+        -1 => Penalty::Dnf,
+        _ => Penalty::PlusTwo,
+    };
+    let ms = result.get(1)?.as_f64()?;
+
+    let mut time = Time::from((ms / 1000.0) as f32);
+    time.penalty = penalty;
+    time.scramble = solve
+        .get(1)
+        .and_then(|v| v.as_str())
+        .unwrap_or("")
+        .to_string();
+    time.date = solve.get(3).and_then(|v| v.as_i64());
+    Some(time)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXPORT: &str = r#"{
+        "session2": [[[0, 9870], "R U R' U'", "", 1700000100]],
+        "session1": [
+            [[0, 12340], "R U F", "", 1700000000],
+            [[2000, 10500], "L D B", "fumbled", 1700000050],
+            [[-1, 15000], "", "", 1700000060],
+            "not a solve"
+        ],
+        "properties": {
+            "sessionData": "{\"1\":{\"name\":\"main\"},\"2\":{\"name\":404}}"
+        }
+    }"#;
+
+    #[test]
+    fn parse_reads_sessions_in_order_with_penalties() {
+        let sessions = parse(EXPORT).unwrap();
+        let names: Vec<(&str, &str)> = sessions
+            .iter()
+            .map(|v| (v.id.as_str(), v.name.as_str()))
+            .collect();
+        assert_eq!(names, [("1", "main"), ("2", "404")]);
+
+        let solves: Vec<(f32, Penalty, Option<i64>)> = sessions[0]
+            .times
+            .iter()
+            .map(|v| (v.time, v.penalty, v.date))
+            .collect();
+        assert_eq!(
+            solves,
+            [
+                (12.34, Penalty::None, Some(1700000000)),
+                (10.5, Penalty::PlusTwo, Some(1700000050)),
+                (15.0, Penalty::Dnf, Some(1700000060)),
+            ]
+        );
+        assert_eq!(sessions[0].times[1].scramble, "L D B");
+    }
+
+    #[test]
+    fn parse_refuses_anything_but_an_object() {
+        assert!(parse("[]").is_err());
+        assert!(parse(r#"{ "session1": 3 }"#).is_err());
+    }
+}
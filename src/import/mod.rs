@@ -0,0 +1,70 @@
+pub mod cstimer;
+pub mod plain;
+pub mod twisty;
+
+use crate::app::{Penalty, Time};
+
+/// A named group of solves found in an import file.
+pub struct Session {
+    /// What the user types to pick this session
+    pub id: String,
+    pub name: String,
+    pub times: Vec<Time>,
+}
+
+/// Choose which session to take from a file that may hold several.
+pub fn pick(mut sessions: Vec<Session>, want: Option<&str>) -> Result<Session, String> {
+    sessions.retain(|v| !v.times.is_empty());
+    match want {
+        None if sessions.len() == 1 => Ok(sessions.remove(0)),
+        None if sessions.is_empty() => Err("no solves found".to_string()),
+        None => {
+            let names: Vec<String> = sessions
+                .iter()
+                .map(|v| match v.id == v.name {
+                    true => v.id.clone(),
+                    false => format!("{} ({})", v.id, v.name),
+                })
+                .collect();
+            Err(format!("pick a session: {}", names.join(", ")))
+        }
+        Some(want) => {
+            let pos = sessions
+                .iter()
+                .position(|v| v.id == want || v.name == want)
+                .ok_or_else(|| format!("no session named {}", want))?;
+            Ok(sessions.remove(pos))
+        }
+    }
+}
+
+/// Parse a typed or exported time such as `12.34`, `1:02.34`, `12.34+` or `DNF`.
+///
+/// The returned time never includes a +2; that's carried by the penalty.
+pub fn parse_time(s: &str) -> Option<(f32, Penalty)> {
+    let s = s.trim();
+    if s.eq_ignore_ascii_case("dnf") {
+        return Some((0.0, Penalty::Dnf));
+    }
+    // csTimer and friends write DNFs with the raw time in brackets
+    if let Some(inner) = s
+        .strip_prefix("DNF(")
+        .or_else(|| s.strip_prefix("dnf("))
+        .and_then(|v| v.strip_suffix(')'))
+    {
+        return Some((parse_seconds(inner)?, Penalty::Dnf));
+    }
+    match s.strip_suffix('+') {
+        Some(raw) => Some(((parse_seconds(raw)? - 2.0).max(0.0), Penalty::PlusTwo)),
+        None => Some((parse_seconds(s)?, Penalty::None)),
+    }
+}
+
+/// Parse `ss.xx`, `m:ss.xx` or `h:mm:ss.xx` into seconds.
+fn parse_seconds(s: &str) -> Option<f32> {
+    let mut total = 0.0;
+    for part in s.trim().split(':') {
+        total = total * 60.0 + part.parse::<f32>().ok()?;
+    }
+    (total >= 0.0).then_some(total)
+}
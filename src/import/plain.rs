@@ -0,0 +1,54 @@
+use super::parse_time;
+use crate::app::Time;
+
+/// Parse a comma or newline separated list of times, returning the solves and how many
+/// entries couldn't be read.
+pub fn parse(text: &str) -> (Vec<Time>, usize) {
+    let mut times = vec![];
+    let mut skipped = 0;
+    for entry in text
+        .split([',', '\n'])
+        .map(str::trim)
+        .filter(|v| !v.is_empty())
+    {
+        // Averages are often pasted with the trimmed solves in brackets
+        let entry = match entry.strip_prefix('(').and_then(|v| v.strip_suffix(')')) {
+            Some(v) => v,
+            None => entry,
+        };
+        match parse_time(entry) {
+            Some((secs, penalty)) => {
+                let mut time = Time::from(secs);
+                time.penalty = penalty;
+                times.push(time);
+            }
+            None => skipped += 1,
+        }
+    }
+    (times, skipped)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::Penalty;
+
+    #[test]
+    fn parse_reads_a_pasted_list() {
+        let text = "12.34, 1:02.50\n(9.80)\n\n14.00+, DNF(11.2), dnf, fast, -3";
+        let (times, skipped) = parse(text);
+        let found: Vec<(f32, Penalty)> = times.iter().map(|v| (v.time, v.penalty)).collect();
+        assert_eq!(
+            found,
+            [
+                (12.34, Penalty::None),
+                (62.5, Penalty::None),
+                (9.8, Penalty::None),
+                (12.0, Penalty::PlusTwo),
+                (11.2, Penalty::Dnf),
+                (0.0, Penalty::Dnf),
+            ]
+        );
+        assert_eq!(skipped, 2);
+    }
+}
@@ -0,0 +1,156 @@
+use super::{parse_time, Session};
+use crate::app::{Penalty, Time};
+use chrono::DateTime;
+use std::{error::Error, fs, path::Path};
+
+/// Parse a Twisty Timer file.
+///
+/// Full backups have `puzzle;category;ms;date ms;scramble;penalty;comment` rows and are
+/// split into one session per puzzle and category. The shorter "export to external"
+/// format has `time;scramble;date` rows and becomes a single session.
+pub fn read(path: &Path) -> Result<Vec<Session>, Box<dyn Error>> {
+    Ok(parse(&fs::read_to_string(path)?))
+}
+
+fn parse(data: &str) -> Vec<Session> {
+    let mut sessions: Vec<Session> = vec![];
+    for line in data.lines().filter(|v| !v.trim().is_empty()) {
+        let fields = split(line);
+        let (id, time) = match fields.len() {
+            n if n >= 6 => match backup_row(&fields) {
+                Some(v) => v,
+                None => continue,
+            },
+            n if n >= 3 => match external_row(&fields) {
+                Some(v) => ("twisty".to_string(), v),
+                None => continue,
+            },
+            _ => continue,
+        };
+        match sessions.iter_mut().find(|v| v.id == id) {
+            Some(session) => session.times.push(time),
+            None => sessions.push(Session {
+                id: id.clone(),
+                name: id,
+                times: vec![time],
+            }),
+        }
+    }
+    sessions
+}
+
+fn backup_row(fields: &[String]) -> Option<(String, Time)> {
+    // The header row fails here along with anything else malformed
+    let ms = fields[2].parse::<f64>().ok()?;
+    let date_ms = fields[3].parse::<i64>().ok()?;
+    let mut time = Time::from((ms / 1000.0) as f32);
+    time.scramble = fields[4].clone();
+    time.penalty = match fields[5].as_str() {
+        "1" => Penalty::PlusTwo,
+        "2" => Penalty::Dnf,
+        _ => Penalty::None,
+    };
+    time.date = Some(date_ms / 1000);
+    Some((format!("{}/{}", fields[0], fields[1]), time))
+}
+
+fn external_row(fields: &[String]) -> Option<Time> {
+    let (secs, penalty) = parse_time(&fields[0])?;
+    let mut time = Time::from(secs);
+    time.penalty = penalty;
+    time.scramble = fields[1].clone();
+    time.date = DateTime::parse_from_rfc3339(&fields[2])
+        .ok()
+        .map(|v| v.timestamp());
+    // Some exports add the penalty as a fourth column
+    match fields.get(3).map(|v| v.as_str()) {
+        Some("+2") => time.penalty = Penalty::PlusTwo,
+        Some("DNF") => time.penalty = Penalty::Dnf,
+        _ => (),
+    }
+    Some(time)
+}
+
+/// Split a `;`-separated row, honouring double-quoted fields.
+fn split(line: &str) -> Vec<String> {
+    let mut fields = vec![];
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => quoted = !quoted,
+            ';' if !quoted => fields.push(std::mem::take(&mut field)),
+            c => field.push(c),
+        }
+    }
+    fields.push(field);
+    fields.iter().map(|v| v.trim().to_string()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    type Solve = (f32, Penalty, Option<i64>);
+
+    fn solves(session: &Session) -> Vec<Solve> {
+        session
+            .times
+            .iter()
+            .map(|v| (v.time, v.penalty, v.date))
+            .collect()
+    }
+
+    #[test]
+    fn parse_splits_a_backup_by_puzzle_and_category() {
+        let backup = "\
+Puzzle;Category;Time(millis);Date(millis);Scramble;Penalty;Comment
+\"333\";\"Normal\";\"11230\";\"1700000000000\";\"R U R'\";\"0\";\"\"
+\"333\";\"OH\";\"20500\";\"1700000060000\";\"F \"\"x\"\"\";\"1\";\"\"
+\"333\";\"Normal\";\"13000\";\"1700000120000\";\"L D\";\"2\";\"a; comment\"
+";
+        let sessions = parse(backup);
+        let ids: Vec<&str> = sessions.iter().map(|v| v.id.as_str()).collect();
+        assert_eq!(ids, ["333/Normal", "333/OH"]);
+        assert_eq!(
+            solves(&sessions[0]),
+            [
+                (11.23, Penalty::None, Some(1700000000)),
+                (13.0, Penalty::Dnf, Some(1700000120)),
+            ]
+        );
+        assert_eq!(
+            solves(&sessions[1]),
+            [(20.5, Penalty::PlusTwo, Some(1700000060))]
+        );
+        assert_eq!(sessions[1].times[0].scramble, "F \"x\"");
+    }
+
+    #[test]
+    fn parse_reads_an_external_export_as_one_session() {
+        let export = "\
+12.34;R U;2023-11-14T22:13:20.000+00:00
+14.00+;F D;2023-11-14T22:14:20.000+00:00
+DNF(9.99);B L;2023-11-14T22:15:20.000+00:00
+10.00;U2;not a date;DNF
+nonsense
+";
+        let sessions = parse(export);
+        assert_eq!(sessions.len(), 1);
+        assert_eq!(sessions[0].id, "twisty");
+        assert_eq!(
+            solves(&sessions[0]),
+            [
+                (12.34, Penalty::None, Some(1700000000)),
+                (12.0, Penalty::PlusTwo, Some(1700000060)),
+                (9.99, Penalty::Dnf, Some(1700000120)),
+                (10.0, Penalty::Dnf, None),
+            ]
+        );
+    }
+}
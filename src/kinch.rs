@@ -0,0 +1,165 @@
+//! A Kinch score, to weigh practice spread over many events: each event's PB as a share of
+//! its world record, at most 100, and their mean over every event, counting those never
+//! practiced as 0.
+//!
+//! Events go by what the WCA ranks them by, as kinchranks.com does: the average of 5 for
+//! most, the mean of 3 for 6x6 and 7x7, whichever of the single and mean does better for
+//! 3BLD and FMC, and points with the time left over for multi-blind. Each event's PBs are
+//! the best of every session for it.
+
+use super::app::{Multiblind, Time, Variation};
+use super::format::TimeFormat;
+use super::scramble::Event;
+use super::storage::Summary;
+use ordered_float::OrderedFloat;
+
+/// What an event is ranked by, with its world record.
+enum Ranked {
+    Average(f32),
+    Mean(f32),
+    /// Blindfolded and fewest moves: the single, then the mean
+    Blind(f32, f32),
+    /// Multi-blind: the points, then the seconds taken
+    Points(i64, f32),
+}
+
+/// What `event` done with `variation` is ranked by, with its world record as it stood when
+/// these were last brought up to date, in early 2025, if it's a WCA event. 4BLD and 5BLD
+/// can't be timed here, so they're left out of the mean rather than counting as 0.
+fn ranked(event: Event, variation: Variation) -> Option<Ranked> {
+    Some(match (event, variation) {
+        (Event::Cube3, Variation::OneHanded) => Ranked::Average(8.09),
+        (_, Variation::OneHanded | Variation::Feet) => return None,
+        (Event::Cube3, _) => Ranked::Average(3.90),
+        (Event::Cube2, _) => Ranked::Average(0.78),
+        (Event::Cube4, _) => Ranked::Average(19.38),
+        (Event::Cube5, _) => Ranked::Average(32.82),
+        (Event::Cube6, _) => Ranked::Mean(65.63),
+        (Event::Cube7, _) => Ranked::Mean(95.26),
+        (Event::Pyraminx, _) => Ranked::Average(1.27),
+        (Event::Megaminx, _) => Ranked::Average(23.98),
+        (Event::Skewb, _) => Ranked::Average(1.36),
+        (Event::Square1, _) => Ranked::Average(4.54),
+        (Event::Clock, _) => Ranked::Average(2.29),
+        (Event::Cube3Blind, _) => Ranked::Blind(12.00, 14.05),
+        (Event::Fmc, _) => Ranked::Blind(16.0, 20.0),
+        (Event::Multiblind, _) => Ranked::Points(59, 3467.0),
+        (Event::Relay234 | Event::Relay27, _) => return None,
+    })
+}
+
+/// An hour, which multi-blind's time left over is taken from.
+const HOUR: f32 = 60.0 * 60.0;
+
+/// One event's part in the score.
+pub struct Part {
+    pub name: &'static str,
+    /// The PB it was scored by, as written, with the kind of result it is
+    pub pb: Option<(&'static str, String)>,
+    /// Out of 100
+    pub score: f32,
+}
+
+pub struct Kinch {
+    pub parts: Vec<Part>,
+    /// The mean of the parts, out of 100
+    pub score: f32,
+}
+
+impl Kinch {
+    /// The score over `sessions`, each a session's event and solves.
+    pub fn of(sessions: &[(Event, Vec<Time>)], format: &TimeFormat) -> Self {
+        let mut parts = vec![];
+        for event in Event::ALL {
+            for variation in [Variation::Normal, Variation::OneHanded] {
+                let Some(ranked) = ranked(event, variation) else {
+                    continue;
+                };
+                let times: Vec<&[Time]> = sessions
+                    .iter()
+                    .filter(|(v, _)| *v == event)
+                    .map(|(_, times)| times.as_slice())
+                    .collect();
+                let (pb, share) = match score(&ranked, &times, variation, format) {
+                    Some((pb, share)) => (Some(pb), share),
+                    None => (None, 0.0),
+                };
+                parts.push(Part {
+                    name: match variation {
+                        Variation::Normal => event.as_str(),
+                        v => v.as_str(),
+                    },
+                    pb,
+                    score: (share * 100.0).min(100.0),
+                });
+            }
+        }
+        let score = parts.iter().map(|v| v.score).sum::<f32>() / parts.len() as f32;
+        Self { parts, score }
+    }
+}
+
+/// The best of each session's bests.
+fn bests(sessions: &[&[Time]], variation: Variation) -> Summary {
+    let summaries: Vec<Summary> = sessions
+        .iter()
+        .map(|v| Summary::from_times(v, variation))
+        .collect();
+    let best = |f: fn(&Summary) -> Option<OrderedFloat<f32>>| {
+        let found = summaries.iter().filter_map(f);
+        found.filter(|v| v.is_finite()).min()
+    };
+    Summary {
+        pbsingle: best(|v| v.pbsingle),
+        pbmo3: best(|v| v.pbmo3),
+        pbao5: best(|v| v.pbao5),
+        ..Summary::default()
+    }
+}
+
+/// The PB an event is ranked by over `sessions`, written with what kind it is, and its
+/// share of the record.
+fn score(
+    ranked: &Ranked,
+    sessions: &[&[Time]],
+    variation: Variation,
+    format: &TimeFormat,
+) -> Option<((&'static str, String), f32)> {
+    if let Ranked::Points(points, secs) = *ranked {
+        let (result, time) = best_multiblind(sessions)?;
+        let share = left_over(result.points(), time.value()) / left_over(points, secs);
+        return Some((("result", format.single(time)), share));
+    }
+    let bests = bests(sessions, variation);
+    let share = |kind: &'static str, record: f32, pb: Option<OrderedFloat<f32>>| {
+        pb.map(|pb| ((kind, format.average(Some(pb), "")), record / pb.0))
+    };
+    match *ranked {
+        Ranked::Average(record) => share("ao5", record, bests.pbao5),
+        Ranked::Mean(record) => share("mo3", record, bests.pbmo3),
+        Ranked::Blind(single, mean) => {
+            let single = share("single", single, bests.pbsingle);
+            let mean = share("mo3", mean, bests.pbmo3);
+            // Whichever is the nearer to its record
+            let both = single.into_iter().chain(mean);
+            both.max_by(|a, b| a.1.total_cmp(&b.1))
+        }
+        Ranked::Points(..) => None,
+    }
+}
+
+/// The best multi-blind attempt over every session, as the WCA ranks them.
+fn best_multiblind<'a>(sessions: &[&'a [Time]]) -> Option<(Multiblind, &'a Time)> {
+    sessions
+        .iter()
+        .flat_map(|v| v.iter())
+        .filter_map(|v| Some((v.multiblind.filter(Multiblind::counts)?, v)))
+        .filter(|(_, v)| v.value().is_finite())
+        .min_by_key(|(result, v)| result.code(v.value()))
+}
+
+/// Points with the share of the hour left over, so of two results with as many points the
+/// quicker scores higher.
+fn left_over(points: i64, secs: f32) -> f32 {
+    points as f32 + (HOUR - secs).max(0.0) / HOUR
+}
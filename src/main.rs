@@ -1,31 +1,106 @@
 mod ui;
 mod app;
-mod cube;
+mod attempt;
+mod backup;
+mod clipboard;
+mod command;
+mod compare;
+mod config;
+mod custom;
+mod drill;
+mod export;
+mod format;
+mod hardware;
+mod import;
+mod kinch;
+mod metronome;
+mod pb;
+mod records;
+mod scramble;
+mod session;
+mod sound;
+mod stats;
+mod storage;
+mod streak;
+#[cfg(feature = "sync")]
+mod sync;
+use config::Config;
 use crossterm::{
-    event::{DisableMouseCapture, EnableMouseCapture},
+    event::{DisableBracketedPaste, DisableMouseCapture, EnableBracketedPaste, EnableMouseCapture},
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
-use std::{error::Error, io};
+use std::{
+    env,
+    error::Error,
+    io::{self, IsTerminal, Read},
+    path::PathBuf,
+    process,
+};
 use tui::{backend::CrosstermBackend, Terminal};
 
+const USAGE: &str = "usage: cube-tui [--data-dir <path>]";
+
 fn main() -> Result<(), Box<dyn Error>> {
+    let mut config = Config::load(&config::config_path()?)?;
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--data-dir" => match args.next() {
+                Some(v) => config.data_dir = Some(PathBuf::from(v)),
+                None => {
+                    eprintln!("--data-dir needs a path\n{}", USAGE);
+                    process::exit(2);
+                }
+            },
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                return Ok(());
+            }
+            other => {
+                eprintln!("unknown argument: {}\n{}", other, USAGE);
+                process::exit(2);
+            }
+        }
+    }
+
+    // Ask for the passphrase before the terminal is taken over
+    let cipher = match config.encrypt {
+        true => Some(storage::Cipher::unlock(&config.data_dir()?)?),
+        false => None,
+    };
+
+    // A list of times piped in is appended to the session
+    let piped = if io::stdin().is_terminal() {
+        None
+    } else {
+        let mut text = String::new();
+        io::stdin().read_to_string(&mut text)?;
+        Some(text)
+    };
+
     // setup terminal
     enable_raw_mode()?;
     let mut stdout = io::stdout();
-    execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
+    execute!(
+        stdout,
+        EnterAlternateScreen,
+        EnableMouseCapture,
+        EnableBracketedPaste
+    )?;
     let backend = CrosstermBackend::new(stdout);
     let mut terminal = Terminal::new(backend)?;
 
     // create app and run it
-    let res = ui::run(&mut terminal);
+    let res = ui::run(&mut terminal, config, cipher, piped);
 
     // restore terminal
     disable_raw_mode()?;
     execute!(
         terminal.backend_mut(),
         LeaveAlternateScreen,
-        DisableMouseCapture
+        DisableMouseCapture,
+        DisableBracketedPaste
     )?;
     terminal.show_cursor()?;
 
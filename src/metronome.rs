@@ -0,0 +1,60 @@
+//! A metronome kept while the timer runs, for practicing turning at a steady pace.
+
+use super::config::MetronomeConfig;
+use super::sound;
+use std::{error::Error, time::Duration};
+
+/// How long the Timer panel stays lit after each beat, or half the beat if that's shorter.
+const FLASH: Duration = Duration::from_millis(100);
+
+pub struct Metronome {
+    /// Time between beats
+    beat: Duration,
+    sound: bool,
+    flash: bool,
+    /// Beats since the solve started that have been sounded
+    beats: u32,
+}
+
+impl Metronome {
+    pub fn new(config: &MetronomeConfig) -> Result<Self, Box<dyn Error>> {
+        if !(1..=600).contains(&config.bpm) {
+            return Err("metronome: bpm must be 1 to 600".into());
+        }
+        if !config.sound && !config.flash {
+            return Err("metronome: turn on sound, flash or both".into());
+        }
+        Ok(Self {
+            beat: Duration::from_secs(60) / config.bpm,
+            sound: config.sound,
+            flash: config.flash,
+            beats: 0,
+        })
+    }
+
+    /// Sound any beat due by `elapsed` into the solve. Beats that came and went unheard,
+    /// say while a device was read, are let go rather than rung all at once.
+    pub fn poll(&mut self, elapsed: Duration) -> Result<(), Box<dyn Error>> {
+        let beats = (elapsed.as_nanos() / self.beat.as_nanos()) as u32;
+        if beats <= self.beats {
+            return Ok(());
+        }
+        self.beats = beats;
+        match self.sound {
+            true => sound::bell(),
+            false => Ok(()),
+        }
+    }
+
+    /// Start counting again from the next solve.
+    pub fn reset(&mut self) {
+        self.beats = 0;
+    }
+
+    /// Whether the Timer panel is lit for a beat `elapsed` into the solve.
+    pub fn flashing(&self, elapsed: Duration) -> bool {
+        let lit = FLASH.min(self.beat / 2);
+        let into = elapsed.as_nanos() % self.beat.as_nanos();
+        self.flash && elapsed >= self.beat && into < lit.as_nanos()
+    }
+}
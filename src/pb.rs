@@ -0,0 +1,118 @@
+//! Personal bests in the order they were set, to show how a session's bests came down.
+//!
+//! They're worked out again from the solves rather than kept, so a solve deleted or given
+//! a penalty afterwards takes any best it set with it. New solves carry the history on.
+
+use super::app::{trimmed_mean, Time, Variation};
+use ordered_float::OrderedFloat;
+use std::collections::VecDeque;
+
+/// The values most recently seen, enough for the longest stat.
+const KEPT: usize = 100;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Stat {
+    Single,
+    Ao5,
+    Ao12,
+    Ao100,
+}
+
+impl Stat {
+    pub const ALL: [Stat; 4] = [Stat::Single, Stat::Ao5, Stat::Ao12, Stat::Ao100];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Stat::Single => "single",
+            Stat::Ao5 => "ao5",
+            Stat::Ao12 => "ao12",
+            Stat::Ao100 => "ao100",
+        }
+    }
+
+    /// The stat ending at the last of `values`, once there are enough of them.
+    fn latest(&self, values: &[f32]) -> Option<OrderedFloat<f32>> {
+        let n = match self {
+            Stat::Single => return values.last().copied().map(OrderedFloat),
+            Stat::Ao5 => 5,
+            Stat::Ao12 => 12,
+            Stat::Ao100 => 100,
+        };
+        (values.len() >= n).then(|| trimmed_mean(&values[values.len() - n..]))
+    }
+}
+
+/// A best beaten, or the first of its kind.
+pub struct Pb {
+    pub stat: Stat,
+    pub variation: Variation,
+    pub value: OrderedFloat<f32>,
+    /// The best it beat
+    pub previous: Option<OrderedFloat<f32>>,
+    /// Where the solve that set it is in the session, oldest first
+    pub solve: usize,
+    pub date: Option<i64>,
+}
+
+/// Every best set so far, each variation's apart. DNFs never set one, and matching a best
+/// doesn't beat it.
+#[derive(Default)]
+pub struct History {
+    seen: Vec<Seen>,
+    pbs: Vec<Pb>,
+    /// Solves gone over
+    count: usize,
+}
+
+/// A variation's latest values and its best of each stat.
+type Seen = (Variation, VecDeque<f32>, [Option<OrderedFloat<f32>>; 4]);
+
+impl History {
+    /// The bests set over `times`, oldest first.
+    pub fn of(times: &[Time]) -> Self {
+        let mut history = Self::default();
+        for time in times {
+            history.push(time);
+        }
+        history
+    }
+
+    pub fn pbs(&self) -> &[Pb] {
+        &self.pbs
+    }
+
+    /// Go on to `time`, the newest solve.
+    pub fn push(&mut self, time: &Time) {
+        let at = match self.seen.iter().position(|v| v.0 == time.variation) {
+            Some(at) => at,
+            None => {
+                self.seen.push((time.variation, VecDeque::new(), [None; 4]));
+                self.seen.len() - 1
+            }
+        };
+        let (_, values, bests) = &mut self.seen[at];
+        values.push_back(time.value());
+        if values.len() > KEPT {
+            values.pop_front();
+        }
+        let values = values.make_contiguous();
+        for (stat, best) in Stat::ALL.into_iter().zip(bests.iter_mut()) {
+            let Some(value) = stat.latest(values).filter(|v| v.is_finite()) else {
+                continue;
+            };
+            if best.is_some_and(|v| value >= v) {
+                continue;
+            }
+            self.pbs.push(Pb {
+                stat,
+                variation: time.variation,
+                value,
+                previous: *best,
+                solve: self.count,
+                date: time.date,
+            });
+            *best = Some(value);
+        }
+        self.count += 1;
+    }
+}
@@ -0,0 +1,93 @@
+//! All-time bests: the fastest of each stat over every session of an event, so a record
+//! set in one session still shows from another.
+//!
+//! Other sessions' bests are worked out once when asked for, from every solve of each,
+//! like a comparison; the current session's are taken as they stand whenever shown.
+
+use super::app::Variation;
+use super::scramble::Event;
+use super::storage::Summary;
+use ordered_float::OrderedFloat;
+
+/// One of a session's bests.
+pub type Stat = fn(&Summary) -> Option<OrderedFloat<f32>>;
+
+/// The stats kept a record of, by name, as the Bests row has them.
+pub const STATS: [(&str, Stat); 7] = [
+    ("single", |v| v.pbsingle),
+    ("mo3", |v| v.pbmo3),
+    ("ao5", |v| v.pbao5),
+    ("ao12", |v| v.pbao12),
+    ("ao50", |v| v.pbao50),
+    ("ao100", |v| v.pbao100),
+    ("ao1000", |v| v.pbao1000),
+];
+
+/// A stat's fastest, and the session it was done in.
+pub struct Record {
+    pub value: OrderedFloat<f32>,
+    pub session: String,
+}
+
+pub struct Records {
+    pub event: Event,
+    pub variation: Variation,
+    /// Sessions of the event the records were looked for in
+    pub sessions: usize,
+    /// The current session's id
+    id: String,
+    /// The current session's own bests, as they were when the records were looked for
+    pub current: Summary,
+    /// Each session's bests by id, the current session's among them
+    summaries: Vec<(String, Summary)>,
+}
+
+impl Records {
+    /// The records over `summaries`, each a session of `event` by its id, the current
+    /// session's among them.
+    pub fn of(
+        event: Event,
+        variation: Variation,
+        current: &str,
+        summaries: Vec<(String, Summary)>,
+    ) -> Self {
+        let mine = summaries
+            .iter()
+            .find(|(id, _)| id == current)
+            .map(|(_, summary)| *summary)
+            .unwrap_or_default();
+        Self {
+            event,
+            variation,
+            sessions: summaries.len(),
+            id: current.to_string(),
+            current: mine,
+            summaries,
+        }
+    }
+
+    /// Each of `STATS`' records, in order, if any session has done one, with `current` as
+    /// the current session's bests.
+    pub fn best(&self, current: &Summary) -> Vec<Option<Record>> {
+        let summaries: Vec<(&String, &Summary)> = self
+            .summaries
+            .iter()
+            .map(|(id, summary)| (id, if *id == self.id { current } else { summary }))
+            .collect();
+        STATS
+            .iter()
+            .map(|(_, stat)| {
+                summaries
+                    .iter()
+                    .filter_map(|(id, summary)| Some((stat(summary)?, *id)))
+                    .filter(|(value, _)| value.is_finite())
+                    // A tie goes to the session first by name
+                    .min_by_key(|(value, _)| *value)
+                    .map(|(value, id)| Record {
+                        value,
+                        session: id.clone(),
+                    })
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,66 @@
+//! Random-move scrambles for cubes with more than three layers, or any cube with a
+//! restricted move set.
+
+use rand::Rng;
+
+const FACES: [char; 6] = ['U', 'R', 'F', 'D', 'L', 'B'];
+
+/// Every turn allowed on an `n`-layered cube, as (face, layers turned from it).
+///
+/// Wide turns go up to half the cube. On even cubes the half-cube depth is only used
+/// from U, R and F, since from the opposite face it is the same turn after a rotation.
+pub fn turns(n: usize) -> Vec<(usize, usize)> {
+    let mut out = vec![];
+    for depth in 1..=n / 2 {
+        for face in 0..6 {
+            if n.is_multiple_of(2) && depth == n / 2 && face >= 3 {
+                continue;
+            }
+            out.push((face, depth));
+        }
+    }
+    out
+}
+
+/// A turn in WCA notation: `R`, `Rw`, then `3Rw` and deeper.
+fn notation(face: usize, depth: usize, turns: usize) -> String {
+    let prefix = match depth {
+        1 | 2 => String::new(),
+        _ => depth.to_string(),
+    };
+    let wide = if depth > 1 { "w" } else { "" };
+    let suffix = ["", "2", "'"][turns];
+    format!("{}{}{}{}", prefix, FACES[face], wide, suffix)
+}
+
+/// `length` random turns from `turns`, each as (face, layers turned from it).
+///
+/// Turns on one axis commute, so within a run of them each turn appears once at most.
+/// With `distinct_faces`, no two turns in a row are from the same face, whatever their
+/// depth.
+pub fn random_moves(
+    rng: &mut impl Rng,
+    turns: &[(usize, usize)],
+    length: usize,
+    distinct_faces: bool,
+) -> String {
+    let mut out = vec![];
+    let mut run: Vec<(usize, usize)> = vec![];
+    while out.len() < length {
+        let (face, depth) = turns[rng.gen_range(0..turns.len())];
+        if distinct_faces && run.last().is_some_and(|&(last, _)| last == face) {
+            continue;
+        }
+        match run.last() {
+            Some(&(last, _)) if last % 3 == face % 3 => {
+                if run.contains(&(face, depth)) {
+                    continue;
+                }
+            }
+            _ => run.clear(),
+        }
+        run.push((face, depth));
+        out.push(notation(face, depth, rng.gen_range(0..3)));
+    }
+    out.join(" ")
+}
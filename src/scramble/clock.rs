@@ -0,0 +1,22 @@
+//! Clock scrambles in the WCA's pin notation.
+
+use rand::Rng;
+
+/// Pin settings turned before and after flipping the puzzle with `y2`.
+const FRONT: [&str; 9] = ["UR", "DR", "DL", "UL", "U", "R", "D", "L", "ALL"];
+const BACK: [&str; 5] = ["U", "R", "D", "L", "ALL"];
+
+/// A random turn of each pin setting, which gives a random state.
+pub fn scramble(rng: &mut impl Rng) -> String {
+    let mut amount = |pins: &str| {
+        // Turns past six hours are written as the shorter way round
+        match rng.gen_range(0..12) {
+            v @ 0..=6 => format!("{}{}+", pins, v),
+            v => format!("{}{}-", pins, 12 - v),
+        }
+    };
+    let mut out: Vec<String> = FRONT.iter().map(|v| amount(v)).collect();
+    out.push("y2".to_string());
+    out.extend(BACK.iter().map(|v| amount(v)));
+    out.join(" ")
+}
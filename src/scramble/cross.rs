@@ -0,0 +1,57 @@
+//! How many moves the U cross of a 3x3 state needs, for filtering scrambles.
+
+use super::cubie::{moves, prune, Cubie};
+use std::sync::OnceLock;
+
+/// Each cross edge's position and flip, packed in base 12 and base 2.
+const N_CROSS: usize = 12 * 12 * 12 * 12 * 16;
+
+fn encode(pos: [usize; 4], flip: [u8; 4]) -> usize {
+    let pos = pos.iter().fold(0, |acc, &p| acc * 12 + p);
+    let flip = flip.iter().fold(0, |acc, &f| acc * 2 + f as usize);
+    pos * 16 + flip
+}
+
+fn decode(index: usize) -> ([usize; 4], [u8; 4]) {
+    let (pos, flip) = (index / 16, index % 16);
+    (
+        std::array::from_fn(|k| pos / 12usize.pow(3 - k as u32) % 12),
+        std::array::from_fn(|k| (flip >> (3 - k)) as u8 & 1),
+    )
+}
+
+fn build() -> Vec<u8> {
+    let moves = moves();
+    // Where each move takes the edge in each position
+    let to: Vec<[usize; 12]> = moves
+        .iter()
+        .map(|mv| {
+            let mut to = [0; 12];
+            for (i, &from) in mv.ep.iter().enumerate() {
+                to[from as usize] = i;
+            }
+            to
+        })
+        .collect();
+    let solved = encode([0, 1, 2, 3], [0; 4]);
+    prune(N_CROSS, solved, 18, |i, m| {
+        let (pos, flip) = decode(i);
+        let pos = pos.map(|p| to[m][p]);
+        let flip = std::array::from_fn(|k| (flip[k] + moves[m].eo[pos[k]]) % 2);
+        encode(pos, flip)
+    })
+}
+
+static DIST: OnceLock<Vec<u8>> = OnceLock::new();
+
+/// Fewest moves that solve the UR, UF, UL and UB edges of `cube`.
+pub fn distance(cube: &Cubie) -> u8 {
+    let (mut pos, mut flip) = ([0; 4], [0; 4]);
+    for (i, &piece) in cube.ep.iter().enumerate() {
+        if piece < 4 {
+            pos[piece as usize] = i;
+            flip[piece as usize] = cube.eo[i];
+        }
+    }
+    DIST.get_or_init(build)[encode(pos, flip)]
+}
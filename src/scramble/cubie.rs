@@ -0,0 +1,260 @@
+//! The 3x3 as pieces: which corner and edge sits where, and how it is turned.
+
+use rand::{seq::SliceRandom, Rng};
+use std::collections::VecDeque;
+
+pub const N_TWIST: usize = 2187;
+pub const N_FLIP: usize = 2048;
+pub const N_PERM8: usize = 40320;
+const FACES: [char; 6] = ['U', 'R', 'F', 'D', 'L', 'B'];
+
+/// Corners as URF UFL ULB UBR DFR DLF DBL DRB, edges as UR UF UL UB DR DF DL DB FR FL BL
+/// BR. Each array says which piece sits in each position.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct Cubie {
+    pub cp: [u8; 8],
+    pub co: [u8; 8],
+    pub ep: [u8; 12],
+    pub eo: [u8; 12],
+}
+
+pub const SOLVED: Cubie = Cubie {
+    cp: [0, 1, 2, 3, 4, 5, 6, 7],
+    co: [0; 8],
+    ep: [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11],
+    eo: [0; 12],
+};
+
+/// Quarter turns of U R F D L B.
+const BASIC: [Cubie; 6] = [
+    Cubie {
+        cp: [3, 0, 1, 2, 4, 5, 6, 7],
+        co: [0; 8],
+        ep: [3, 0, 1, 2, 4, 5, 6, 7, 8, 9, 10, 11],
+        eo: [0; 12],
+    },
+    Cubie {
+        cp: [4, 1, 2, 0, 7, 5, 6, 3],
+        co: [2, 0, 0, 1, 1, 0, 0, 2],
+        ep: [8, 1, 2, 3, 11, 5, 6, 7, 4, 9, 10, 0],
+        eo: [0; 12],
+    },
+    Cubie {
+        cp: [1, 5, 2, 3, 0, 4, 6, 7],
+        co: [1, 2, 0, 0, 2, 1, 0, 0],
+        ep: [0, 9, 2, 3, 4, 8, 6, 7, 1, 5, 10, 11],
+        eo: [0, 1, 0, 0, 0, 1, 0, 0, 1, 1, 0, 0],
+    },
+    Cubie {
+        cp: [0, 1, 2, 3, 5, 6, 7, 4],
+        co: [0; 8],
+        ep: [0, 1, 2, 3, 5, 6, 7, 4, 8, 9, 10, 11],
+        eo: [0; 12],
+    },
+    Cubie {
+        cp: [0, 2, 6, 3, 4, 1, 5, 7],
+        co: [0, 1, 2, 0, 0, 2, 1, 0],
+        ep: [0, 1, 10, 3, 4, 5, 9, 7, 8, 2, 6, 11],
+        eo: [0; 12],
+    },
+    Cubie {
+        cp: [0, 1, 3, 7, 4, 5, 2, 6],
+        co: [0, 0, 1, 2, 0, 0, 2, 1],
+        ep: [0, 1, 2, 11, 4, 5, 6, 10, 8, 9, 3, 7],
+        eo: [0, 0, 0, 1, 0, 0, 0, 1, 0, 0, 1, 1],
+    },
+];
+
+impl Cubie {
+    /// This state followed by `m`.
+    pub fn mul(&self, m: &Cubie) -> Cubie {
+        let mut out = SOLVED;
+        for i in 0..8 {
+            out.cp[i] = self.cp[m.cp[i] as usize];
+            out.co[i] = (self.co[m.cp[i] as usize] + m.co[i]) % 3;
+        }
+        for i in 0..12 {
+            out.ep[i] = self.ep[m.ep[i] as usize];
+            out.eo[i] = (self.eo[m.ep[i] as usize] + m.eo[i]) % 2;
+        }
+        out
+    }
+
+    /// A uniformly random solvable state.
+    pub fn random(rng: &mut impl Rng) -> Cubie {
+        let mut cube = SOLVED;
+        cube.cp = perm_unrank(rng.gen_range(0..N_PERM8), 8)
+            .try_into()
+            .unwrap();
+        let edges = rng.gen_range(0..N_PERM8 * 11 * 10 * 9 * 8);
+        cube.ep = perm_unrank(edges, 12).try_into().unwrap();
+        // Corner and edge permutations must have the same parity
+        if parity(&cube.cp) != parity(&cube.ep) {
+            cube.cp.swap(0, 1);
+        }
+        set_twist(&mut cube.co, rng.gen_range(0..N_TWIST));
+        set_flip(&mut cube.eo, rng.gen_range(0..N_FLIP));
+        cube
+    }
+
+    /// A random state with only the last layer unsolved: U pieces swapped around with
+    /// `permute`, twisted and flipped with `orient`, then a random U turn.
+    pub fn random_last_layer(rng: &mut impl Rng, permute: bool, orient: bool) -> Cubie {
+        let mut cube = SOLVED;
+        if permute {
+            cube.cp[..4].shuffle(rng);
+            cube.ep[..4].shuffle(rng);
+            // Corner and edge permutations must have the same parity
+            if parity(&cube.cp) != parity(&cube.ep) {
+                cube.cp.swap(0, 1);
+            }
+        }
+        if orient {
+            for i in 0..3 {
+                cube.co[i] = rng.gen_range(0..3);
+                cube.eo[i] = rng.gen_range(0..2);
+            }
+            cube.co[3] = (6 - cube.co[..3].iter().sum::<u8>()) % 3;
+            cube.eo[3] = cube.eo[..3].iter().sum::<u8>() % 2;
+        }
+        (0..rng.gen_range(0..4)).fold(cube, |cube, _| cube.mul(&BASIC[0]))
+    }
+
+    pub fn twist(&self) -> usize {
+        self.co[..7].iter().fold(0, |acc, &v| acc * 3 + v as usize)
+    }
+
+    pub fn flip(&self) -> usize {
+        self.eo[..11].iter().fold(0, |acc, &v| acc * 2 + v as usize)
+    }
+
+    /// Which positions hold the E-slice edges, as a bitmask.
+    pub fn slice_mask(&self) -> usize {
+        (0..12).filter(|&i| self.ep[i] >= 8).map(|i| 1 << i).sum()
+    }
+}
+
+/// The cube after each of the 18 moves, indexed as `face * 3 + turns - 1`.
+pub fn moves() -> [Cubie; 18] {
+    let mut out = [SOLVED; 18];
+    for face in 0..6 {
+        let mut cube = SOLVED;
+        for turns in 0..3 {
+            cube = cube.mul(&BASIC[face]);
+            out[face * 3 + turns] = cube;
+        }
+    }
+    out
+}
+
+pub fn set_twist(co: &mut [u8; 8], mut twist: usize) {
+    for i in (0..7).rev() {
+        co[i] = (twist % 3) as u8;
+        twist /= 3;
+    }
+    co[7] = ((3 - co[..7].iter().map(|&v| v as usize).sum::<usize>() % 3) % 3) as u8;
+}
+
+pub fn set_flip(eo: &mut [u8; 12], mut flip: usize) {
+    for i in (0..11).rev() {
+        eo[i] = (flip % 2) as u8;
+        flip /= 2;
+    }
+    eo[11] = (eo[..11].iter().map(|&v| v as usize).sum::<usize>() % 2) as u8;
+}
+
+pub fn parity(p: &[u8]) -> bool {
+    let mut odd = false;
+    for i in 0..p.len() {
+        for j in i + 1..p.len() {
+            odd ^= p[j] < p[i];
+        }
+    }
+    odd
+}
+
+/// Lehmer code of a permutation of `0..p.len()`.
+pub fn perm_rank(p: &[u8]) -> usize {
+    let mut rank = 0;
+    for i in 0..p.len() {
+        let smaller = p[i + 1..].iter().filter(|&&v| v < p[i]).count();
+        rank = rank * (p.len() - i) + smaller;
+    }
+    rank
+}
+
+pub fn perm_unrank(mut rank: usize, n: usize) -> Vec<u8> {
+    let mut digits = vec![0; n];
+    for i in (0..n).rev() {
+        digits[i] = rank % (n - i);
+        rank /= n - i;
+    }
+    let mut left: Vec<u8> = (0..n as u8).collect();
+    digits.into_iter().map(|d| left.remove(d)).collect()
+}
+
+/// Distance from `start` to every state, given how each of `moves` changes an index.
+pub fn prune(
+    size: usize,
+    start: usize,
+    moves: usize,
+    step: impl Fn(usize, usize) -> usize,
+) -> Vec<u8> {
+    let mut dist = vec![u8::MAX; size];
+    dist[start] = 0;
+    let mut queue = VecDeque::from([start]);
+    while let Some(i) = queue.pop_front() {
+        for m in 0..moves {
+            let next = step(i, m);
+            if dist[next] == u8::MAX {
+                dist[next] = dist[i] + 1;
+                queue.push_back(next);
+            }
+        }
+    }
+    dist
+}
+
+/// An optimal solution to a random state at least `min` moves from solved, found by
+/// always stepping closer in a table from `prune`.
+pub fn solve_random(
+    rng: &mut impl Rng,
+    dist: &[u8],
+    min: u8,
+    moves: usize,
+    apply: impl Fn(usize, usize) -> usize,
+) -> Vec<usize> {
+    let mut index = loop {
+        let index = rng.gen_range(0..dist.len());
+        // Unreachable indices keep the `u8::MAX` they started with
+        if (min..u8::MAX).contains(&dist[index]) {
+            break index;
+        }
+    };
+    let mut solution = vec![];
+    while dist[index] > 0 {
+        let m = (0..moves)
+            .find(|&m| dist[apply(index, m)] < dist[index])
+            .unwrap_or(0);
+        index = apply(index, m);
+        solution.push(m);
+    }
+    solution
+}
+
+/// Write moves as `face * 3 + turns - 1` in standard notation.
+pub fn format(moves: &[usize]) -> String {
+    moves
+        .iter()
+        .map(|&m| {
+            let suffix = ["", "2", "'"][m % 3];
+            format!("{}{}", FACES[m / 3], suffix)
+        })
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+/// The moves that undo `moves`.
+pub fn invert(moves: &[usize]) -> Vec<usize> {
+    moves.iter().rev().map(|&m| m / 3 * 3 + 2 - m % 3).collect()
+}
@@ -0,0 +1,25 @@
+//! Pochmann-style Megaminx scrambles, the notation the WCA uses.
+
+use rand::Rng;
+
+pub const LINES: usize = 7;
+const MOVES_PER_LINE: usize = 10;
+
+/// Lines of ten alternating `R` and `D` double turns, each ending with a `U` turn in the
+/// direction of its last `D`. The WCA uses seven.
+pub fn scramble(rng: &mut impl Rng, lines: usize) -> String {
+    let lines: Vec<String> = (0..lines)
+        .map(|_| {
+            let mut line = vec![];
+            let mut down = false;
+            for i in 0..MOVES_PER_LINE {
+                let face = ["R", "D"][i % 2];
+                down = rng.gen();
+                line.push(format!("{}{}", face, ["++", "--"][down as usize]));
+            }
+            line.push(["U", "U'"][down as usize].to_string());
+            line.join(" ")
+        })
+        .collect();
+    lines.join("\n")
+}
@@ -0,0 +1,736 @@
+mod big;
+mod clock;
+mod cross;
+mod cubie;
+mod megaminx;
+pub mod moves;
+mod net;
+mod pocket;
+mod pyraminx;
+mod skewb;
+mod square1;
+mod two_phase;
+mod worker;
+
+use rand::{seq::SliceRandom, Rng};
+use serde::{Deserialize, Serialize};
+use std::{error::Error, fmt};
+
+use moves::{Layers, Move};
+
+pub use net::Net;
+pub use worker::{Job, Worker};
+
+/// Generated scrambles are dropped after this many fail a filter, so a filter that can't
+/// be met doesn't hang the app.
+const FILTER_ATTEMPTS: usize = 100;
+
+/// Conditions generated scrambles must meet, for targeted practice.
+#[derive(Clone, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Filter {
+    /// Most moves the U cross may need (3x3 events only)
+    pub max_cross: Option<u8>,
+    /// Fewest moves a scramble may have
+    pub min_length: Option<usize>,
+    /// Never turn the same face twice in a row, even at different depths
+    pub distinct_faces: bool,
+}
+
+impl Filter {
+    fn accepts(&self, scramble: &str) -> bool {
+        let moves: Vec<&str> = scramble.split_whitespace().collect();
+        if self.min_length.is_some_and(|min| moves.len() < min) {
+            return false;
+        }
+        let face = |v: &str| v.chars().find(|c| c.is_ascii_alphabetic());
+        !self.distinct_faces
+            || moves
+                .windows(2)
+                .all(|w| face(w[0]).is_none() || face(w[0]) != face(w[1]))
+    }
+}
+
+/// A puzzle's `[scramble.<event>]` table, for practice with non-standard scrambles.
+///
+/// Setting either on a random-state cube event switches it to random moves.
+#[derive(Clone, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Settings {
+    /// Moves in the scramble; lines for Megaminx, slashes for Square-1
+    pub length: Option<usize>,
+    /// Turns to scramble with, like `R` or `Rw`, each in a random direction
+    pub moves: Option<Vec<String>>,
+    /// Scramble to a random last layer case instead (3x3 only)
+    pub subset: Option<Subset>,
+    /// Cubes in each attempt (multi-blind only)
+    pub cubes: Option<usize>,
+}
+
+/// Random-state 3x3 scrambles with F2L solved, for drilling last layer cases.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Subset {
+    /// The whole last layer
+    Ll,
+    /// Only orientation, with the pieces where they belong
+    Oll,
+    /// Only permutation, with the pieces oriented
+    Pll,
+}
+
+impl Subset {
+    pub const ALL: [Subset; 3] = [Subset::Ll, Subset::Oll, Subset::Pll];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Subset::Ll => "ll",
+            Subset::Oll => "oll",
+            Subset::Pll => "pll",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Subset> {
+        Self::ALL.into_iter().find(|v| v.as_str() == name)
+    }
+}
+
+impl fmt::Display for Subset {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.as_str().to_uppercase())
+    }
+}
+
+impl Settings {
+    /// Make sure the settings make sense for `event`, so generating can't fail later.
+    pub fn check(&self, event: Event) -> Result<(), Box<dyn Error>> {
+        if event == Event::Multiblind {
+            if self.cubes.is_some_and(|v| v < 2) {
+                return Err(format!("{}: cubes must be at least 2", event).into());
+            }
+            let rest = Settings {
+                cubes: None,
+                ..self.clone()
+            };
+            if rest != Settings::default() {
+                return Err(format!("{}: only the number of cubes can be changed", event).into());
+            }
+            return Ok(());
+        }
+        if self.cubes.is_some() {
+            return Err(format!("{}: cubes are only for multi-blind", event).into());
+        }
+        if event.relay().is_some() && *self != Settings::default() {
+            return Err(format!("{}: relays use each puzzle's standard scrambles", event).into());
+        }
+        if self.length == Some(0) {
+            return Err(format!("{}: length must be at least 1", event).into());
+        }
+        if self.subset.is_some() {
+            if event != Event::Cube3 {
+                return Err(format!("{}: subsets are only for 3x3", event).into());
+            }
+            if self.length.is_some() || self.moves.is_some() {
+                return Err(format!("{}: a subset can't have a length or moves", event).into());
+            }
+        }
+        let moves = match &self.moves {
+            Some(v) => v,
+            None if self.length.is_some() && event == Event::Clock => {
+                return Err(format!("{}: length can't be changed", event).into())
+            }
+            None => return Ok(()),
+        };
+        match event {
+            _ if event.layers().is_some() => {
+                let turns = self.turns(event).ok_or_else(|| {
+                    format!(
+                        "{}: moves must be face turns like R or Rw, without 2 or '",
+                        event
+                    )
+                })?;
+                // A single axis would run out of turns that don't cancel
+                if turns.iter().all(|(face, _)| face % 3 == turns[0].0 % 3) {
+                    return Err(format!("{}: moves must turn at least two axes", event).into());
+                }
+            }
+            Event::Pyraminx | Event::Skewb => {
+                if moves.iter().any(|v| !TIPLESS_FACES.contains(&v.as_str())) {
+                    return Err(format!("{}: moves must be among U, L, R and B", event).into());
+                }
+                if moves.iter().all(|v| *v == moves[0]) {
+                    return Err(format!("{}: moves must turn at least two faces", event).into());
+                }
+            }
+            _ => return Err(format!("{}: moves can't be changed", event).into()),
+        }
+        Ok(())
+    }
+
+    /// The configured moves of a cube event as (face, layers turned from it).
+    fn turns(&self, event: Event) -> Option<Vec<(usize, usize)>> {
+        let max = max_depth(event)?;
+        let moves = self.moves.as_ref()?;
+        moves
+            .iter()
+            .map(|v| match Move::parse(v) {
+                Some(Move {
+                    face,
+                    layers: Layers::Block(1, depth),
+                    turns: 1,
+                }) if depth <= max => Some((face, depth)),
+                _ => None,
+            })
+            .collect()
+    }
+}
+
+/// Pyraminx and Skewb faces, leaving out Pyraminx tips.
+const TIPLESS_FACES: [&str; 4] = ["U", "L", "R", "B"];
+
+/// A puzzle a session can be for, named as the WCA names its events.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Default, Debug, Serialize, Deserialize)]
+pub enum Event {
+    #[default]
+    #[serde(rename = "3x3")]
+    Cube3,
+    #[serde(rename = "2x2")]
+    Cube2,
+    #[serde(rename = "4x4")]
+    Cube4,
+    #[serde(rename = "5x5")]
+    Cube5,
+    #[serde(rename = "6x6")]
+    Cube6,
+    #[serde(rename = "7x7")]
+    Cube7,
+    #[serde(rename = "pyraminx")]
+    Pyraminx,
+    #[serde(rename = "megaminx")]
+    Megaminx,
+    #[serde(rename = "skewb")]
+    Skewb,
+    #[serde(rename = "square-1")]
+    Square1,
+    #[serde(rename = "clock")]
+    Clock,
+    #[serde(rename = "3bld")]
+    Cube3Blind,
+    #[serde(rename = "fmc")]
+    Fmc,
+    #[serde(rename = "mbld")]
+    Multiblind,
+    #[serde(rename = "relay-2-3-4")]
+    Relay234,
+    #[serde(rename = "relay-2-7")]
+    Relay27,
+}
+
+impl Event {
+    pub const ALL: [Event; 16] = [
+        Event::Cube3,
+        Event::Cube2,
+        Event::Cube4,
+        Event::Cube5,
+        Event::Cube6,
+        Event::Cube7,
+        Event::Pyraminx,
+        Event::Megaminx,
+        Event::Skewb,
+        Event::Square1,
+        Event::Clock,
+        Event::Cube3Blind,
+        Event::Fmc,
+        Event::Multiblind,
+        Event::Relay234,
+        Event::Relay27,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Event::Cube3 => "3x3",
+            Event::Cube2 => "2x2",
+            Event::Cube4 => "4x4",
+            Event::Cube5 => "5x5",
+            Event::Cube6 => "6x6",
+            Event::Cube7 => "7x7",
+            Event::Pyraminx => "pyraminx",
+            Event::Megaminx => "megaminx",
+            Event::Skewb => "skewb",
+            Event::Square1 => "square-1",
+            Event::Clock => "clock",
+            Event::Cube3Blind => "3bld",
+            Event::Fmc => "fmc",
+            Event::Multiblind => "mbld",
+            Event::Relay234 => "relay-2-3-4",
+            Event::Relay27 => "relay-2-7",
+        }
+    }
+
+    /// Layers of the cube for NxN events.
+    fn layers(&self) -> Option<usize> {
+        match self {
+            Event::Cube2 => Some(2),
+            Event::Cube3 | Event::Cube3Blind | Event::Fmc => Some(3),
+            Event::Cube4 => Some(4),
+            Event::Cube5 => Some(5),
+            Event::Cube6 => Some(6),
+            Event::Cube7 => Some(7),
+            _ => None,
+        }
+    }
+
+    /// The puzzles of a relay, solved one after another for a single time.
+    fn relay(&self) -> Option<&'static [Event]> {
+        const CUBES: [Event; 6] = [
+            Event::Cube2,
+            Event::Cube3,
+            Event::Cube4,
+            Event::Cube5,
+            Event::Cube6,
+            Event::Cube7,
+        ];
+        match self {
+            Event::Relay234 => Some(&CUBES[..3]),
+            Event::Relay27 => Some(&CUBES),
+            _ => None,
+        }
+    }
+
+    /// Whether solves are timed with the live timer; FMC attempts are scored by moves.
+    pub fn timed(&self) -> bool {
+        *self != Event::Fmc
+    }
+
+    pub fn parse(name: &str) -> Option<Event> {
+        Self::ALL.into_iter().find(|v| v.as_str() == name)
+    }
+}
+
+impl fmt::Display for Event {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// A fresh scramble for `event` with `settings` meeting `filter`: random-state where a
+/// solver is quick enough, random moves otherwise.
+pub fn generate(event: Event, settings: &Settings, filter: &Filter, rng: &mut impl Rng) -> String {
+    // Multi-blind is a numbered line per cube, each scrambled as for 3BLD
+    if event == Event::Multiblind {
+        let cubes = settings.cubes.unwrap_or(MULTIBLIND_CUBES);
+        let lines: Vec<String> = (1..=cubes)
+            .map(|i| {
+                let scramble = generate(Event::Cube3Blind, &Settings::default(), filter, rng);
+                format!("{}: {}", i, scramble)
+            })
+            .collect();
+        return lines.join("\n");
+    }
+    // A relay is a line per puzzle, each scrambled as usual
+    if let Some(parts) = event.relay() {
+        let lines: Vec<String> = parts
+            .iter()
+            .map(|&v| format!("{}: {}", v, generate(v, &Settings::default(), filter, rng)))
+            .collect();
+        return lines.join("\n");
+    }
+    let mut scramble = one(event, settings, filter, rng);
+    for _ in 1..FILTER_ATTEMPTS {
+        if filter.accepts(&scramble) {
+            break;
+        }
+        scramble = one(event, settings, filter, rng);
+    }
+    scramble
+}
+
+fn one(event: Event, settings: &Settings, filter: &Filter, rng: &mut impl Rng) -> String {
+    let distinct = filter.distinct_faces;
+    if let Some(subset) = settings.subset {
+        let cube =
+            cubie::Cubie::random_last_layer(rng, subset != Subset::Oll, subset != Subset::Pll);
+        return cubie::format(&cubie::invert(&two_phase::solve(cube)));
+    }
+    let custom = settings.length.is_some() || settings.moves.is_some();
+    if let (Some(n), true) = (event.layers(), custom) {
+        let turns = settings.turns(event).unwrap_or_else(|| big::turns(n));
+        let length = settings.length.unwrap_or(random_length(n));
+        return big::random_moves(rng, &turns, length, distinct);
+    }
+    if let (Event::Pyraminx | Event::Skewb, true) = (event, custom) {
+        let all = TIPLESS_FACES.map(String::from).to_vec();
+        let moves = settings.moves.as_ref().unwrap_or(&all);
+        let length = settings.length.unwrap_or(TIPLESS_LENGTH);
+        return tipless_moves(rng, moves, length);
+    }
+    match event {
+        Event::Cube3 => three(filter, rng),
+        Event::Cube2 => cubie::format(&cubie::invert(&pocket::random_solution(rng))),
+        Event::Cube4 | Event::Cube5 | Event::Cube6 | Event::Cube7 => {
+            let n = event.layers().unwrap_or(4);
+            big::random_moves(rng, &big::turns(n), random_length(n), distinct)
+        }
+        Event::Pyraminx => pyraminx::scramble(rng),
+        Event::Megaminx => megaminx::scramble(rng, settings.length.unwrap_or(megaminx::LINES)),
+        Event::Skewb => skewb::scramble(rng),
+        Event::Square1 => square1::scramble(rng, settings.length.unwrap_or(square1::SLASHES)),
+        Event::Clock => clock::scramble(rng),
+        Event::Cube3Blind => blind(filter, rng),
+        Event::Fmc => fmc(filter, rng),
+        Event::Multiblind | Event::Relay234 | Event::Relay27 => {
+            unreachable!("multi-blind and relays are generated by part")
+        }
+    }
+}
+
+/// Cubes in a multi-blind attempt unless `[scramble.mbld]` says otherwise: the fewest the
+/// WCA allows.
+const MULTIBLIND_CUBES: usize = 2;
+
+/// Turns in a random-move scramble of an `n`-layered cube: 25 for the 3x3 as the WCA
+/// used, and as many as the WCA uses for bigger cubes.
+fn random_length(n: usize) -> usize {
+    match n {
+        2 => 11,
+        3 => 25,
+        _ => 20 * (n - 2),
+    }
+}
+
+/// Default length of a Pyraminx or Skewb scramble with a restricted move set.
+const TIPLESS_LENGTH: usize = 11;
+
+/// `length` random turns from `moves`, never the same face twice in a row.
+fn tipless_moves(rng: &mut impl Rng, moves: &[String], length: usize) -> String {
+    let mut out: Vec<String> = vec![];
+    let mut last = None;
+    while out.len() < length {
+        let face = &moves[rng.gen_range(0..moves.len())];
+        if last == Some(face) {
+            continue;
+        }
+        last = Some(face);
+        out.push(format!("{}{}", face, ["", "'"][rng.gen_range(0..2)]));
+    }
+    out.join(" ")
+}
+
+/// Check an FMC solution typed for `scramble`, returning it tidied up with its length in
+/// the outer block turn metric: face and wide turns count 1, rotations nothing and inner
+/// slices 2, as the two outer turns they stand for.
+pub fn fewest_moves(scramble: &str, text: &str) -> Result<(String, usize), Box<dyn Error>> {
+    let tokens: Vec<&str> = text.split_whitespace().collect();
+    let moves = moves::parse(text)?;
+    if moves.is_empty() {
+        return Err("the solution is empty".into());
+    }
+    if let Some(i) = moves.iter().position(|v| v.span(3).is_none()) {
+        return Err(format!("not a 3x3 move: {}", tokens[i]).into());
+    }
+    let mut net = Net::scrambled(3, scramble);
+    net.apply(&moves);
+    if !net.is_solved() {
+        return Err("the solution doesn't solve the scramble".into());
+    }
+    let count = moves
+        .iter()
+        .map(|v| match v.layers {
+            Layers::All => 0,
+            Layers::Block(1, _) => 1,
+            _ => 2,
+        })
+        .sum();
+    Ok((tokens.join(" "), count))
+}
+
+/// The sticker net of `scramble` applied to a solved cube, for NxN events.
+pub fn net(event: Event, scramble: &str) -> Option<Net> {
+    event.layers().map(|n| Net::scrambled(n, scramble))
+}
+
+/// Check a scramble typed in for `event` against its notation, returning it tidied up.
+pub fn parse(event: Event, text: &str) -> Result<String, Box<dyn Error>> {
+    if event == Event::Square1 {
+        return square1(text);
+    }
+    if let Some(parts) = event.relay() {
+        return relay(event, parts, text);
+    }
+    if event == Event::Multiblind {
+        return multiblind(text);
+    }
+    let tokens: Vec<&str> = text.split_whitespace().collect();
+    if tokens.is_empty() {
+        return Err("the scramble is empty".into());
+    }
+    if let (Some(n), Some(max)) = (event.layers(), max_depth(event)) {
+        let moves = moves::parse(text)?;
+        if let Some(i) = moves.iter().position(|v| !cube_move(v, n, max)) {
+            return Err(format!("not a {} move: {}", event, tokens[i]).into());
+        }
+        return Ok(tokens.join(" "));
+    }
+    if let Some(bad) = tokens.iter().find(|v| !valid_move(event, v)) {
+        return Err(format!("not a {} move: {}", event, bad).into());
+    }
+    if event != Event::Megaminx {
+        return Ok(tokens.join(" "));
+    }
+    // Megaminx scrambles read as lines, each ending with a U turn
+    let mut out = String::new();
+    for token in tokens {
+        out.push_str(token);
+        out.push(if token.starts_with('U') { '\n' } else { ' ' });
+    }
+    Ok(out.trim_end().to_string())
+}
+
+/// How many layers a turn of an NxN event may turn.
+fn max_depth(event: Event) -> Option<usize> {
+    match event {
+        // The 3BLD orientation is written with wide turns
+        Event::Cube3Blind => Some(2),
+        _ => event.layers().map(|n| n / 2),
+    }
+}
+
+fn valid_move(event: Event, token: &str) -> bool {
+    let (face, suffix) = token.split_at(token.find(['+', '-', '\'']).unwrap_or(token.len()));
+    match event {
+        Event::Pyraminx => {
+            ["U", "L", "R", "B", "u", "l", "r", "b"].contains(&face) && ["", "'"].contains(&suffix)
+        }
+        Event::Skewb => ["U", "L", "R", "B"].contains(&face) && ["", "'"].contains(&suffix),
+        Event::Megaminx => match face {
+            "R" | "D" => ["++", "--"].contains(&suffix),
+            "U" => ["", "'"].contains(&suffix),
+            _ => false,
+        },
+        Event::Clock => {
+            const PINS: [&str; 9] = ["UR", "DR", "DL", "UL", "U", "R", "D", "L", "ALL"];
+            let (pins, hours) = face.split_at(
+                face.find(|c: char| c.is_ascii_digit())
+                    .unwrap_or(face.len()),
+            );
+            match (hours.parse::<u8>(), suffix) {
+                (Ok(0..=6), "+" | "-") => PINS.contains(&pins),
+                // Pins left up at the end, as older scramblers wrote them
+                _ => token == "y2" || PINS[..4].contains(&token),
+            }
+        }
+        _ => false,
+    }
+}
+
+/// A move that fits the cube, where blocks from the face (the only wide turns WCA
+/// scrambles use) may be at most `max` layers deep.
+fn cube_move(mv: &Move, n: usize, max: usize) -> bool {
+    match mv.layers {
+        Layers::Block(1, depth) => depth <= max,
+        _ => mv.span(n).is_some(),
+    }
+}
+
+/// Read a relay typed as each puzzle's name followed by its scramble, like
+/// `2x2: R U' ... 3x3: D2 F ...`, returning a line per puzzle.
+fn relay(event: Event, parts: &[Event], text: &str) -> Result<String, Box<dyn Error>> {
+    let names: Vec<String> = parts.iter().map(|v| format!("{}:", v)).collect();
+    let mut found: Vec<(Event, String)> = vec![];
+    for token in text.split_whitespace() {
+        match token.strip_suffix(':').and_then(Event::parse) {
+            Some(v) => found.push((v, String::new())),
+            None => {
+                let (_, part) = found
+                    .last_mut()
+                    .ok_or_else(|| format!("start with the first puzzle, like {}", names[0]))?;
+                part.push_str(token);
+                part.push(' ');
+            }
+        }
+    }
+    if !found.iter().map(|v| v.0).eq(parts.iter().copied()) {
+        return Err(format!("a {} scramble has parts {}", event, names.join(" ")).into());
+    }
+    let mut lines = vec![];
+    for (part, text) in found {
+        if text.is_empty() {
+            return Err(format!("the {} part is empty", part).into());
+        }
+        lines.push(format!("{}: {}", part, parse(part, &text)?));
+    }
+    Ok(lines.join("\n"))
+}
+
+/// Read a multi-blind attempt typed as numbered 3BLD scrambles, like
+/// `1: R U' ... 2: D2 F ...`, returning a line per cube.
+fn multiblind(text: &str) -> Result<String, Box<dyn Error>> {
+    let mut parts: Vec<String> = vec![];
+    for token in text.split_whitespace() {
+        match token.strip_suffix(':').and_then(|v| v.parse::<u32>().ok()) {
+            Some(n) if n as usize == parts.len() + 1 => parts.push(String::new()),
+            Some(_) => return Err(format!("number the cubes from 1, not {}", token).into()),
+            None => {
+                let part = parts
+                    .last_mut()
+                    .ok_or("start with the first cube, like 1:")?;
+                part.push_str(token);
+                part.push(' ');
+            }
+        }
+    }
+    if parts.len() < 2 {
+        return Err("a multi-blind attempt has at least 2 cubes".into());
+    }
+    let mut lines = vec![];
+    for (i, text) in parts.iter().enumerate() {
+        if text.is_empty() {
+            return Err(format!("cube {} is empty", i + 1).into());
+        }
+        lines.push(format!("{}: {}", i + 1, parse(Event::Cube3Blind, text)?));
+    }
+    Ok(lines.join("\n"))
+}
+
+/// Read Square-1 turns, with or without spaces, as `(x,y)` pairs and `/` slashes.
+fn square1(text: &str) -> Result<String, Box<dyn Error>> {
+    let text: String = text.split_whitespace().collect();
+    let mut out = vec![];
+    let mut rest = text.as_str();
+    while !rest.is_empty() {
+        if let Some(after) = rest.strip_prefix('/') {
+            out.push("/".to_string());
+            rest = after;
+            continue;
+        }
+        let end = rest.find(')').ok_or("expected (x,y) or /")?;
+        let pair = rest[..end].strip_prefix('(').ok_or("expected (x,y) or /")?;
+        let (x, y) = pair.split_once(',').ok_or("expected (x,y) or /")?;
+        let turn = |v: &str| {
+            v.parse::<i32>()
+                .map_err(|_| format!("not a turn: ({})", pair))
+        };
+        let (x, y) = (turn(x)?, turn(y)?);
+        if !(-5..=6).contains(&x) || !(-5..=6).contains(&y) {
+            return Err(format!("turn out of range: ({},{})", x, y).into());
+        }
+        out.push(format!("({},{})", x, y));
+        rest = &rest[end + 1..];
+    }
+    if out.is_empty() {
+        return Err("the scramble is empty".into());
+    }
+    // Join each slash onto the turn before it, as generated scrambles are written
+    Ok(out.join(" ").replace(" /", "/"))
+}
+
+/// A random 3x3 state with a cross no further from solved than the filter allows.
+fn random_cube(filter: &Filter, rng: &mut impl Rng) -> cubie::Cubie {
+    loop {
+        let cube = cubie::Cubie::random(rng);
+        // Checking the state is cheap next to solving it, so this can take many tries
+        if filter
+            .max_cross
+            .is_none_or(|max| cross::distance(&cube) <= max)
+        {
+            return cube;
+        }
+    }
+}
+
+/// A WCA-style scramble for the 3x3: the inverse of a solution to a random state.
+fn three(filter: &Filter, rng: &mut impl Rng) -> String {
+    let solution = two_phase::solve(random_cube(filter, rng));
+    cubie::format(&cubie::invert(&solution))
+}
+
+/// A 3x3 scramble padded with `R' U' F` on both ends, as in WCA FMC scrambles.
+fn fmc(filter: &Filter, rng: &mut impl Rng) -> String {
+    // The padding mustn't cancel into the scramble, so it can't start on the F/B axis or
+    // end on the R/L axis
+    let moves = loop {
+        let moves = cubie::invert(&two_phase::solve(random_cube(filter, rng)));
+        let first = moves.first().map(|m| m / 3 % 3);
+        let last = moves.last().map(|m| m / 3 % 3);
+        if first != Some(2) && last != Some(1) {
+            break moves;
+        }
+    };
+    format!("R' U' F {} R' U' F", cubie::format(&moves))
+}
+
+/// A 3x3 scramble followed by wide turns into one of the 24 orientations, so the
+/// solver can't rely on holding the cube a known way.
+fn blind(filter: &Filter, rng: &mut impl Rng) -> String {
+    // Each turn with its axis, as the solver numbers them: U/D 0, R/L 1, F/B 2
+    let top = [
+        ("", 0),
+        ("Rw", 1),
+        ("Rw2", 1),
+        ("Rw'", 1),
+        ("Fw", 2),
+        ("Fw'", 2),
+    ]
+    .choose(rng);
+    let front = [("", 0), ("Uw", 0), ("Uw2", 0), ("Uw'", 0)].choose(rng);
+    let turns: Vec<(&str, usize)> = [top, front]
+        .into_iter()
+        .flatten()
+        .copied()
+        .filter(|(v, _)| !v.is_empty())
+        .collect();
+    // The scramble mustn't end on the axis of the first turn, or it would fold into it
+    let axis = turns.first().map(|(_, axis)| *axis);
+    let moves = loop {
+        let moves = cubie::invert(&two_phase::solve(random_cube(filter, rng)));
+        if axis.is_none() || moves.last().map(|m| m / 3 % 3) != axis {
+            break moves;
+        }
+    };
+    let mut out = cubie::format(&moves);
+    for (turn, _) in turns {
+        out.push(' ');
+        out.push_str(turn);
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_checks_a_cube_scramble_fits_the_cube() {
+        assert_eq!(parse(Event::Cube3, " R  U'\tF2 ").unwrap(), "R U' F2");
+        assert!(parse(Event::Cube4, "Rw U 2R").is_ok());
+        assert!(parse(Event::Cube3, "3Rw").is_err());
+        assert!(parse(Event::Cube4, "M").is_err());
+        assert!(parse(Event::Cube3Blind, "R U Fw").is_ok());
+    }
+
+    #[test]
+    fn parse_keeps_square1_turns_in_range() {
+        assert_eq!(
+            parse(Event::Square1, "(1,0) / (-5, 6)/").unwrap(),
+            "(1,0)/ (-5,6)/"
+        );
+        for bad in ["(7,0)", "(0,-6)", "(1,x)", "(1 0)", "/ (1,0"] {
+            assert!(parse(Event::Square1, bad).is_err(), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn parse_wants_a_relays_parts_in_order() {
+        let text = "2x2: R U 3x3: F D 4x4: Rw U";
+        assert_eq!(
+            parse(Event::Relay234, text).unwrap(),
+            "2x2: R U\n3x3: F D\n4x4: Rw U"
+        );
+        assert!(parse(Event::Relay234, "3x3: F D 2x2: R U 4x4: Rw U").is_err());
+        assert!(parse(Event::Relay234, "2x2: R U 3x3: F D").is_err());
+        assert!(parse(Event::Relay234, "R U 2x2: R 3x3: F 4x4: U").is_err());
+        assert!(parse(Event::Relay234, "2x2: 3x3: F D 4x4: Rw U").is_err());
+        // Each part is checked as its own puzzle
+        assert!(parse(Event::Relay234, "2x2: M 3x3: F 4x4: U").is_err());
+    }
+}
@@ -0,0 +1,144 @@
+//! WCA notation for NxN cubes, read into turns of a run of layers about a face.
+//!
+//! As well as face turns like `R`, `R2` and `R'`, this reads wide turns (`Rw`, `3Rw`
+//! and SiGN's `r`), single inner layers (`2R`), middle slices (`M`, `E`, `S`) and
+//! rotations (`x`, `y`, `z`).
+
+use std::error::Error;
+
+/// Which layers a move turns, counted in from its face with the face itself as 1.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Layers {
+    /// The layers from the first to the second, both included
+    Block(usize, usize),
+    /// The middle layer, which only odd cubes have
+    Middle,
+    /// The whole cube
+    All,
+}
+
+/// Slices and rotations, with the face (URFDLB) each turns the same way as.
+const SLICES: [(char, usize, Layers); 6] = [
+    ('M', 4, Layers::Middle),
+    ('E', 3, Layers::Middle),
+    ('S', 2, Layers::Middle),
+    ('x', 1, Layers::All),
+    ('y', 0, Layers::All),
+    ('z', 2, Layers::All),
+];
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Move {
+    /// The face (URFDLB) the move turns clockwise as seen from
+    pub face: usize,
+    pub layers: Layers,
+    /// Quarter turns clockwise, from 1 to 3
+    pub turns: usize,
+}
+
+impl Move {
+    /// Read one move, each with an optional `2` or `'`.
+    pub fn parse(token: &str) -> Option<Move> {
+        let digits = token
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(token.len());
+        let (prefix, rest) = token.split_at(digits);
+        let mut chars = rest.chars();
+        let letter = chars.next()?;
+        let rest = chars.as_str();
+
+        let slice = SLICES.iter().find(|v| v.0 == letter);
+        let (face, layers, suffix) = if let Some(&(_, face, layers)) = slice {
+            if !prefix.is_empty() {
+                return None;
+            }
+            (face, layers, rest)
+        } else if let Some(face) = "urfdlb".find(letter) {
+            if !prefix.is_empty() {
+                return None;
+            }
+            (face, Layers::Block(1, 2), rest)
+        } else {
+            let face = "URFDLB".find(letter)?;
+            let (wide, suffix) = match rest.strip_prefix('w') {
+                Some(v) => (true, v),
+                None => (false, rest),
+            };
+            let layers = match (prefix.parse::<usize>(), wide) {
+                (Err(_), false) => Layers::Block(1, 1),
+                (Err(_), true) => Layers::Block(1, 2),
+                (Ok(v), true) if v >= 2 => Layers::Block(1, v),
+                (Ok(v), false) if v >= 2 => Layers::Block(v, v),
+                _ => return None,
+            };
+            (face, layers, suffix)
+        };
+        let turns = ["", "2", "'"].iter().position(|v| *v == suffix)? + 1;
+        Some(Move {
+            face,
+            layers,
+            turns,
+        })
+    }
+
+    /// The first and last layers turned on a cube of `n` layers, if the move fits it.
+    pub fn span(&self, n: usize) -> Option<(usize, usize)> {
+        match self.layers {
+            Layers::Block(first, last) if last <= n => Some((first, last)),
+            Layers::Middle if n % 2 == 1 && n > 1 => Some((n / 2 + 1, n / 2 + 1)),
+            Layers::All => Some((1, n)),
+            _ => None,
+        }
+    }
+}
+
+/// Read moves separated by whitespace, naming the first thing that isn't one.
+pub fn parse(text: &str) -> Result<Vec<Move>, Box<dyn Error>> {
+    text.split_whitespace()
+        .map(|v| Move::parse(v).ok_or_else(|| format!("not a move: {}", v).into()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mv(face: usize, layers: Layers, turns: usize) -> Option<Move> {
+        Some(Move {
+            face,
+            layers,
+            turns,
+        })
+    }
+
+    #[test]
+    fn parse_reads_wide_turns_every_way_written() {
+        assert_eq!(Move::parse("Rw"), mv(1, Layers::Block(1, 2), 1));
+        assert_eq!(Move::parse("r'"), mv(1, Layers::Block(1, 2), 3));
+        assert_eq!(Move::parse("3Fw2"), mv(2, Layers::Block(1, 3), 2));
+        assert_eq!(Move::parse("2L"), mv(4, Layers::Block(2, 2), 1));
+        // One layer deep is just the face
+        assert_eq!(Move::parse("1Rw"), None);
+        assert_eq!(Move::parse("2r"), None);
+    }
+
+    #[test]
+    fn parse_reads_slices_and_rotations() {
+        assert_eq!(Move::parse("M'"), mv(4, Layers::Middle, 3));
+        assert_eq!(Move::parse("E2"), mv(3, Layers::Middle, 2));
+        assert_eq!(Move::parse("y"), mv(0, Layers::All, 1));
+        assert_eq!(Move::parse("2M"), None);
+        assert_eq!(Move::parse("M").unwrap().span(4), None);
+        assert_eq!(Move::parse("S").unwrap().span(5), Some((3, 3)));
+        assert_eq!(Move::parse("4Uw").unwrap().span(3), None);
+    }
+
+    #[test]
+    fn parse_refuses_bad_suffixes() {
+        for token in ["R3", "R2'", "R''", "U+", "Rw'2", "Q", "", "x'2"] {
+            assert_eq!(Move::parse(token), None, "{}", token);
+        }
+        let err = parse("R U2 F3 D").unwrap_err();
+        assert_eq!(err.to_string(), "not a move: F3");
+    }
+}
@@ -0,0 +1,109 @@
+//! The stickers of an NxN cube after a scramble, laid out as a net for checking it.
+//!
+//! Stickers are tracked by position on a cube centred at the origin, with x towards R,
+//! y towards U and z towards F. Coordinates are doubled so every sticker sits on whole
+//! numbers: each face is the plane at ±n and its stickers are at odd offsets across it.
+
+use super::moves::Move;
+
+/// Per face, in the order URFDLB: its outward normal, then the directions of its columns
+/// and rows as drawn in the net, with U above F and D below it.
+const FRAMES: [[[i32; 3]; 3]; 6] = [
+    [[0, 1, 0], [1, 0, 0], [0, 0, 1]],
+    [[1, 0, 0], [0, 0, -1], [0, -1, 0]],
+    [[0, 0, 1], [1, 0, 0], [0, -1, 0]],
+    [[0, -1, 0], [1, 0, 0], [0, 0, -1]],
+    [[-1, 0, 0], [0, 0, 1], [0, -1, 0]],
+    [[0, 0, -1], [-1, 0, 0], [0, -1, 0]],
+];
+
+fn dot(a: [i32; 3], b: [i32; 3]) -> i32 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+/// `v` turned a quarter clockwise, looking at the origin from the end of `axis`.
+fn rotate(v: [i32; 3], axis: [i32; 3]) -> [i32; 3] {
+    let along = dot(axis, v);
+    let cross = [
+        axis[1] * v[2] - axis[2] * v[1],
+        axis[2] * v[0] - axis[0] * v[2],
+        axis[0] * v[1] - axis[1] * v[0],
+    ];
+    std::array::from_fn(|i| axis[i] * along - cross[i])
+}
+
+/// Each face's stickers, row by row, as the face (URFDLB) whose color they show.
+pub struct Net {
+    pub n: usize,
+    pub faces: [Vec<usize>; 6],
+}
+impl Net {
+    pub fn solved(n: usize) -> Net {
+        let faces = std::array::from_fn(|face| vec![face; n * n]);
+        Net { n, faces }
+    }
+
+    /// Whether each face is one color, however the cube is turned.
+    pub fn is_solved(&self) -> bool {
+        self.faces.iter().all(|v| v.iter().all(|c| *c == v[0]))
+    }
+
+    /// The cube with `n` layers after `scramble`, skipping anything that isn't a move.
+    pub fn scrambled(n: usize, scramble: &str) -> Net {
+        let moves: Vec<Move> = scramble
+            .split_whitespace()
+            .filter_map(Move::parse)
+            .collect();
+        let mut net = Net::solved(n);
+        net.apply(&moves);
+        net
+    }
+
+    /// Turn the cube through `moves`, skipping any that don't fit it.
+    pub fn apply(&mut self, moves: &[Move]) {
+        let (n, size) = (self.n, self.n as i32);
+        let mut stickers: Vec<([i32; 3], usize)> = vec![];
+        for (face, [normal, right, down]) in FRAMES.iter().enumerate() {
+            for r in 0..size {
+                for c in 0..size {
+                    let pos = std::array::from_fn(|i| {
+                        size * normal[i]
+                            + (2 * c - size + 1) * right[i]
+                            + (2 * r - size + 1) * down[i]
+                    });
+                    stickers.push((pos, self.faces[face][(r * size + c) as usize]));
+                }
+            }
+        }
+
+        for mv in moves {
+            let Some((first, last)) = mv.span(n) else {
+                continue;
+            };
+            let axis = FRAMES[mv.face][0];
+            // The k-th layer in from the face holds the stickers at n - 2k + 1 along it,
+            // and only the faces' own stickers sit at n - 2k
+            let outer = size - 2 * first as i32 + 2;
+            let inner = size - 2 * last as i32;
+            for (pos, _) in stickers.iter_mut().filter(|(pos, _)| {
+                let along = dot(*pos, axis);
+                inner <= along && along <= outer
+            }) {
+                for _ in 0..mv.turns {
+                    *pos = rotate(*pos, axis);
+                }
+            }
+        }
+
+        for (pos, color) in stickers {
+            let face = FRAMES
+                .iter()
+                .position(|frame| dot(pos, frame[0]) == size)
+                .unwrap_or(0);
+            let [_, right, down] = FRAMES[face];
+            let col = (dot(pos, right) + size - 1) / 2;
+            let row = (dot(pos, down) + size - 1) / 2;
+            self.faces[face][row as usize * n + col as usize] = color;
+        }
+    }
+}
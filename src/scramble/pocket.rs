@@ -0,0 +1,125 @@
+//! Optimal 2x2 solving from a table holding the distance of every state.
+
+use super::cubie::{moves, perm_rank, perm_unrank, prune, solve_random, Cubie, SOLVED};
+use rand::Rng;
+use std::sync::OnceLock;
+
+/// Positions of the corners that move; DBL stays put so the puzzle can't rotate.
+const POSITIONS: [usize; 7] = [0, 1, 2, 3, 4, 5, 7];
+const N_PERM7: usize = 5040;
+const N_TWIST6: usize = 729;
+/// Quarter, half and inverse turns of U, R and F.
+const N_MOVES: usize = 9;
+/// The fewest turns from solved a scramble may be, below which the WCA rejects a 2x2 state.
+const MIN_LENGTH: u8 = 4;
+
+struct Tables {
+    perm: Vec<[u16; N_MOVES]>,
+    twist: Vec<[u16; N_MOVES]>,
+    dist: Vec<u8>,
+}
+
+fn encode(cube: &Cubie) -> usize {
+    let perm: Vec<u8> = POSITIONS
+        .iter()
+        .map(|&p| {
+            POSITIONS
+                .iter()
+                .position(|&q| q == cube.cp[p] as usize)
+                .unwrap_or(0) as u8
+        })
+        .collect();
+    let twist = POSITIONS[..6]
+        .iter()
+        .fold(0, |acc, &p| acc * 3 + cube.co[p] as usize);
+    perm_rank(&perm) * N_TWIST6 + twist
+}
+
+fn decode(index: usize) -> Cubie {
+    let mut cube = SOLVED;
+    for (i, &v) in perm_unrank(index / N_TWIST6, 7).iter().enumerate() {
+        cube.cp[POSITIONS[i]] = POSITIONS[v as usize] as u8;
+    }
+    let mut twist = index % N_TWIST6;
+    for i in (0..6).rev() {
+        cube.co[POSITIONS[i]] = (twist % 3) as u8;
+        twist /= 3;
+    }
+    let sum: usize = POSITIONS[..6].iter().map(|&p| cube.co[p] as usize).sum();
+    cube.co[POSITIONS[6]] = ((3 - sum % 3) % 3) as u8;
+    cube
+}
+
+impl Tables {
+    fn build() -> Self {
+        let moves = moves();
+        let perm = (0..N_PERM7)
+            .map(|p| {
+                let cube = decode(p * N_TWIST6);
+                std::array::from_fn(|m| (encode(&cube.mul(&moves[m])) / N_TWIST6) as u16)
+            })
+            .collect::<Vec<[u16; N_MOVES]>>();
+        let twist = (0..N_TWIST6)
+            .map(|t| {
+                let cube = decode(t);
+                std::array::from_fn(|m| (encode(&cube.mul(&moves[m])) % N_TWIST6) as u16)
+            })
+            .collect::<Vec<[u16; N_MOVES]>>();
+        let dist = prune(N_PERM7 * N_TWIST6, encode(&SOLVED), N_MOVES, |i, m| {
+            perm[i / N_TWIST6][m] as usize * N_TWIST6 + twist[i % N_TWIST6][m] as usize
+        });
+        Self { perm, twist, dist }
+    }
+
+    fn apply(&self, index: usize, m: usize) -> usize {
+        let (p, t) = (index / N_TWIST6, index % N_TWIST6);
+        self.perm[p][m] as usize * N_TWIST6 + self.twist[t][m] as usize
+    }
+}
+
+static TABLES: OnceLock<Tables> = OnceLock::new();
+
+/// An optimal solution, as `face * 3 + turns - 1`, to a random state at least a few
+/// moves from solved.
+pub fn random_solution(rng: &mut impl Rng) -> Vec<usize> {
+    let tables = TABLES.get_or_init(Tables::build);
+    solve_random(rng, &tables.dist, MIN_LENGTH, N_MOVES, |i, m| {
+        tables.apply(i, m)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::cubie::invert;
+    use super::*;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    /// Every state a 2x2 (with DBL fixed) can reach.
+    const STATES: usize = 3_674_160;
+
+    #[test]
+    fn table_reaches_every_state_within_eleven_moves() {
+        let tables = TABLES.get_or_init(Tables::build);
+        let reached = tables.dist.iter().filter(|&&d| d != u8::MAX);
+        let max = reached.clone().max().copied();
+        assert_eq!(reached.count(), STATES);
+        assert_eq!(max, Some(11));
+    }
+
+    #[test]
+    fn random_solution_is_optimal() {
+        let mut rng = ChaCha8Rng::seed_from_u64(1);
+        let tables = TABLES.get_or_init(Tables::build);
+        let moves = moves();
+        for _ in 0..100 {
+            let solution = random_solution(&mut rng);
+            let scrambled = invert(&solution)
+                .iter()
+                .fold(SOLVED, |c, &m| c.mul(&moves[m]));
+            assert_eq!(tables.dist[encode(&scrambled)] as usize, solution.len());
+            let end = solution.iter().fold(scrambled, |c, &m| c.mul(&moves[m]));
+            assert!(end == SOLVED);
+        }
+    }
+}
@@ -0,0 +1,164 @@
+//! Optimal Pyraminx solving, with the tips left to be scrambled separately.
+//!
+//! Vertices are numbered U, L, R, B and each face is named for the vertex opposite it.
+//! Edges sit between two vertices, with a sticker on each of the other two faces.
+
+use super::cubie::{perm_rank, perm_unrank, prune, solve_random};
+use rand::Rng;
+use std::sync::OnceLock;
+
+const VERTICES: [char; 4] = ['U', 'L', 'R', 'B'];
+const EDGES: [(usize, usize); 6] = [(0, 1), (0, 2), (0, 3), (1, 2), (1, 3), (2, 3)];
+/// Where a clockwise turn about each vertex, seen from that vertex, sends every vertex.
+const TURNS: [[usize; 4]; 4] = [[0, 3, 1, 2], [2, 1, 3, 0], [3, 0, 2, 1], [1, 2, 0, 3]];
+const N_PERM6: usize = 720;
+/// Edge orientations as one bit per position; only even ones are reachable.
+const N_FLIP6: usize = 64;
+/// The twist of the axial piece under each vertex.
+const N_AXIS: usize = 81;
+/// A clockwise and an anticlockwise turn of each vertex.
+const N_MOVES: usize = 8;
+/// Tips aside, a state solvable in fewer turns is too easy to be a fair scramble.
+const MIN_LENGTH: u8 = 6;
+
+/// The faces an edge has stickers on, in order.
+fn faces(edge: usize) -> [usize; 2] {
+    let (a, b) = EDGES[edge];
+    let mut out = (0..4).filter(|&v| v != a && v != b);
+    [out.next().unwrap_or(0), out.next().unwrap_or(0)]
+}
+
+/// Where a clockwise turn of `vertex` takes the edge in `edge`, and which of the new
+/// position's faces each of its stickers lands on.
+fn turn_edge(vertex: usize, edge: usize) -> Option<(usize, [usize; 2])> {
+    let (a, b) = EDGES[edge];
+    if a != vertex && b != vertex {
+        return None;
+    }
+    let sigma = TURNS[vertex];
+    let (x, y) = (sigma[a].min(sigma[b]), sigma[a].max(sigma[b]));
+    let to = EDGES.iter().position(|&e| e == (x, y)).unwrap_or(0);
+    let slots = faces(edge).map(|f| {
+        let moved = faces(to).iter().position(|&g| g == sigma[f]);
+        moved.unwrap_or(0)
+    });
+    Some((to, slots))
+}
+
+struct Tables {
+    perm: Vec<[u16; N_MOVES]>,
+    flip: Vec<[u8; N_MOVES]>,
+    axis: Vec<[u8; N_MOVES]>,
+    dist: Vec<u8>,
+}
+
+impl Tables {
+    fn build() -> Self {
+        // Anticlockwise is the clockwise turn done twice
+        let perm = (0..N_PERM6)
+            .map(|r| {
+                std::array::from_fn(|m| {
+                    let mut p = perm_unrank(r, 6);
+                    for _ in 0..m % 2 + 1 {
+                        let mut next = p.clone();
+                        for (e, &piece) in p.iter().enumerate() {
+                            if let Some((to, _)) = turn_edge(m / 2, e) {
+                                next[to] = piece;
+                            }
+                        }
+                        p = next;
+                    }
+                    perm_rank(&p) as u16
+                })
+            })
+            .collect();
+        let flip = (0..N_FLIP6)
+            .map(|bits| {
+                std::array::from_fn(|m| {
+                    let mut bits = bits;
+                    for _ in 0..m % 2 + 1 {
+                        let mut next = bits;
+                        for e in 0..6 {
+                            if let Some((to, slots)) = turn_edge(m / 2, e) {
+                                let o = (bits >> e) & 1;
+                                next = next & !(1 << to) | slots[o] << to;
+                            }
+                        }
+                        bits = next;
+                    }
+                    bits as u8
+                })
+            })
+            .collect();
+        let axis = (0..N_AXIS)
+            .map(|a| {
+                std::array::from_fn(|m| {
+                    let place = 3usize.pow(m as u32 / 2);
+                    let twist = a / place % 3;
+                    let turned = (twist + m % 2 + 1) % 3;
+                    (a - twist * place + turned * place) as u8
+                })
+            })
+            .collect();
+        let mut tables = Self {
+            perm,
+            flip,
+            axis,
+            dist: vec![],
+        };
+        let dist = prune(N_PERM6 * N_FLIP6 * N_AXIS, 0, N_MOVES, |i, m| {
+            tables.apply(i, m)
+        });
+        tables.dist = dist;
+        tables
+    }
+
+    fn apply(&self, index: usize, m: usize) -> usize {
+        let (edges, a) = (index / N_AXIS, index % N_AXIS);
+        let (p, f) = (edges / N_FLIP6, edges % N_FLIP6);
+        let p = self.perm[p][m] as usize;
+        let f = self.flip[f][m] as usize;
+        (p * N_FLIP6 + f) * N_AXIS + self.axis[a][m] as usize
+    }
+}
+
+static TABLES: OnceLock<Tables> = OnceLock::new();
+
+/// A WCA-style Pyraminx scramble: an optimal scramble to a random state, then each tip
+/// turned at random.
+pub fn scramble(rng: &mut impl Rng) -> String {
+    let tables = TABLES.get_or_init(Tables::build);
+    let solution = solve_random(rng, &tables.dist, MIN_LENGTH, N_MOVES, |i, m| {
+        tables.apply(i, m)
+    });
+    let mut out: Vec<String> = solution
+        .iter()
+        .rev()
+        .map(|&m| format!("{}{}", VERTICES[m / 2], ["'", ""][m % 2]))
+        .collect();
+    for vertex in VERTICES {
+        match rng.gen_range(0..3) {
+            1 => out.push(vertex.to_ascii_lowercase().to_string()),
+            2 => out.push(format!("{}'", vertex.to_ascii_lowercase())),
+            _ => (),
+        }
+    }
+    out.join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Every state a Pyraminx without its tips can reach.
+    const STATES: usize = 933_120;
+
+    #[test]
+    fn table_reaches_every_state_within_eleven_moves() {
+        let tables = TABLES.get_or_init(Tables::build);
+        let reached = tables.dist.iter().filter(|&&d| d != u8::MAX);
+        let max = reached.clone().max().copied();
+        assert_eq!(reached.count(), STATES);
+        assert_eq!(max, Some(11));
+    }
+}
@@ -0,0 +1,186 @@
+//! Optimal Skewb solving from a table holding the distance of every state.
+//!
+//! Pieces are found by position, with x towards R, y towards U and z towards F. The UFR
+//! corner never moves under the WCA's turns, so it fixes the puzzle in space.
+
+use super::cubie::{parity, perm_rank, perm_unrank, prune, solve_random};
+use rand::Rng;
+use std::sync::OnceLock;
+
+type Vec3 = [i32; 3];
+
+const TURNS: [char; 4] = ['R', 'U', 'L', 'B'];
+/// The corner each turn is about: DRB, ULB, DLF and DLB.
+const AXES: [Vec3; 4] = [[1, -1, -1], [-1, 1, -1], [-1, -1, 1], [-1, -1, -1]];
+const FACES: [Vec3; 6] = [
+    [0, 1, 0],
+    [1, 0, 0],
+    [0, 0, 1],
+    [0, -1, 0],
+    [-1, 0, 0],
+    [0, 0, -1],
+];
+/// The corners that move, in the two sets that never mix.
+const TETRAD_A: [Vec3; 3] = [[1, -1, -1], [-1, 1, -1], [-1, -1, 1]];
+const TETRAD_B: [Vec3; 4] = [[-1, -1, -1], [1, 1, -1], [1, -1, 1], [-1, 1, 1]];
+/// Every turn is a 3-cycle, so only even permutations of each set come up.
+const N_CENTERS: usize = 360;
+const N_A: usize = 3 * 27;
+const N_B: usize = 12 * 81;
+/// A clockwise and an anticlockwise turn about each axis.
+const N_MOVES: usize = 8;
+/// The WCA Skewb scrambler throws away states this close to solved, so this does too.
+const MIN_LENGTH: u8 = 7;
+
+fn dot(a: Vec3, b: Vec3) -> i32 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+/// Turn `v` a third clockwise, seen from outside, about the corner `c`.
+fn rotate(c: Vec3, v: Vec3) -> Vec3 {
+    let cross = [
+        c[1] * v[2] - c[2] * v[1],
+        c[2] * v[0] - c[0] * v[2],
+        c[0] * v[1] - c[1] * v[0],
+    ];
+    let along = dot(c, v);
+    std::array::from_fn(|i| (c[i] * along - v[i] - cross[i]) / 2)
+}
+
+/// Where a turn about `c` sends each of `pieces`, if it moves them at all.
+fn targets(c: Vec3, pieces: &[Vec3]) -> Vec<usize> {
+    pieces
+        .iter()
+        .enumerate()
+        .map(|(i, &p)| match dot(p, c) > 0 {
+            true => pieces.iter().position(|&q| q == rotate(c, p)).unwrap_or(i),
+            false => i,
+        })
+        .collect()
+}
+
+fn even_rank(p: &[u8]) -> usize {
+    // The last free digit of the Lehmer code only ever fixes the parity
+    perm_rank(p) / 2
+}
+
+fn even_unrank(rank: usize, n: usize) -> Vec<u8> {
+    let p = perm_unrank(rank * 2, n);
+    match parity(&p) {
+        true => perm_unrank(rank * 2 + 1, n),
+        false => p,
+    }
+}
+
+/// How each turn changes a set of corners, tracking each one's twist as the axis its
+/// x-facing sticker points along.
+fn corner_table(corners: &[Vec3]) -> Vec<[u16; N_MOVES]> {
+    let n = corners.len();
+    let twists = 3usize.pow(n as u32);
+    (0..(3..=n).product::<usize>() * twists)
+        .map(|i| {
+            std::array::from_fn(|m| {
+                let mut perm = even_unrank(i / twists, n);
+                let mut twist: Vec<usize> = (0..n)
+                    .map(|j| i % twists / 3usize.pow((n - 1 - j) as u32) % 3)
+                    .collect();
+                let c = AXES[m / 2];
+                let to = targets(c, corners);
+                // Which axis each axis turns into
+                let axis: Vec<usize> = [[1, 0, 0], [0, 1, 0], [0, 0, 1]]
+                    .iter()
+                    .map(|&f| rotate(c, f).iter().position(|&v| v != 0).unwrap_or(0))
+                    .collect();
+                for _ in 0..m % 2 + 1 {
+                    let (mut p, mut t) = (perm.clone(), twist.clone());
+                    for j in 0..n {
+                        if dot(corners[j], c) > 0 {
+                            p[to[j]] = perm[j];
+                            t[to[j]] = axis[twist[j]];
+                        }
+                    }
+                    (perm, twist) = (p, t);
+                }
+                let twist = twist.iter().fold(0, |acc, &t| acc * 3 + t);
+                (even_rank(&perm) * twists + twist) as u16
+            })
+        })
+        .collect()
+}
+
+struct Tables {
+    centers: Vec<[u16; N_MOVES]>,
+    a: Vec<[u16; N_MOVES]>,
+    b: Vec<[u16; N_MOVES]>,
+    dist: Vec<u8>,
+}
+
+impl Tables {
+    fn build() -> Self {
+        let centers = (0..N_CENTERS)
+            .map(|r| {
+                std::array::from_fn(|m| {
+                    let to = targets(AXES[m / 2], &FACES);
+                    let mut p = even_unrank(r, 6);
+                    for _ in 0..m % 2 + 1 {
+                        let mut next = p.clone();
+                        for (j, &piece) in p.iter().enumerate() {
+                            next[to[j]] = piece;
+                        }
+                        p = next;
+                    }
+                    even_rank(&p) as u16
+                })
+            })
+            .collect();
+        let mut tables = Self {
+            centers,
+            a: corner_table(&TETRAD_A),
+            b: corner_table(&TETRAD_B),
+            dist: vec![],
+        };
+        let dist = prune(N_CENTERS * N_A * N_B, 0, N_MOVES, |i, m| tables.apply(i, m));
+        tables.dist = dist;
+        tables
+    }
+
+    fn apply(&self, index: usize, m: usize) -> usize {
+        let (rest, b) = (index / N_B, index % N_B);
+        let (c, a) = (rest / N_A, rest % N_A);
+        let c = self.centers[c][m] as usize;
+        (c * N_A + self.a[a][m] as usize) * N_B + self.b[b][m] as usize
+    }
+}
+
+static TABLES: OnceLock<Tables> = OnceLock::new();
+
+/// A WCA-style Skewb scramble: an optimal scramble to a random state.
+pub fn scramble(rng: &mut impl Rng) -> String {
+    let tables = TABLES.get_or_init(Tables::build);
+    let solution = solve_random(rng, &tables.dist, MIN_LENGTH, N_MOVES, |i, m| {
+        tables.apply(i, m)
+    });
+    solution
+        .iter()
+        .rev()
+        .map(|&m| format!("{}{}", TURNS[m / 2], ["'", ""][m % 2]))
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Every state a Skewb can reach.
+    const STATES: usize = 3_149_280;
+
+    #[test]
+    fn table_reaches_every_state_within_eleven_moves() {
+        let tables = TABLES.get_or_init(Tables::build);
+        let reached = tables.dist.iter().filter(|&&d| d != u8::MAX);
+        let max = reached.clone().max().copied();
+        assert_eq!(reached.count(), STATES);
+        assert_eq!(max, Some(11));
+    }
+}
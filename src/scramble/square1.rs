@@ -0,0 +1,58 @@
+//! Random-move Square-1 scrambles in `(x,y)/` notation.
+//!
+//! Each layer is twelve 30° slots, numbered clockwise seen from above starting at the
+//! front end of the slice, with corners filling two slots. A slash only works when no
+//! piece straddles the slice, so the shape is tracked to pick turns that leave it free.
+
+use rand::Rng;
+
+/// Turn-and-slash pairs in a scramble.
+pub const SLASHES: usize = 20;
+
+/// Which piece fills each slot of one layer; a corner's two slots share its number.
+type Layer = [u8; 12];
+
+/// A layer of the cube shape: corner, edge, corner, edge around from the slice.
+fn solved(first: u8) -> Layer {
+    std::array::from_fn(|i| first + (i / 3 * 2 + i % 3 / 2) as u8)
+}
+
+/// Turn a layer `units` slots clockwise, seen from above.
+fn turn(layer: &Layer, units: i32) -> Layer {
+    std::array::from_fn(|i| layer[(i as i32 - units).rem_euclid(12) as usize])
+}
+
+/// Whether the slice is clear of pieces on this layer, at both ends.
+fn sliceable(layer: &Layer) -> bool {
+    layer[11] != layer[0] && layer[5] != layer[6]
+}
+
+/// Flip the right half of each layer over to the other.
+fn slash(top: &mut Layer, bottom: &mut Layer) {
+    for i in 0..6 {
+        std::mem::swap(&mut top[6 + i], &mut bottom[11 - i]);
+    }
+}
+
+/// A WCA-style Square-1 scramble of `slashes` turns from the solved cube shape. The WCA
+/// uses 20.
+pub fn scramble(rng: &mut impl Rng, slashes: usize) -> String {
+    let (mut top, mut bottom) = (solved(0), solved(8));
+    let mut out = vec![];
+    while out.len() < slashes {
+        let (x, y) = (rng.gen_range(-5..=6), rng.gen_range(-5..=6));
+        // A slash straight after a slash would undo it
+        if (x, y) == (0, 0) {
+            continue;
+        }
+        // The bottom turns clockwise as seen from below
+        let (t, b) = (turn(&top, x), turn(&bottom, -y));
+        if !sliceable(&t) || !sliceable(&b) {
+            continue;
+        }
+        (top, bottom) = (t, b);
+        slash(&mut top, &mut bottom);
+        out.push(format!("({},{})/", x, y));
+    }
+    out.join(" ")
+}
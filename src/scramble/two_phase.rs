@@ -0,0 +1,298 @@
+//! Kociemba's two-phase algorithm, used to solve a random 3x3 state.
+//!
+//! Phase 1 brings the cube into the subgroup <U, D, R2, F2, L2, B2>, where every
+//! corner and edge is oriented and the E-slice edges are in the E slice. Phase 2 solves
+//! the rest using only those moves. Both phases are IDA* searches over coordinates with
+//! pruning tables built once, on first use.
+
+use super::cubie::{
+    moves, perm_rank, perm_unrank, prune, set_flip, set_twist, Cubie, N_FLIP, N_PERM8, N_TWIST,
+    SOLVED,
+};
+use std::sync::OnceLock;
+
+const N_SLICE: usize = 495;
+const N_SLICE_PERM: usize = 24;
+/// Longest solution accepted; random states need 20 at most and two-phase finds this
+/// length quickly.
+const MAX_LENGTH: usize = 21;
+/// Moves that keep the cube in the phase 2 subgroup, as `face * 3 + turns - 1`.
+const PHASE2_MOVES: [usize; 10] = [0, 1, 2, 4, 7, 9, 10, 11, 13, 16];
+
+/// How a permutation of `n` pieces changes under each phase 2 move, where `from` gives
+/// the position a move brings into position `i`.
+fn perm_table(
+    moves: &[Cubie; 18],
+    n: usize,
+    from: impl Fn(&Cubie, usize) -> usize,
+) -> Vec<[u16; 10]> {
+    (0..(1..=n).product())
+        .map(|r| {
+            let p = perm_unrank(r, n);
+            std::array::from_fn(|j| {
+                let mv = &moves[PHASE2_MOVES[j]];
+                let next: Vec<u8> = (0..n).map(|i| p[from(mv, i)]).collect();
+                perm_rank(&next) as u16
+            })
+        })
+        .collect()
+}
+
+struct Tables {
+    moves: [Cubie; 18],
+    twist: Vec<[u16; 18]>,
+    flip: Vec<[u16; 18]>,
+    slice: Vec<[u16; 18]>,
+    mask_rank: Vec<u16>,
+    corners: Vec<[u16; 10]>,
+    edges: Vec<[u16; 10]>,
+    slice_perm: Vec<[u8; 10]>,
+    twist_prune: Vec<u8>,
+    flip_prune: Vec<u8>,
+    corner_prune: Vec<u8>,
+    edge_prune: Vec<u8>,
+}
+
+impl Tables {
+    fn build() -> Self {
+        let moves = moves();
+
+        let twist: Vec<[u16; 18]> = (0..N_TWIST)
+            .map(|t| {
+                let mut co = [0; 8];
+                set_twist(&mut co, t);
+                std::array::from_fn(|m| {
+                    let mv = &moves[m];
+                    let mut out = SOLVED;
+                    for i in 0..8 {
+                        out.co[i] = (co[mv.cp[i] as usize] + mv.co[i]) % 3;
+                    }
+                    out.twist() as u16
+                })
+            })
+            .collect();
+        let flip: Vec<[u16; 18]> = (0..N_FLIP)
+            .map(|f| {
+                let mut eo = [0; 12];
+                set_flip(&mut eo, f);
+                std::array::from_fn(|m| {
+                    let mv = &moves[m];
+                    let mut out = SOLVED;
+                    for i in 0..12 {
+                        out.eo[i] = (eo[mv.ep[i] as usize] + mv.eo[i]) % 2;
+                    }
+                    out.flip() as u16
+                })
+            })
+            .collect();
+
+        // The E-slice coordinate only tracks which 4 of the 12 positions hold slice edges
+        let masks: Vec<usize> = (0..1 << 12)
+            .filter(|v: &usize| v.count_ones() == 4)
+            .collect();
+        let mut mask_rank = vec![0; 1 << 12];
+        for (i, &mask) in masks.iter().enumerate() {
+            mask_rank[mask] = i as u16;
+        }
+        let slice: Vec<[u16; 18]> = masks
+            .iter()
+            .map(|&mask| {
+                std::array::from_fn(|m| {
+                    let next: usize = (0..12)
+                        .filter(|&i| mask & (1 << moves[m].ep[i]) != 0)
+                        .map(|i| 1 << i)
+                        .sum();
+                    mask_rank[next]
+                })
+            })
+            .collect();
+        let slice_solved = mask_rank[SOLVED.slice_mask()] as usize;
+
+        let corners = perm_table(&moves, 8, |mv, i| mv.cp[i] as usize);
+        let edges = perm_table(&moves, 8, |mv, i| mv.ep[i] as usize);
+        let slice_perm: Vec<[u8; 10]> = perm_table(&moves, 4, |mv, i| mv.ep[8 + i] as usize - 8)
+            .into_iter()
+            .map(|v| v.map(|v| v as u8))
+            .collect();
+
+        let twist_prune = prune(N_SLICE * N_TWIST, slice_solved * N_TWIST, 18, |i, m| {
+            let (s, t) = (i / N_TWIST, i % N_TWIST);
+            slice[s][m] as usize * N_TWIST + twist[t][m] as usize
+        });
+        let flip_prune = prune(N_SLICE * N_FLIP, slice_solved * N_FLIP, 18, |i, m| {
+            let (s, f) = (i / N_FLIP, i % N_FLIP);
+            slice[s][m] as usize * N_FLIP + flip[f][m] as usize
+        });
+        let corner_prune = prune(N_PERM8 * N_SLICE_PERM, 0, 10, |i, m| {
+            let (c, s) = (i / N_SLICE_PERM, i % N_SLICE_PERM);
+            corners[c][m] as usize * N_SLICE_PERM + slice_perm[s][m] as usize
+        });
+        let edge_prune = prune(N_PERM8 * N_SLICE_PERM, 0, 10, |i, m| {
+            let (e, s) = (i / N_SLICE_PERM, i % N_SLICE_PERM);
+            edges[e][m] as usize * N_SLICE_PERM + slice_perm[s][m] as usize
+        });
+
+        Self {
+            moves,
+            twist,
+            flip,
+            slice,
+            mask_rank,
+            corners,
+            edges,
+            slice_perm,
+            twist_prune,
+            flip_prune,
+            corner_prune,
+            edge_prune,
+        }
+    }
+
+    fn phase1_dist(&self, twist: usize, flip: usize, slice: usize) -> usize {
+        self.twist_prune[slice * N_TWIST + twist].max(self.flip_prune[slice * N_FLIP + flip])
+            as usize
+    }
+
+    fn phase2_dist(&self, corners: usize, edges: usize, slice: usize) -> usize {
+        let c = self.corner_prune[corners * N_SLICE_PERM + slice];
+        let e = self.edge_prune[edges * N_SLICE_PERM + slice];
+        c.max(e) as usize
+    }
+}
+
+static TABLES: OnceLock<Tables> = OnceLock::new();
+
+/// Whether `face` may follow `last`: never the same face twice, and opposite faces only
+/// in one order since they commute.
+fn allowed(face: usize, last: Option<usize>) -> bool {
+    match last {
+        Some(last) => face != last && face + 3 != last,
+        None => true,
+    }
+}
+
+struct Search<'a> {
+    tables: &'a Tables,
+    cube: Cubie,
+    path: Vec<usize>,
+}
+
+impl Search<'_> {
+    fn phase1(&mut self, twist: usize, flip: usize, slice: usize, depth: usize) -> bool {
+        let dist = self.tables.phase1_dist(twist, flip, slice);
+        if dist == 0 && depth == 0 {
+            return self.start_phase2();
+        }
+        if dist > depth || (dist == 0 && depth < 5) {
+            // Leaving the subgroup and coming back this late never beats phase 2 from here
+            return false;
+        }
+        let last = self.path.last().map(|m| m / 3);
+        for m in 0..18 {
+            if !allowed(m / 3, last) {
+                continue;
+            }
+            self.path.push(m);
+            let t = self.tables;
+            let found = self.phase1(
+                t.twist[twist][m] as usize,
+                t.flip[flip][m] as usize,
+                t.slice[slice][m] as usize,
+                depth - 1,
+            );
+            if found {
+                return true;
+            }
+            self.path.pop();
+        }
+        false
+    }
+
+    fn start_phase2(&mut self) -> bool {
+        let cube = self
+            .path
+            .iter()
+            .fold(self.cube, |cube, &m| cube.mul(&self.tables.moves[m]));
+        let corners = perm_rank(&cube.cp);
+        let edges = perm_rank(&cube.ep[..8]);
+        let slice: Vec<u8> = cube.ep[8..].iter().map(|v| v - 8).collect();
+        let slice = perm_rank(&slice);
+
+        let phase1 = self.path.len();
+        let dist = self.tables.phase2_dist(corners, edges, slice);
+        for depth in dist..=MAX_LENGTH.saturating_sub(phase1) {
+            if self.phase2(corners, edges, slice, depth) {
+                return true;
+            }
+        }
+        false
+    }
+
+    fn phase2(&mut self, corners: usize, edges: usize, slice: usize, depth: usize) -> bool {
+        let dist = self.tables.phase2_dist(corners, edges, slice);
+        if dist == 0 && depth == 0 {
+            return true;
+        }
+        if dist > depth {
+            return false;
+        }
+        let last = self.path.last().map(|m| m / 3);
+        for (j, &m) in PHASE2_MOVES.iter().enumerate() {
+            if !allowed(m / 3, last) {
+                continue;
+            }
+            self.path.push(m);
+            let t = self.tables;
+            let found = self.phase2(
+                t.corners[corners][j] as usize,
+                t.edges[edges][j] as usize,
+                t.slice_perm[slice][j] as usize,
+                depth - 1,
+            );
+            if found {
+                return true;
+            }
+            self.path.pop();
+        }
+        false
+    }
+}
+
+/// A sequence of moves, as `face * 3 + turns - 1`, that solves `cube`.
+pub fn solve(cube: Cubie) -> Vec<usize> {
+    let tables = TABLES.get_or_init(Tables::build);
+    let mut search = Search {
+        tables,
+        cube,
+        path: vec![],
+    };
+    let (twist, flip) = (cube.twist(), cube.flip());
+    let slice = tables.mask_rank[cube.slice_mask()] as usize;
+    for depth in tables.phase1_dist(twist, flip, slice)..=MAX_LENGTH {
+        if search.phase1(twist, flip, slice, depth) {
+            return search.path;
+        }
+    }
+    unreachable!("every state solves within {} moves", MAX_LENGTH)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::cubie::format;
+    use super::*;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    #[test]
+    fn solve_solves_random_states() {
+        let mut rng = ChaCha8Rng::seed_from_u64(1);
+        let moves = moves();
+        for _ in 0..20 {
+            let cube = Cubie::random(&mut rng);
+            let solution = solve(cube);
+            assert!(solution.len() <= MAX_LENGTH);
+            let end = solution.iter().fold(cube, |c, &m| c.mul(&moves[m]));
+            assert!(end == SOLVED, "{} doesn't solve it", format(&solution));
+        }
+    }
+}
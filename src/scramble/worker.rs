@@ -0,0 +1,211 @@
+//! Scrambles generated on a thread of their own, since random-state solving can take long
+//! enough to notice between solves.
+//!
+//! While it has nothing else to do, the thread keeps a few scrambles ready for every
+//! event, so finishing a solve or switching events shows one straight away.
+
+use super::{generate, Event, Filter, Settings};
+use rand::{rngs::ThreadRng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use std::{
+    collections::VecDeque,
+    sync::mpsc::{self, Receiver, Sender, TryRecvError},
+    thread,
+};
+
+/// Scrambles kept ready for each job.
+const QUEUE: usize = 3;
+
+/// What to generate scrambles for.
+#[derive(Clone, PartialEq)]
+pub struct Job {
+    pub event: Event,
+    pub settings: Settings,
+    pub filter: Filter,
+    /// Shared to race others on the same scrambles in the same order
+    pub seed: Option<String>,
+}
+
+enum Request {
+    /// Generate for a new job from now on, tagging results with its number
+    Start(u64, Job),
+    Next,
+    /// Jobs to keep scrambles ready for, replacing the last list
+    Prefetch(Vec<Job>),
+}
+
+/// The same sequence of random numbers for a seed on every machine.
+fn seeded(seed: &str) -> ChaCha8Rng {
+    // FNV-1a, since std's hashers may differ between builds
+    let hash = seed.bytes().fold(0xcbf29ce484222325, |hash: u64, v| {
+        (hash ^ v as u64).wrapping_mul(0x100000001b3)
+    });
+    ChaCha8Rng::seed_from_u64(hash)
+}
+
+struct Thread {
+    results: Sender<(u64, String)>,
+    current: Option<(u64, Job)>,
+    /// Only set for seeded jobs, which are never queued so they stay in order
+    seeded: Option<ChaCha8Rng>,
+    rng: ThreadRng,
+    queues: Vec<(Job, VecDeque<String>)>,
+}
+
+impl Thread {
+    fn handle(&mut self, request: Request) -> Result<(), mpsc::SendError<(u64, String)>> {
+        match request {
+            Request::Start(id, job) => {
+                self.seeded = job.seed.as_deref().map(seeded);
+                self.current = Some((id, job));
+            }
+            Request::Prefetch(jobs) => {
+                let mut queues = std::mem::take(&mut self.queues);
+                self.queues = jobs
+                    .into_iter()
+                    .map(|job| match queues.iter().position(|(v, _)| *v == job) {
+                        Some(i) => queues.swap_remove(i),
+                        None => (job, VecDeque::new()),
+                    })
+                    .collect();
+            }
+            Request::Next => {
+                let Some((id, job)) = &self.current else {
+                    return Ok(());
+                };
+                let scramble = match &mut self.seeded {
+                    Some(rng) => generate(job.event, &job.settings, &job.filter, rng),
+                    None => {
+                        let ready = self.queues.iter_mut().find(|(v, _)| v == job);
+                        match ready.and_then(|(_, queue)| queue.pop_front()) {
+                            Some(v) => v,
+                            None => generate(job.event, &job.settings, &job.filter, &mut self.rng),
+                        }
+                    }
+                };
+                self.results.send((*id, scramble))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// The queue to top up next: the current job's, then the rest in order.
+    fn unfilled(&self) -> Option<usize> {
+        let current = self.current.as_ref().map(|(_, job)| job);
+        let short = |&i: &usize| self.queues[i].1.len() < QUEUE;
+        let first = self.queues.iter().position(|(job, _)| Some(job) == current);
+        first
+            .filter(short)
+            .or_else(|| (0..self.queues.len()).find(short))
+    }
+
+    fn prefetch(&mut self, i: usize) {
+        let (job, queue) = &mut self.queues[i];
+        let scramble = generate(job.event, &job.settings, &job.filter, &mut self.rng);
+        queue.push_back(scramble);
+    }
+}
+
+fn work(requests: Receiver<Request>, results: Sender<(u64, String)>) {
+    let mut thread = Thread {
+        results,
+        current: None,
+        seeded: None,
+        rng: rand::thread_rng(),
+        queues: vec![],
+    };
+    loop {
+        // Only wait for the app once every queue is full
+        let request = match thread.unfilled() {
+            Some(i) => match requests.try_recv() {
+                Ok(v) => v,
+                Err(TryRecvError::Empty) => {
+                    thread.prefetch(i);
+                    continue;
+                }
+                Err(TryRecvError::Disconnected) => return,
+            },
+            None => match requests.recv() {
+                Ok(v) => v,
+                Err(_) => return,
+            },
+        };
+        // The app has quit
+        if thread.handle(request).is_err() {
+            return;
+        }
+    }
+}
+
+/// The app's end of the scramble thread. Scrambles come back in the order asked for.
+pub struct Worker {
+    requests: Sender<Request>,
+    results: Receiver<(u64, String)>,
+    job: u64,
+}
+
+impl Worker {
+    pub fn spawn() -> Self {
+        let (requests, incoming) = mpsc::channel();
+        let (outgoing, results) = mpsc::channel();
+        thread::spawn(move || work(incoming, outgoing));
+        Self {
+            requests,
+            results,
+            job: 0,
+        }
+    }
+
+    /// Generate for `job` from now on, dropping scrambles still coming for the last one.
+    pub fn start(&mut self, job: Job) {
+        self.job += 1;
+        // Sending only fails if the thread panicked, and then nothing more will come
+        let _ = self.requests.send(Request::Start(self.job, job));
+    }
+
+    /// Keep scrambles ready for `jobs` whenever there's time.
+    pub fn prefetch(&self, jobs: Vec<Job>) {
+        let _ = self.requests.send(Request::Prefetch(jobs));
+    }
+
+    /// Ask for one more scramble.
+    pub fn request(&self) {
+        let _ = self.requests.send(Request::Next);
+    }
+
+    /// A scramble for the current job, if one is ready.
+    pub fn try_recv(&self) -> Option<String> {
+        loop {
+            match self.results.try_recv() {
+                Ok((job, scramble)) if job == self.job => return Some(scramble),
+                Ok(_) => continue,
+                Err(_) => return None,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn seeded_scrambles_never_change() {
+        // Anyone racing on an older build has to get these same scrambles
+        let mut rng = seeded("race");
+        let (settings, filter) = (Settings::default(), Filter::default());
+        assert_eq!(
+            generate(Event::Cube3, &settings, &filter, &mut rng),
+            "U L2 D2 U L2 U' L2 B2 L2 U' L' D' R' F' D2 U F' R D B2 R2"
+        );
+        assert_eq!(
+            generate(Event::Clock, &settings, &filter, &mut rng),
+            "UR4- DR1+ DL5+ UL0+ U5- R5+ D1+ L4+ ALL3+ y2 U3- R5+ D5- L1+ ALL1+"
+        );
+        let mut other = seeded("race ");
+        assert_ne!(
+            generate(Event::Clock, &settings, &filter, &mut other),
+            generate(Event::Clock, &settings, &filter, &mut seeded("race"))
+        );
+    }
+}
@@ -0,0 +1,1003 @@
+use super::app::{Penalty, Time, Times, Variation, LONGEST};
+use super::attempt::{self, Record};
+use super::backup::Backups;
+use super::custom::{CustomStat, Running};
+use super::pb::{History, Pb};
+use super::scramble::Event;
+use super::stats::{Rolling, Tally};
+use super::storage::{self, schema, Cipher, Storage, Summary};
+use super::streak::{self, Streaks};
+#[cfg(feature = "sync")]
+use super::sync::Tombstone;
+use chrono::{Local, NaiveDate};
+use ordered_float::OrderedFloat;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, HashSet},
+    error::Error,
+    fs,
+    path::{Path, PathBuf},
+};
+
+const DEFAULT_ID: &str = "default";
+/// Solves read at a time; older ones stay on disk until the table scrolls to them.
+const PAGE: usize = 500;
+/// Earlier solves needed to work out the averages of the first solve in a page.
+const CONTEXT: usize = LONGEST - 1;
+/// Files from before sessions existed, moved into the default session on first run.
+const LEGACY_FILES: [&str; 5] = [
+    "times",
+    "times.json",
+    "times.journal",
+    "times.db",
+    "backups",
+];
+
+/// The solves before one, it, and those after it.
+type Around = (Vec<Time>, Time, Vec<Time>);
+
+/// Describes a session; kept in `session.json` beside its solves, and sealed as they are.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct SessionMeta {
+    pub name: String,
+    pub event: Event,
+    /// Unix timestamp of when the session was made
+    pub created: i64,
+    /// Seconds a solve must beat to hit the session's target
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub target: Option<f32>,
+    /// How new solves are done, and whose bests the Bests row shows
+    #[serde(default, skip_serializing_if = "Variation::is_normal")]
+    pub variation: Variation,
+    /// Shares of solves to get under a time, by time
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub goals: Vec<Goal>,
+    /// Anything written down about the session, such as the cube used
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub notes: String,
+    /// Done with: left out of the session list, though still counted in records
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub archived: bool,
+    /// Labels to find the session by in the list, such as "main" or "new cube"
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+}
+
+/// A share of solves to get under a time, such as half of them sub-12.
+#[derive(Clone, Copy, Serialize, Deserialize)]
+pub struct Goal {
+    pub time: f32,
+    pub percent: f32,
+}
+
+impl SessionMeta {
+    fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            event: Event::default(),
+            created: chrono::Utc::now().timestamp(),
+            target: None,
+            variation: Variation::Normal,
+            goals: vec![],
+            notes: String::new(),
+            archived: false,
+            tags: vec![],
+        }
+    }
+}
+
+/// Where a solve stands among the rest, by the share of them it's among in whole percent.
+#[derive(Clone, Copy)]
+pub enum Standing {
+    /// The share as fast or faster, for a solve faster than most
+    Top(usize),
+    /// The share as slow or slower, for the rest
+    Bottom(usize),
+}
+
+/// A named set of solves living in its own directory.
+pub struct Session {
+    /// The session's directory name
+    pub id: String,
+    pub meta: SessionMeta,
+    meta_path: PathBuf,
+    pub times: Times,
+    /// Finished mean of 3 and average of 5 attempts, oldest first
+    pub attempts: Vec<Record>,
+    attempts_path: PathBuf,
+    /// Personal bests as they were set, worked out when first asked for, carried on to
+    /// new solves, and worked out again after any other change to the solves
+    pbs: Option<History>,
+    /// Worked out and carried on the same way, also again when the target or variation
+    /// changes
+    streaks: Option<Streaks>,
+    /// Solves on each day, worked out and carried on the same way
+    days: Option<BTreeMap<NaiveDate, usize>>,
+    /// The config's own stats, worked out when first asked for and kept up to date a solve at
+    /// a time, and again when the variation, day or week changes
+    custom: Option<Vec<Running>>,
+    /// Every solve of the variation by how it counts, fastest first and DNFs last, worked
+    /// out when first asked for and then kept in order as solves come and go
+    ranked: Option<Vec<OrderedFloat<f32>>>,
+    /// The averages ending at the newest loaded solve, for the next one to carry on from
+    rolling: Rolling,
+    /// The Bests row's numbers, started from the store when the session opens and carried
+    /// on from solve to solve
+    tally: Tally,
+    storage: Box<dyn Storage>,
+    pub cipher: Option<Cipher>,
+    pub backups: Backups,
+}
+
+impl Session {
+    fn open(dir: &Path, id: &str, cipher: Option<Cipher>) -> Result<Self, Box<dyn Error>> {
+        fs::create_dir_all(dir)?;
+        let meta_path = dir.join("session.json");
+        let fresh = !meta_path.exists();
+        let meta = match fresh {
+            true => SessionMeta::new(id),
+            false => read_meta(&meta_path, cipher.as_ref())?,
+        };
+        let path = dir.join(times_file(cipher.as_ref()));
+        let attempts_path = dir.join("attempts.json");
+        let attempts = match attempts_path.exists() {
+            true => {
+                let text = storage::unseal(&fs::read(&attempts_path)?, cipher.as_ref())?;
+                let doc = schema::ATTEMPTS.upgrade(serde_json::from_str(&text)?)?;
+                serde_json::from_value(doc["attempts"].clone())?
+            }
+            false => vec![],
+        };
+        let mut session = Self {
+            id: id.to_string(),
+            meta,
+            meta_path,
+            times: Times::new(),
+            attempts,
+            attempts_path,
+            pbs: None,
+            streaks: None,
+            days: None,
+            custom: None,
+            ranked: None,
+            rolling: Rolling::default(),
+            tally: Tally::of(&[], Variation::Normal),
+            storage: storage::open(&path, cipher.as_ref())?,
+            cipher,
+            backups: Backups::new(&path),
+        };
+        if fresh {
+            session.save_meta()?;
+        }
+        session.times.times = session.fetch(0, PAGE)?;
+        session.rolling = Rolling::of(&session.times.times);
+        session.tally = session.open_tally()?;
+        session.apply_tally();
+        session.backups.snapshot()?;
+        Ok(session)
+    }
+
+    /// Read the session in `dir` without opening it to time solves in, so nothing is
+    /// backed up or written: its description and every solve, with their averages.
+    fn peek(
+        dir: &Path,
+        cipher: Option<&Cipher>,
+    ) -> Result<(SessionMeta, Vec<Time>), Box<dyn Error>> {
+        let meta = read_meta(&dir.join("session.json"), cipher)?;
+        let times = storage::open(&dir.join(times_file(cipher)), cipher)?.load()?;
+        Ok((meta, with_stats(times)))
+    }
+
+    fn save_meta(&self) -> Result<(), Box<dyn Error>> {
+        write_meta(&self.meta_path, &self.meta, self.cipher.as_ref())
+    }
+
+    fn save_attempts(&self) -> Result<(), Box<dyn Error>> {
+        let doc = schema::ATTEMPTS.stamp(serde_json::json!({ "attempts": self.attempts }))?;
+        let text = serde_json::to_string_pretty(&doc)?;
+        let data = storage::seal(text, self.cipher.as_ref())?;
+        fs::write(&self.attempts_path, data)?;
+        Ok(())
+    }
+
+    /// Keep a finished attempt.
+    pub fn record_attempt(&mut self, record: Record) -> Result<(), Box<dyn Error>> {
+        self.attempts.push(record);
+        self.save_attempts()
+    }
+
+    /// Carry a new penalty on `solve` over to the latest attempt, if the solve finished
+    /// it, returning whether it did.
+    pub fn rescore_attempt(&mut self, solve: &Time) -> Result<bool, Box<dyn Error>> {
+        let Some(record) = self.attempts.last_mut() else {
+            return Ok(false);
+        };
+        match record.solves.last_mut() {
+            Some(last) if attempt::same(last, solve) => {
+                last.penalty = solve.penalty;
+                last.inspection_penalty = solve.inspection_penalty;
+            }
+            _ => return Ok(false),
+        }
+        record.score();
+        self.save_attempts()?;
+        Ok(true)
+    }
+
+    /// Switch the puzzle this session is for.
+    pub fn set_event(&mut self, event: Event) -> Result<(), Box<dyn Error>> {
+        self.meta.event = event;
+        self.save_meta()
+    }
+
+    pub fn set_notes(&mut self, notes: String) -> Result<(), Box<dyn Error>> {
+        self.meta.notes = notes;
+        self.save_meta()
+    }
+
+    /// Set or clear the time solves in this session aim to beat.
+    pub fn set_target(&mut self, target: Option<f32>) -> Result<(), Box<dyn Error>> {
+        self.streaks = None;
+        self.meta.target = target;
+        self.save_meta()
+    }
+
+    /// Aim for `goal`, in place of any goal for the same time.
+    pub fn set_goal(&mut self, goal: Goal) -> Result<(), Box<dyn Error>> {
+        self.meta.goals.retain(|v| v.time != goal.time);
+        self.meta.goals.push(goal);
+        self.meta.goals.sort_by(|a, b| a.time.total_cmp(&b.time));
+        self.save_meta()
+    }
+
+    pub fn clear_goals(&mut self) -> Result<(), Box<dyn Error>> {
+        self.meta.goals.clear();
+        self.save_meta()
+    }
+
+    /// Switch how new solves are done, and the bests shown to that variation's.
+    pub fn set_variation(&mut self, variation: Variation) -> Result<(), Box<dyn Error>> {
+        self.streaks = None;
+        self.custom = None;
+        self.ranked = None;
+        self.meta.variation = variation;
+        self.save_meta()?;
+        self.tally = self.open_tally()?;
+        self.apply_tally();
+        Ok(())
+    }
+
+    /// Retag the solve at `index` among those loaded, oldest first, working out every
+    /// average again since the solves around it are now of different variations.
+    pub fn tag(&mut self, index: usize, variation: Variation) -> Result<(), Box<dyn Error>> {
+        if index >= self.times.times.len() {
+            return Err("no solves yet".into());
+        }
+        let offset = self.times.count - self.times.times.len();
+        let mut times = self.all()?;
+        times[offset + index].variation = variation;
+        self.replace(times)
+    }
+
+    /// Read a page of solves, regenerating their stats rather than trusting older files.
+    fn fetch(&mut self, skip: usize, limit: usize) -> Result<Vec<Time>, Box<dyn Error>> {
+        let mut times = with_stats(self.storage.page(skip, limit + CONTEXT)?);
+        let context = times.len().saturating_sub(limit);
+        Ok(times.split_off(context))
+    }
+
+    /// Page in the next older batch of solves, returning whether there were any.
+    pub fn load_older(&mut self) -> Result<bool, Box<dyn Error>> {
+        let loaded = self.times.times.len();
+        if loaded >= self.times.count {
+            return Ok(false);
+        }
+        let mut times = self.fetch(loaded, PAGE)?;
+        times.append(&mut self.times.times);
+        self.times.times = times;
+        self.rolling = Rolling::of(&self.times.times);
+        Ok(true)
+    }
+
+    /// Page in older solves until the next one's averages have all they need.
+    fn fill_context(&mut self) -> Result<(), Box<dyn Error>> {
+        while self.times.times.len() < CONTEXT && self.load_older()? {}
+        Ok(())
+    }
+
+    /// Every solve in the session, read from storage.
+    pub fn all(&mut self) -> Result<Vec<Time>, Box<dyn Error>> {
+        Ok(with_stats(self.storage.load()?))
+    }
+
+    /// Every personal best in the session, oldest first.
+    pub fn pbs(&mut self) -> Result<&[Pb], Box<dyn Error>> {
+        let pbs = match self.pbs.take() {
+            Some(v) => v,
+            None => History::of(&self.storage.load()?),
+        };
+        Ok(self.pbs.insert(pbs).pbs())
+    }
+
+    /// The session's streaks, with sub-target ones when it has a target.
+    pub fn streaks(&mut self) -> Result<Streaks, Box<dyn Error>> {
+        let streaks = match self.streaks {
+            Some(v) => v,
+            None => streak::of(&self.storage.load()?, self.meta.variation, self.meta.target),
+        };
+        Ok(self.streaks.insert(streaks).today())
+    }
+
+    /// How many solves were done each day, of any variation.
+    pub fn days(&mut self) -> Result<&BTreeMap<NaiveDate, usize>, Box<dyn Error>> {
+        let days = match self.days.take() {
+            Some(v) => v,
+            None => streak::per_day(&self.storage.load()?),
+        };
+        Ok(self.days.insert(days))
+    }
+
+    /// The value of each of `stats` over solves of the session's variation, in order.
+    pub fn custom(
+        &mut self,
+        stats: &[CustomStat],
+    ) -> Result<Vec<Option<OrderedFloat<f32>>>, Box<dyn Error>> {
+        if stats.is_empty() {
+            return Ok(vec![]);
+        }
+        let now = Local::now();
+        // Those within a day or week are gone over again once it's over
+        let kept = self.custom.take();
+        let running = match kept.filter(|v| v.iter().all(|v| v.current(now))) {
+            Some(v) => v,
+            None => {
+                let times = self.storage.load()?;
+                let start = |v: &CustomStat| v.start(&times, self.meta.variation, now);
+                stats.iter().map(start).collect()
+            }
+        };
+        let running = self.custom.insert(running);
+        Ok(running.iter().map(Running::value).collect())
+    }
+
+    /// Where a solve counting `value` stands among the variation's solves, DNFs included.
+    pub fn standing(&mut self, value: f32) -> Result<Option<Standing>, Box<dyn Error>> {
+        let ranked = match self.ranked.take() {
+            Some(v) => v,
+            None => {
+                let variation = self.meta.variation;
+                let mut ranked: Vec<OrderedFloat<f32>> = self
+                    .storage
+                    .load()?
+                    .iter()
+                    .filter(|v| v.variation == variation)
+                    .map(|v| OrderedFloat(v.value()))
+                    .collect();
+                ranked.sort();
+                ranked
+            }
+        };
+        let ranked = self.ranked.insert(ranked);
+        let value = OrderedFloat(value);
+        let n = ranked.len();
+        if n == 0 {
+            return Ok(None);
+        }
+        // Rounded up, so the best solve is in the top 1% and the worst in the bottom 1%
+        let share = |count: usize| (count * 100).div_ceil(n);
+        let top = share(ranked.partition_point(|v| *v <= value));
+        Ok(Some(match top {
+            0..=50 => Standing::Top(top),
+            _ => Standing::Bottom(share(n - ranked.partition_point(|v| *v < value))),
+        }))
+    }
+
+    /// Drop what was worked out from the whole history, after the solves changed.
+    fn forget(&mut self) {
+        self.pbs = None;
+        self.streaks = None;
+        self.days = None;
+    }
+
+    /// Carry what was worked out from the whole history on to `time`, the newest solve.
+    fn carry_on(&mut self, time: &Time) {
+        if let Some(pbs) = &mut self.pbs {
+            pbs.push(time);
+        }
+        if let Some(streaks) = &mut self.streaks {
+            streaks.push(time, self.meta.variation, self.meta.target);
+        }
+        if let Some(days) = &mut self.days {
+            streak::count_day(days, time);
+        }
+    }
+
+    /// Record a just-finished solve.
+    pub fn push(&mut self, mut time: Time) -> Result<(), Box<dyn Error>> {
+        self.fill_context()?;
+        time.variation = self.meta.variation;
+        self.rolling.push(&mut time);
+        self.tally.push(&time);
+        self.carry_on(&time);
+        self.custom_push(&time);
+        self.rank(&time);
+        self.storage.push(&time)?;
+        self.times.insert(time);
+        self.apply_tally();
+        if self.backups.solved() {
+            self.storage.flush()?;
+            self.backups.snapshot()?;
+        }
+        Ok(())
+    }
+
+    /// Remove the solve at `index` among those loaded, oldest first.
+    ///
+    /// The averages of the solves of its variation after it took it in, so theirs are
+    /// worked out again, written back and swapped in the tally; nothing before the solves
+    /// they need is read.
+    pub fn remove(&mut self, index: usize) -> Result<(), Box<dyn Error>> {
+        if index >= self.times.times.len() {
+            return Ok(());
+        }
+        self.forget();
+        let offset = self.times.count - self.times.times.len();
+        let at = offset + index;
+        let variation = self.times.times[index].variation;
+        let (before, removed, mut after) = self.around(at, variation)?;
+        let whole = before.len() == at;
+        self.storage.remove(at)?;
+        #[cfg(feature = "sync")]
+        if let Some(date) = removed.date {
+            self.bury(&[(date, removed.time)])?;
+        }
+        self.times.times.remove(index);
+        self.times.count -= 1;
+        self.unrank(&removed);
+        self.tally.remove(&removed);
+        let mut custom = self.custom.iter_mut().flatten();
+        if !custom.all(|v| v.remove(&removed, &before, whole, &after)) {
+            // Only when a window reaches back further than was read
+            self.custom = None;
+        }
+
+        // Only the next solves of its variation up to the longest average had it in theirs
+        let mut rolling = Rolling::of(&before);
+        let mut changed = None;
+        let later = after.iter_mut().enumerate().filter(|v| v.1.variation == variation);
+        for (i, time) in later.take(LONGEST) {
+            let old = time.clone();
+            rolling.push(time);
+            if storage::averages(&old) != storage::averages(time) {
+                self.tally.reaverage(&old, time);
+                changed = Some(i);
+            }
+        }
+        if let Some(last) = changed {
+            self.storage.update_from(at, &after[..=last])?;
+        }
+        if variation == self.meta.variation {
+            let mut newest = after.iter().rev().chain(before.iter().rev());
+            self.tally.set_newest(newest.find(|v| v.variation == variation));
+        }
+        // The loaded solves are the newest, so they end where the session does
+        for (time, fresh) in self.times.times[index..].iter_mut().zip(&after) {
+            if time.variation == variation {
+                *time = fresh.clone();
+            }
+        }
+        self.apply_tally();
+        // Keep enough history loaded for the next solve's averages
+        self.fill_context()?;
+        self.rolling = Rolling::of(&self.times.times);
+        Ok(())
+    }
+
+    /// The solves before the one at `at`, it, and those after it to the newest, as stored.
+    /// Enough come before for the averages of `variation` after it, unless the session
+    /// starts first.
+    fn around(&mut self, at: usize, variation: Variation) -> Result<Around, Box<dyn Error>> {
+        let mut context = CONTEXT;
+        loop {
+            let start = at.saturating_sub(context);
+            let mut times = self.storage.page(0, self.times.count - start)?;
+            let mut after = times.split_off(at - start);
+            let enough = times.iter().filter(|v| v.variation == variation).count() >= CONTEXT;
+            if enough || start == 0 {
+                let removed = after.remove(0);
+                return Ok((times, removed, after));
+            }
+            context *= 2;
+        }
+    }
+
+    /// Give the newest solve `penalty`, working its averages out again.
+    pub fn set_last_penalty(&mut self, penalty: Penalty) -> Result<(), Box<dyn Error>> {
+        self.forget();
+        self.fill_context()?;
+        let last = self
+            .times
+            .times
+            .len()
+            .checked_sub(1)
+            .ok_or("no solves yet")?;
+        let mut time = self.times.times[last].clone();
+        self.unrank(&time);
+        time.penalty = penalty;
+        time.inspection_penalty = false;
+        self.rolling.pop(time.variation);
+        self.rolling.push(&mut time);
+        // Counted in again with its new penalty, taken out as the tally has it if it wasn't
+        // the last counted in
+        if !self.tally.pop() {
+            let stored = self.storage.page(0, 1)?;
+            self.tally.remove(stored.first().unwrap_or(&self.times.times[last]));
+        }
+        self.tally.push(&time);
+        self.storage.update(self.times.count - 1, &time)?;
+        let (before, old) = self.times.times.split_at(last);
+        let whole = self.times.count == self.times.times.len();
+        let mut custom = self.custom.iter_mut().flatten();
+        match custom.all(|v| v.pop() || v.remove(&old[0], before, whole, &[])) {
+            true => self.custom_push(&time),
+            false => self.custom = None,
+        }
+        self.rank(&time);
+        self.times.times[last] = time;
+        self.apply_tally();
+        Ok(())
+    }
+
+    /// The tally of the session's solves of its variation, read from the store.
+    fn open_tally(&mut self) -> Result<Tally, Box<dyn Error>> {
+        let variation = self.meta.variation;
+        Ok(Tally::open(
+            &self.storage.summary(variation)?,
+            &self.storage.solves(variation)?,
+            &self.storage.averages(variation)?,
+            variation,
+        ))
+    }
+
+    /// Count `time` into the custom stats being kept as the newest solve.
+    fn custom_push(&mut self, time: &Time) {
+        for running in self.custom.iter_mut().flatten() {
+            running.push(time);
+        }
+    }
+
+    /// Put `time` among the ranked values, if they're kept and it's of the variation.
+    fn rank(&mut self, time: &Time) {
+        let variation = self.meta.variation;
+        if let Some(ranked) = self.ranked.as_mut().filter(|_| time.variation == variation) {
+            let value = OrderedFloat(time.value());
+            ranked.insert(ranked.partition_point(|v| *v <= value), value);
+        }
+    }
+
+    /// Take `time` back out of the ranked values.
+    fn unrank(&mut self, time: &Time) {
+        let variation = self.meta.variation;
+        if let Some(ranked) = self.ranked.as_mut().filter(|_| time.variation == variation) {
+            let value = OrderedFloat(time.value());
+            let at = ranked.partition_point(|v| *v < value);
+            if ranked.get(at) == Some(&value) {
+                ranked.remove(at);
+            }
+        }
+    }
+
+    /// The Bests row's numbers as they stand.
+    pub fn summary(&self) -> Summary {
+        self.tally.summary()
+    }
+
+    /// Show the tally's numbers.
+    fn apply_tally(&mut self) {
+        self.times.apply(self.tally.summary());
+    }
+
+    /// Add solves to the end of the session as though they had just been timed.
+    pub fn append(&mut self, times: Vec<Time>) -> Result<(), Box<dyn Error>> {
+        self.fill_context()?;
+        for mut time in times {
+            time.variation = self.meta.variation;
+            self.rolling.push(&mut time);
+            self.tally.push(&time);
+            self.carry_on(&time);
+            self.custom_push(&time);
+            self.rank(&time);
+            self.storage.push(&time)?;
+            self.times.insert(time);
+        }
+        self.apply_tally();
+        Ok(())
+    }
+
+    /// Fold solves from elsewhere into the session, returning how many were new.
+    pub fn merge(&mut self, incoming: Vec<Time>) -> Result<usize, Box<dyn Error>> {
+        self.merge_except(incoming, &HashSet::new())
+    }
+
+    /// Take in a synced copy's solves and the ones deleted from it, returning how many
+    /// solves were new. Solves deleted on either side are left out of both.
+    #[cfg(feature = "sync")]
+    pub fn sync_in(
+        &mut self,
+        incoming: Vec<Time>,
+        deleted: &[Tombstone],
+    ) -> Result<usize, Box<dyn Error>> {
+        self.bury(deleted)?;
+        let buried = self.deleted()?.iter().map(|v| (v.0, v.1.to_bits())).collect();
+        self.merge_except(incoming, &buried)
+    }
+
+    /// Merge `incoming`, dropping solves on either side whose date and time are `buried`.
+    fn merge_except(
+        &mut self,
+        incoming: Vec<Time>,
+        buried: &HashSet<(i64, u32)>,
+    ) -> Result<usize, Box<dyn Error>> {
+        let mut times = self.all()?;
+        times.retain(|v| !v.date.is_some_and(|date| buried.contains(&(date, v.time.to_bits()))));
+        let mut seen: HashSet<(i64, u32)> = times
+            .iter()
+            .filter_map(|v| Some((v.date?, v.time.to_bits())))
+            .chain(buried.iter().copied())
+            .collect();
+        let before = times.len();
+        for time in incoming {
+            // Solves without a date can't be told apart, so they're always taken
+            if let Some(date) = time.date {
+                if !seen.insert((date, time.time.to_bits())) {
+                    continue;
+                }
+            }
+            times.push(time);
+        }
+        let added = times.len() - before;
+
+        // Stable, so undated solves keep their relative order at the front
+        times.sort_by_key(|v| v.date);
+        self.replace(times)?;
+        Ok(added)
+    }
+
+    /// Swap every solve in the session for `times`.
+    pub fn replace(&mut self, times: Vec<Time>) -> Result<(), Box<dyn Error>> {
+        self.forget();
+        let mut times = with_stats(times);
+        self.storage.save(&times)?;
+        self.times.times = times.split_off(times.len().saturating_sub(PAGE));
+        self.rolling = Rolling::of(&self.times.times);
+        self.tally = self.open_tally()?;
+        self.custom = None;
+        self.ranked = None;
+        self.apply_tally();
+        Ok(())
+    }
+
+    /// The solves deleted from the session, for syncing to leave out.
+    #[cfg(feature = "sync")]
+    pub fn deleted(&self) -> Result<Vec<Tombstone>, Box<dyn Error>> {
+        let path = self.meta_path.with_file_name("deleted.json");
+        if !path.exists() {
+            return Ok(vec![]);
+        }
+        let text = storage::unseal(&fs::read(&path)?, self.cipher.as_ref())?;
+        let doc = schema::DELETED.upgrade(serde_json::from_str(&text)?)?;
+        Ok(serde_json::from_value(doc["deleted"].clone())?)
+    }
+
+    /// Note solves as deleted, once each.
+    #[cfg(feature = "sync")]
+    fn bury(&self, tombstones: &[Tombstone]) -> Result<(), Box<dyn Error>> {
+        let mut deleted = self.deleted()?;
+        let mut seen: HashSet<(i64, u32)> = deleted.iter().map(|v| (v.0, v.1.to_bits())).collect();
+        let before = deleted.len();
+        deleted.extend(tombstones.iter().filter(|v| seen.insert((v.0, v.1.to_bits()))));
+        if deleted.len() == before {
+            return Ok(());
+        }
+        let doc = schema::DELETED.stamp(serde_json::json!({ "deleted": deleted }))?;
+        let data = storage::seal(serde_json::to_string_pretty(&doc)?, self.cipher.as_ref())?;
+        fs::write(self.meta_path.with_file_name("deleted.json"), data)?;
+        Ok(())
+    }
+
+    /// Replace the session with a backup, snapshotting the current state first.
+    pub fn restore(&mut self, backup: &Path) -> Result<usize, Box<dyn Error>> {
+        let times = storage::open(backup, self.cipher.as_ref())?.load()?;
+        self.storage.flush()?;
+        self.backups.snapshot()?;
+        let count = times.len();
+        self.replace(times)?;
+        Ok(count)
+    }
+
+    pub fn flush(&mut self) -> Result<(), Box<dyn Error>> {
+        self.storage.flush()
+    }
+
+    /// What happened while opening the session that the user should know about.
+    pub fn take_notice(&mut self) -> Option<String> {
+        self.storage.take_notice()
+    }
+}
+
+fn read_meta(path: &Path, cipher: Option<&Cipher>) -> Result<SessionMeta, Box<dyn Error>> {
+    let text = storage::unseal(&fs::read(path)?, cipher)?;
+    let doc = serde_json::from_str(&text)?;
+    Ok(serde_json::from_value(schema::SESSION.upgrade(doc)?)?)
+}
+
+fn write_meta(
+    path: &Path,
+    meta: &SessionMeta,
+    cipher: Option<&Cipher>,
+) -> Result<(), Box<dyn Error>> {
+    let doc = schema::SESSION.stamp(serde_json::to_value(meta)?)?;
+    let data = storage::seal(serde_json::to_string_pretty(&doc)?, cipher)?;
+    fs::write(path, data)?;
+    Ok(())
+}
+
+/// The file a session's solves are kept in. SQLite can't be encrypted, so an encrypted
+/// session stays in JSON.
+fn times_file(cipher: Option<&Cipher>) -> &'static str {
+    if cfg!(feature = "sqlite") && cipher.is_none() {
+        "times.db"
+    } else {
+        "times.json"
+    }
+}
+
+/// Work out each solve's averages from the ones before it.
+fn with_stats(mut times: Vec<Time>) -> Vec<Time> {
+    let mut rolling = Rolling::default();
+    for time in &mut times {
+        rolling.push(time);
+    }
+    times
+}
+
+/// Owns the data directory, where each session has a directory under `sessions/`.
+pub struct SessionManager {
+    pub current: Session,
+    /// Where each session has its directory
+    dir: PathBuf,
+    /// Holds the current session's id, to pick it up again next run
+    current_path: PathBuf,
+}
+
+impl SessionManager {
+    pub fn open(data_dir: &Path, cipher: Option<Cipher>) -> Result<Self, Box<dyn Error>> {
+        let sessions_dir = data_dir.join("sessions");
+        fs::create_dir_all(&sessions_dir)?;
+        migrate_legacy(data_dir, &sessions_dir.join(DEFAULT_ID))?;
+
+        // Pick up where the last run left off
+        let current_path = data_dir.join("current");
+        let id = fs::read_to_string(&current_path)
+            .map(|v| v.trim().to_string())
+            .ok()
+            .filter(|v| !v.is_empty() && sessions_dir.join(v).is_dir())
+            .unwrap_or_else(|| DEFAULT_ID.to_string());
+        fs::write(&current_path, &id)?;
+
+        Ok(Self {
+            current: Session::open(&sessions_dir.join(&id), &id, cipher)?,
+            dir: sessions_dir,
+            current_path,
+        })
+    }
+
+    /// Every session by id, sorted, with its description.
+    pub fn list(&self) -> Result<Vec<(String, SessionMeta)>, Box<dyn Error>> {
+        let mut sessions = vec![];
+        for id in self.ids()? {
+            let path = self.dir.join(&id).join("session.json");
+            // Anything else left in the directory isn't a session
+            if path.exists() {
+                sessions.push((id, read_meta(&path, self.current.cipher.as_ref())?));
+            }
+        }
+        Ok(sessions)
+    }
+
+    /// Make session `id` the current one, starting it if there's no such session yet, and
+    /// return whether it was started.
+    pub fn switch(&mut self, id: &str) -> Result<bool, Box<dyn Error>> {
+        check_id(id)?;
+        if id == self.current.id {
+            return Ok(false);
+        }
+        let dir = self.dir.join(id);
+        let fresh = !dir.join("session.json").exists();
+        self.current.flush()?;
+        let cipher = self.current.cipher.clone();
+        self.current = Session::open(&dir, id, cipher)?;
+        fs::write(&self.current_path, id)?;
+        Ok(fresh)
+    }
+
+    /// Give session `id` the name `to`, moving its directory.
+    pub fn rename(&mut self, id: &str, to: &str) -> Result<(), Box<dyn Error>> {
+        check_id(to)?;
+        let old = self.existing(id)?;
+        let dir = self.dir.join(to);
+        if dir.exists() {
+            return Err(format!("there's a session {} already", to).into());
+        }
+        let current = id == self.current.id;
+        if current {
+            self.current.flush()?;
+        }
+        fs::rename(old, &dir)?;
+        let path = dir.join("session.json");
+        let cipher = self.current.cipher.clone();
+        let mut meta = read_meta(&path, cipher.as_ref())?;
+        meta.name = to.to_string();
+        write_meta(&path, &meta, cipher.as_ref())?;
+        // Its files are somewhere else now
+        if current {
+            self.current = Session::open(&dir, to, cipher)?;
+            fs::write(&self.current_path, to)?;
+        }
+        Ok(())
+    }
+
+    /// Delete session `id` with every solve and backup of it. The current session can't be.
+    pub fn delete(&mut self, id: &str) -> Result<(), Box<dyn Error>> {
+        if id == self.current.id {
+            return Err("switch to another session to delete this one".into());
+        }
+        fs::remove_dir_all(self.existing(id)?)?;
+        Ok(())
+    }
+
+    /// Archive session `id`, or bring it back. The current session can't be archived.
+    pub fn set_archived(&mut self, id: &str, archived: bool) -> Result<(), Box<dyn Error>> {
+        if id == self.current.id && archived {
+            return Err("switch to another session to archive this one".into());
+        }
+        self.update_meta(id, |meta| meta.archived = archived)
+    }
+
+    /// Label session `id` with `tags`, in place of those it had.
+    pub fn set_tags(&mut self, id: &str, tags: Vec<String>) -> Result<(), Box<dyn Error>> {
+        self.update_meta(id, |meta| meta.tags = tags)
+    }
+
+    /// Change the description of session `id`, which needn't be the current one.
+    fn update_meta(
+        &mut self,
+        id: &str,
+        change: impl FnOnce(&mut SessionMeta),
+    ) -> Result<(), Box<dyn Error>> {
+        if id == self.current.id {
+            change(&mut self.current.meta);
+            return self.current.save_meta();
+        }
+        let path = self.existing(id)?.join("session.json");
+        let cipher = self.current.cipher.as_ref();
+        let mut meta = read_meta(&path, cipher)?;
+        change(&mut meta);
+        write_meta(&path, &meta, cipher)
+    }
+
+    /// The directory of session `id`, if there's such a session.
+    fn existing(&self, id: &str) -> Result<PathBuf, Box<dyn Error>> {
+        check_id(id)?;
+        let dir = self.dir.join(id);
+        match dir.join("session.json").exists() {
+            true => Ok(dir),
+            false => Err(format!("no session {}", id).into()),
+        }
+    }
+
+    /// The directory names of every session, sorted.
+    pub fn ids(&self) -> Result<Vec<String>, Box<dyn Error>> {
+        let mut ids = vec![];
+        for entry in fs::read_dir(&self.dir)? {
+            let entry = entry?;
+            if entry.file_type()?.is_dir() {
+                ids.push(entry.file_name().to_string_lossy().into_owned());
+            }
+        }
+        ids.sort();
+        Ok(ids)
+    }
+
+    /// The description and every solve of session `id`, which needn't be the current one.
+    pub fn peek(&mut self, id: &str) -> Result<(SessionMeta, Vec<Time>), Box<dyn Error>> {
+        if id == self.current.id {
+            return Ok((self.current.meta.clone(), self.current.all()?));
+        }
+        let dir = self.dir.join(id);
+        if !dir.join("session.json").exists() {
+            let ids = self.ids()?.join(", ");
+            return Err(format!("no session {}; there are {}", id, ids).into());
+        }
+        Session::peek(&dir, self.current.cipher.as_ref())
+    }
+}
+
+/// A session's id names its directory, so it's kept to what's safe in a path.
+fn check_id(id: &str) -> Result<(), Box<dyn Error>> {
+    let safe = |v: char| v.is_alphanumeric() || matches!(v, '-' | '_' | '.');
+    match id.chars().all(safe) && !id.is_empty() && !id.starts_with('.') {
+        true => Ok(()),
+        false => Err(format!("not a session name: \"{}\" (letters, digits, - _ .)", id).into()),
+    }
+}
+
+/// Move the single data file of older versions into the default session.
+fn migrate_legacy(data_dir: &Path, dest: &Path) -> Result<(), Box<dyn Error>> {
+    if dest.exists() {
+        return Ok(());
+    }
+    let found: Vec<PathBuf> = LEGACY_FILES
+        .iter()
+        .map(|v| data_dir.join(v))
+        .filter(|v| v.exists())
+        .collect();
+    if found.is_empty() {
+        return Ok(());
+    }
+    fs::create_dir_all(dest)?;
+    for path in found {
+        if let Some(name) = path.file_name() {
+            fs::rename(&path, dest.join(name))?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(all(test, any(feature = "encrypt", feature = "sync")))]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[cfg(feature = "encrypt")]
+    fn notes_are_sealed_with_the_key() {
+        let dir = std::env::temp_dir().join(format!("cube-tui-meta-{}", std::process::id()));
+        let cipher = Cipher::with_passphrase(&dir, "hunter2").unwrap();
+        let path = dir.join("session.json");
+        let mut meta = SessionMeta::new("main");
+        meta.notes = "new cube, still breaking in".to_string();
+        write_meta(&path, &meta, Some(&cipher)).unwrap();
+
+        let data = fs::read(&path).unwrap();
+        assert!(!String::from_utf8_lossy(&data).contains("breaking in"));
+        assert!(read_meta(&path, None).is_err());
+        assert_eq!(read_meta(&path, Some(&cipher)).unwrap().notes, meta.notes);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    #[cfg(feature = "sync")]
+    fn sync_leaves_out_solves_deleted_on_either_side() {
+        let dir = std::env::temp_dir().join(format!("cube-tui-sync-{}", std::process::id()));
+        let mut session = Session::open(&dir, "main", None).unwrap();
+        let solve = |time: f32, date: i64| {
+            let mut solve = Time::from(time);
+            solve.date = Some(date);
+            solve
+        };
+        for (time, date) in [(10.0, 100), (11.0, 200), (12.0, 300)] {
+            session.push(solve(time, date)).unwrap();
+        }
+        session.remove(0).unwrap();
+        assert_eq!(session.deleted().unwrap(), [(100, 10.0)]);
+
+        // The remote still has the first, and deleted the second
+        let incoming = vec![solve(10.0, 100), solve(13.0, 400)];
+        let added = session.sync_in(incoming, &[(200, 11.0)]).unwrap();
+        assert_eq!(added, 1);
+        let left: Vec<f32> = session.all().unwrap().iter().map(|v| v.time).collect();
+        assert_eq!(left, [12.0, 13.0]);
+        assert_eq!(session.deleted().unwrap(), [(100, 10.0), (200, 11.0)]);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
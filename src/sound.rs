@@ -0,0 +1,31 @@
+//! Sounds made without looking at the screen: the terminal's bell, or a command of the
+//! user's own for anything more.
+
+use std::{
+    error::Error,
+    io::{self, Write},
+    process::{Command, Stdio},
+    thread,
+};
+
+/// Ring the terminal's bell, the one sound every terminal can make.
+pub fn bell() -> Result<(), Box<dyn Error>> {
+    let mut stdout = io::stdout();
+    write!(stdout, "\x07")?;
+    stdout.flush()?;
+    Ok(())
+}
+
+/// Run `command` with `sh -c` and `arg` as `$1`, leaving it to play while the app goes on.
+pub fn play(command: &str, arg: &str) -> Result<(), Box<dyn Error>> {
+    let mut child = Command::new("sh")
+        .args(["-c", command, "sh", arg])
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|err| format!("{}: {}", command, err))?;
+    // Waited on elsewhere so it doesn't linger as a zombie
+    thread::spawn(move || child.wait());
+    Ok(())
+}
@@ -0,0 +1,851 @@
+//! Rolling means and trimmed averages, kept up to date a solve at a time, and the Bests
+//! row's numbers over the whole session with them.
+//!
+//! Each window splits its solves three ways: the fastest that are trimmed, the slowest that
+//! are trimmed, and the middle that count, whose sum is kept. Adding the newest solve and
+//! dropping the oldest moves at most a few solves between them, so it costs `O(log n)` in
+//! the window rather than sorting the window again. The session's median is kept the same
+//! way, split into halves, and the rest of its numbers are sums, counts and bests.
+
+use super::app::{trim, Penalty, Time, Variation, LONGEST};
+use super::storage::{averages, Averages, Outcome, Spread, Summary, SITTING_GAP};
+use ordered_float::OrderedFloat;
+use std::collections::{BTreeMap, VecDeque};
+
+/// The windows worked out for every solve: a mean of 3, then trimmed averages.
+const WINDOWS: [usize; 6] = [3, 5, 12, 50, 100, 1000];
+
+/// A sorted bag of solve values, which may repeat.
+#[derive(Default)]
+pub struct Bag {
+    counts: BTreeMap<OrderedFloat<f32>, usize>,
+    len: usize,
+}
+
+impl Bag {
+    pub fn insert(&mut self, v: OrderedFloat<f32>) {
+        *self.counts.entry(v).or_default() += 1;
+        self.len += 1;
+    }
+
+    /// Take one `v` out, returning whether there was one.
+    pub fn remove(&mut self, v: OrderedFloat<f32>) -> bool {
+        let Some(count) = self.counts.get_mut(&v) else {
+            return false;
+        };
+        *count -= 1;
+        if *count == 0 {
+            self.counts.remove(&v);
+        }
+        self.len -= 1;
+        true
+    }
+
+    pub fn first(&self) -> Option<OrderedFloat<f32>> {
+        self.counts.keys().next().copied()
+    }
+
+    pub fn last(&self) -> Option<OrderedFloat<f32>> {
+        self.counts.keys().next_back().copied()
+    }
+}
+
+/// The middle of a window, with its sum kept apart from the DNFs in it so they can leave
+/// again without making the sum a NaN.
+#[derive(Default)]
+struct Middle {
+    bag: Bag,
+    sum: f64,
+    dnfs: usize,
+}
+
+impl Middle {
+    fn insert(&mut self, v: OrderedFloat<f32>) {
+        match v.is_finite() {
+            true => self.sum += v.0 as f64,
+            false => self.dnfs += 1,
+        }
+        self.bag.insert(v);
+    }
+
+    fn remove(&mut self, v: OrderedFloat<f32>) -> bool {
+        if !self.bag.remove(v) {
+            return false;
+        }
+        match v.is_finite() {
+            true => self.sum -= v.0 as f64,
+            false => self.dnfs -= 1,
+        }
+        true
+    }
+}
+
+/// The trimmed mean of the latest solves, as `app::trimmed_mean` takes it.
+struct Window {
+    size: usize,
+    trim: usize,
+    fastest: Bag,
+    middle: Middle,
+    slowest: Bag,
+}
+
+impl Window {
+    fn new(size: usize) -> Self {
+        // A mean of 3 has nothing trimmed; averages lose 5% at each end, rounded up
+        let trim = match size {
+            3 => 0,
+            n => trim(n),
+        };
+        Self {
+            size,
+            trim,
+            fastest: Bag::default(),
+            middle: Middle::default(),
+            slowest: Bag::default(),
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.fastest.len + self.middle.bag.len + self.slowest.len
+    }
+
+    fn insert(&mut self, v: f32) {
+        let v = OrderedFloat(v);
+        if self.fastest.last().is_some_and(|last| v < last) {
+            self.fastest.insert(v);
+        } else if self.slowest.first().is_some_and(|first| v > first) {
+            self.slowest.insert(v);
+        } else {
+            self.middle.insert(v);
+        }
+        self.balance();
+    }
+
+    fn remove(&mut self, v: f32) {
+        let v = OrderedFloat(v);
+        // Equal values are interchangeable, so any part holding one will do
+        let _ = self.fastest.remove(v) || self.slowest.remove(v) || self.middle.remove(v);
+        self.balance();
+    }
+
+    /// Move solves between the parts until each end holds as many as are trimmed.
+    fn balance(&mut self) {
+        while self.fastest.len > self.trim {
+            let v = self.fastest.last().unwrap_or_default();
+            self.fastest.remove(v);
+            self.middle.insert(v);
+        }
+        while self.slowest.len > self.trim {
+            let v = self.slowest.first().unwrap_or_default();
+            self.slowest.remove(v);
+            self.middle.insert(v);
+        }
+        while self.fastest.len < self.trim {
+            let Some(v) = self.middle.bag.first() else {
+                break;
+            };
+            self.middle.remove(v);
+            self.fastest.insert(v);
+        }
+        while self.slowest.len < self.trim {
+            let Some(v) = self.middle.bag.last() else {
+                break;
+            };
+            self.middle.remove(v);
+            self.slowest.insert(v);
+        }
+    }
+
+    /// The mean of the middle, once the window is full.
+    fn value(&self) -> Option<OrderedFloat<f32>> {
+        if self.len() < self.size {
+            return None;
+        }
+        Some(match self.middle.dnfs {
+            0 => OrderedFloat((self.middle.sum / self.middle.bag.len as f64) as f32),
+            _ => OrderedFloat(f32::INFINITY),
+        })
+    }
+}
+
+/// The latest solves of one variation, with a window of each size over them.
+struct Track {
+    variation: Variation,
+    /// Enough of the latest values to refill the largest window when one is taken back
+    values: VecDeque<f32>,
+    windows: [Window; 6],
+}
+
+impl Track {
+    fn new(variation: Variation) -> Self {
+        Self {
+            variation,
+            values: VecDeque::new(),
+            windows: WINDOWS.map(Window::new),
+        }
+    }
+
+    fn push(&mut self, v: f32) {
+        self.values.push_back(v);
+        let len = self.values.len();
+        for window in &mut self.windows {
+            window.insert(v);
+            if len > window.size {
+                window.remove(self.values[len - 1 - window.size]);
+            }
+        }
+        if len > LONGEST + 1 {
+            self.values.pop_front();
+        }
+    }
+
+    fn pop(&mut self) {
+        let Some(v) = self.values.pop_back() else {
+            return;
+        };
+        let len = self.values.len();
+        for window in &mut self.windows {
+            window.remove(v);
+            if len >= window.size {
+                window.insert(self.values[len - window.size]);
+            }
+        }
+    }
+}
+
+/// Every variation's windows over a session's solves, oldest first.
+#[derive(Default)]
+pub struct Rolling {
+    tracks: Vec<Track>,
+}
+
+impl Rolling {
+    /// The windows over `times`, ready for the solve after them.
+    pub fn of(times: &[Time]) -> Self {
+        let mut rolling = Self::default();
+        for time in times {
+            rolling.track(time.variation).push(time.value());
+        }
+        rolling
+    }
+
+    fn track(&mut self, variation: Variation) -> &mut Track {
+        let at = match self.tracks.iter().position(|v| v.variation == variation) {
+            Some(at) => at,
+            None => {
+                self.tracks.push(Track::new(variation));
+                self.tracks.len() - 1
+            }
+        };
+        &mut self.tracks[at]
+    }
+
+    /// Add `time` as the newest solve, filling in the averages ending at it.
+    pub fn push(&mut self, time: &mut Time) {
+        let track = self.track(time.variation);
+        track.push(time.value());
+        let [mo3, ao5, ao12, ao50, ao100, ao1000] = track.windows.each_ref().map(Window::value);
+        time.mo3 = mo3;
+        time.ao5 = ao5;
+        time.ao12 = ao12;
+        time.ao50 = ao50;
+        time.ao100 = ao100;
+        time.ao1000 = ao1000;
+    }
+
+    /// Take back the newest solve of `variation`, as before it was pushed. Only the one is
+    /// kept to take back between pushes.
+    pub fn pop(&mut self, variation: Variation) {
+        self.track(variation).pop();
+    }
+}
+
+/// Every value of a variation's solves split into a slower and a faster half, the faster
+/// having the one extra, so the median is at the ends where they meet.
+#[derive(Default)]
+struct Median {
+    low: Bag,
+    high: Bag,
+}
+
+impl Median {
+    fn insert(&mut self, v: f32) {
+        let v = OrderedFloat(v);
+        match self.high.first().is_some_and(|first| v >= first) {
+            true => self.high.insert(v),
+            false => self.low.insert(v),
+        }
+        self.balance();
+    }
+
+    fn remove(&mut self, v: f32) {
+        let v = OrderedFloat(v);
+        let _ = self.low.remove(v) || self.high.remove(v);
+        self.balance();
+    }
+
+    fn balance(&mut self) {
+        while self.low.len > self.high.len + 1 {
+            let v = self.low.last().unwrap_or_default();
+            self.low.remove(v);
+            self.high.insert(v);
+        }
+        while self.high.len > self.low.len {
+            let v = self.high.first().unwrap_or_default();
+            self.high.remove(v);
+            self.low.insert(v);
+        }
+    }
+
+    fn value(&self) -> Option<OrderedFloat<f32>> {
+        let low = self.low.last()?;
+        match self.low.len > self.high.len {
+            true => Some(low),
+            false => Some((low + self.high.first()?) / 2.0),
+        }
+    }
+}
+
+/// What a tally is made of besides its bags, small enough to copy to take a solve back.
+#[derive(Default, Clone, Copy)]
+struct Counts {
+    /// Solves of every variation
+    count: usize,
+    /// The ao50, ao100 and ao1000 ending at the newest solve
+    latest: [Option<OrderedFloat<f32>>; 3],
+    solves: usize,
+    dnfs: usize,
+    plus_twos: usize,
+    practice: f32,
+    /// Solves that didn't DNF, with their mean and summed squared deviation, as Welford
+    /// keeps them
+    solved: usize,
+    mean: f64,
+    squares: f64,
+    /// The dated solves, with their sittings and how long those took
+    dated: usize,
+    sittings: usize,
+    sat: f32,
+}
+
+/// What the newest solve put in a tally, so it can be taken back.
+struct Before {
+    counts: Counts,
+    /// The value it put in the median
+    solved: Option<f32>,
+    /// The averages it put in the bags
+    averages: Averages,
+    date: Option<i64>,
+}
+
+/// The Bests row's numbers over the solves of one variation, kept up to date a solve at a
+/// time as the averages are, so timing, deleting or penalizing one doesn't go back over the
+/// whole session.
+pub struct Tally {
+    variation: Variation,
+    counts: Counts,
+    median: Median,
+    /// Every finite mo3 and average of each size, so a best taken away leaves the next
+    averages: [Bag; 6],
+    /// The times of the solves finishing at each date, in session order, which the
+    /// sittings go by
+    dates: BTreeMap<i64, Vec<f32>>,
+    before: Option<Before>,
+}
+
+impl Tally {
+    fn new(variation: Variation) -> Self {
+        Self {
+            variation,
+            counts: Counts::default(),
+            median: Median::default(),
+            averages: Default::default(),
+            dates: BTreeMap::new(),
+            before: None,
+        }
+    }
+
+    /// The tally of `times`, oldest first, whose averages have been worked out.
+    pub fn of(times: &[Time], variation: Variation) -> Self {
+        let mut tally = Self::new(variation);
+        for time in times {
+            tally.push(time);
+        }
+        tally.before = None;
+        tally
+    }
+
+    /// The tally of a store's solves without reading them whole: the count and latest
+    /// averages from its `summary`, the rest from how each solve of `variation` went and
+    /// the averages kept with it, oldest first.
+    pub fn open(
+        summary: &Summary,
+        solves: &[Outcome],
+        averages: &[Averages],
+        variation: Variation,
+    ) -> Self {
+        let mut tally = Self::new(variation);
+        for &(penalty, time, date) in solves {
+            tally.count(penalty, time, date);
+        }
+        for solve in averages {
+            tally.insert_averages(solve);
+        }
+        tally.counts.count = summary.count;
+        tally.counts.latest = [summary.ao50, summary.ao100, summary.ao1000];
+        tally
+    }
+
+    fn insert_averages(&mut self, averages: &Averages) {
+        for (bag, v) in self.averages.iter_mut().zip(averages) {
+            if let Some(v) = v.filter(|v| v.is_finite()) {
+                bag.insert(v);
+            }
+        }
+    }
+
+    fn remove_averages(&mut self, averages: &Averages) {
+        for (bag, v) in self.averages.iter_mut().zip(averages) {
+            if let Some(v) = v.filter(|v| v.is_finite()) {
+                bag.remove(v);
+            }
+        }
+    }
+
+    /// Count `time` in as the newest solve.
+    pub fn push(&mut self, time: &Time) {
+        let counts = self.counts;
+        self.counts.count += 1;
+        let mut before = Before {
+            counts,
+            solved: None,
+            averages: [None; 6],
+            date: None,
+        };
+        if time.variation == self.variation {
+            before.averages = averages(time);
+            self.insert_averages(&before.averages);
+            self.counts.latest = [time.ao50, time.ao100, time.ao1000];
+            before.solved = self.count(time.penalty, time.time, time.date);
+            before.date = time.date;
+        }
+        self.before = Some(before);
+    }
+
+    /// Take out `time`, wherever it was in the session. The averages of the solves after it
+    /// are changed apart, with `reaverage`.
+    pub fn remove(&mut self, time: &Time) {
+        self.before = None;
+        self.counts.count -= 1;
+        if time.variation != self.variation {
+            return;
+        }
+        self.remove_averages(&averages(time));
+        let counts = &mut self.counts;
+        counts.solves -= 1;
+        counts.practice -= time.time;
+        let solved = match time.penalty {
+            Penalty::None => Some(time.time),
+            Penalty::PlusTwo => {
+                counts.plus_twos -= 1;
+                Some(time.time + 2.0)
+            }
+            Penalty::Dnf => {
+                counts.dnfs -= 1;
+                None
+            }
+        };
+        if let Some(v) = solved {
+            // Welford's step run backwards
+            let n = counts.solved as f64;
+            counts.solved -= 1;
+            match counts.solved {
+                0 => (counts.mean, counts.squares) = (0.0, 0.0),
+                _ => {
+                    let mean = (counts.mean * n - v as f64) / (n - 1.0);
+                    counts.squares -= (v as f64 - counts.mean) * (v as f64 - mean);
+                    counts.mean = mean;
+                }
+            }
+            self.median.remove(v);
+        }
+        if let Some(date) = time.date {
+            self.undate(date, time.time);
+        }
+    }
+
+    /// Swap the averages `old` had for those `new`, the same solve, has once one before it
+    /// was removed.
+    pub fn reaverage(&mut self, old: &Time, new: &Time) {
+        if old.variation == self.variation {
+            self.remove_averages(&averages(old));
+            self.insert_averages(&averages(new));
+        }
+    }
+
+    /// Take the latest averages from `newest`, the newest solve of the variation, if any.
+    pub fn set_newest(&mut self, newest: Option<&Time>) {
+        self.counts.latest = newest.map_or([None; 3], |v| [v.ao50, v.ao100, v.ao1000]);
+    }
+
+    /// Count in everything but the averages for a solve of the tally's variation, returning
+    /// the value it put in the median.
+    fn count(&mut self, penalty: Penalty, time: f32, date: Option<i64>) -> Option<f32> {
+        let counts = &mut self.counts;
+        counts.solves += 1;
+        counts.practice += time;
+        let solved = match penalty {
+            Penalty::None => Some(time),
+            Penalty::PlusTwo => {
+                counts.plus_twos += 1;
+                Some(time + 2.0)
+            }
+            Penalty::Dnf => {
+                counts.dnfs += 1;
+                None
+            }
+        };
+        if let Some(v) = solved {
+            counts.solved += 1;
+            let delta = v as f64 - counts.mean;
+            counts.mean += delta / counts.solved as f64;
+            counts.squares += delta * (v as f64 - counts.mean);
+            self.median.insert(v);
+        }
+        if let Some(date) = date {
+            self.date(date, time);
+        }
+        solved
+    }
+
+    /// The nearest dates before and after `date` with solves, the later with the time of
+    /// its first, and whether each is close enough to be in the same sitting.
+    fn neighbours(&self, date: i64) -> (Option<i64>, Option<(i64, f32)>, bool, bool) {
+        let before = self.dates.range(..date).next_back().map(|v| *v.0);
+        let after = self.dates.range(date + 1..).next().map(|v| (*v.0, v.1[0]));
+        let joins_before = before.is_some_and(|v| date - v <= SITTING_GAP);
+        let joins_after = after.is_some_and(|v| v.0 - date <= SITTING_GAP);
+        (before, after, joins_before, joins_after)
+    }
+
+    /// Put a solve taking `time` that finished at `date` among the sittings. Each sitting
+    /// runs from when its first solve started to when its last finished, so only those it
+    /// joins or splits change.
+    fn date(&mut self, date: i64, time: f32) {
+        self.counts.dated += 1;
+        if let Some(times) = self.dates.get_mut(&date) {
+            times.push(time);
+            return;
+        }
+        let (before, after, joins_before, joins_after) = self.neighbours(date);
+        let counts = &mut self.counts;
+        match (before, after) {
+            (Some(before), Some((after, first))) if joins_before && joins_after => {
+                // Bridging two sittings makes them one
+                if after - before > SITTING_GAP {
+                    counts.sittings -= 1;
+                    counts.sat += (after - before) as f32 - first;
+                }
+            }
+            (Some(before), _) if joins_before => counts.sat += (date - before) as f32,
+            (_, Some((after, first))) if joins_after => {
+                counts.sat += (after - date) as f32 + time - first;
+            }
+            _ => {
+                counts.sittings += 1;
+                counts.sat += time;
+            }
+        }
+        self.dates.insert(date, vec![time]);
+    }
+
+    /// Take a solve back out of the sittings, as `date` put it in.
+    fn undate(&mut self, date: i64, time: f32) {
+        let Some(times) = self.dates.get_mut(&date) else {
+            return;
+        };
+        self.counts.dated -= 1;
+        if times.len() > 1 {
+            let at = times.iter().position(|v| *v == time).unwrap_or(times.len() - 1);
+            times.remove(at);
+            let first = times[0];
+            // The first solve of a sitting starts it
+            if at == 0 && !self.neighbours(date).2 {
+                self.counts.sat += first - time;
+            }
+            return;
+        }
+        self.dates.remove(&date);
+        let (before, after, joins_before, joins_after) = self.neighbours(date);
+        let counts = &mut self.counts;
+        match (before, after) {
+            (Some(before), Some((after, first))) if joins_before && joins_after => {
+                if after - before > SITTING_GAP {
+                    counts.sittings += 1;
+                    counts.sat -= (after - before) as f32 - first;
+                }
+            }
+            (Some(before), _) if joins_before => counts.sat -= (date - before) as f32,
+            (_, Some((after, first))) if joins_after => {
+                counts.sat -= (after - date) as f32 + time - first;
+            }
+            _ => {
+                counts.sittings -= 1;
+                counts.sat -= time;
+            }
+        }
+    }
+
+    /// Take back the newest solve, returning whether it could be: only the one pushed last
+    /// is kept to take back.
+    pub fn pop(&mut self) -> bool {
+        let Some(before) = self.before.take() else {
+            return false;
+        };
+        self.counts = before.counts;
+        if let Some(v) = before.solved {
+            self.median.remove(v);
+        }
+        self.remove_averages(&before.averages);
+        if let Some(date) = before.date {
+            // The counts are back as they were, so only the date has to go
+            if let Some(times) = self.dates.get_mut(&date) {
+                times.pop();
+                if times.is_empty() {
+                    self.dates.remove(&date);
+                }
+            }
+        }
+        true
+    }
+
+    pub fn summary(&self) -> Summary {
+        let counts = &self.counts;
+        let [pbmo3, pbao5, pbao12, pbao50, pbao100, pbao1000] =
+            self.averages.each_ref().map(Bag::first);
+        let [ao50, ao100, ao1000] = counts.latest;
+        let solved = counts.solved;
+        Summary {
+            count: counts.count,
+            pbsingle: self.median.low.first(),
+            pbmo3,
+            pbao5,
+            pbao12,
+            pbao50,
+            pbao100,
+            pbao1000,
+            ao50,
+            ao100,
+            ao1000,
+            mean: (solved > 0).then_some(OrderedFloat(counts.mean as f32)),
+            spread: Spread {
+                solves: counts.solves,
+                dnfs: counts.dnfs,
+                plus_twos: counts.plus_twos,
+                worst: self.median.high.last().or(self.median.low.last()),
+                median: self.median.value(),
+                deviation: (solved > 1)
+                    .then(|| OrderedFloat((counts.squares / (solved - 1) as f64).sqrt() as f32)),
+                practice: counts.practice,
+                sittings: counts.sittings,
+                sat: counts.sat,
+                sat_solves: counts.dated,
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::{mean, trimmed_mean};
+    use rand::{Rng, SeedableRng};
+    use rand_chacha::ChaCha8Rng;
+
+    /// Solves of two variations with DNFs, +2s and a break or two between sittings, their
+    /// averages filled in.
+    fn random_times(rng: &mut impl Rng, n: usize) -> Vec<Time> {
+        let mut rolling = Rolling::default();
+        let mut date = 1_700_000_000;
+        (0..n)
+            .map(|_| {
+                let mut time = Time::from(rng.gen_range(8.0..20.0));
+                time.penalty = match rng.gen_range(0..20) {
+                    0 => Penalty::Dnf,
+                    1 => Penalty::PlusTwo,
+                    _ => Penalty::None,
+                };
+                if rng.gen_bool(0.1) {
+                    time.variation = Variation::OneHanded;
+                }
+                date += match rng.gen_bool(0.02) {
+                    true => 3 * SITTING_GAP,
+                    false => 30,
+                };
+                time.date = rng.gen_bool(0.9).then_some(date);
+                rolling.push(&mut time);
+                time
+            })
+            .collect()
+    }
+
+    fn close(a: Option<OrderedFloat<f32>>, b: Option<OrderedFloat<f32>>) -> bool {
+        match (a, b) {
+            (Some(a), Some(b)) => a == b || (a.0 - b.0).abs() < 1e-3,
+            (a, b) => a == b,
+        }
+    }
+
+    fn assert_same(a: &Summary, b: &Summary) {
+        assert_eq!(a.count, b.count);
+        let bests = |v: &Summary| {
+            [
+                v.pbsingle, v.pbmo3, v.pbao5, v.pbao12, v.pbao50, v.pbao100, v.pbao1000,
+            ]
+        };
+        assert_eq!(bests(a), bests(b));
+        // Worked out by the windows rather than sorting, so they may differ in the last bit
+        assert!(close(a.ao50, b.ao50) && close(a.ao100, b.ao100));
+        assert!(close(a.ao1000, b.ao1000));
+        assert!(close(a.mean, b.mean));
+        let (a, b) = (a.spread, b.spread);
+        assert_eq!(
+            (a.solves, a.dnfs, a.plus_twos, a.sittings, a.sat_solves),
+            (b.solves, b.dnfs, b.plus_twos, b.sittings, b.sat_solves)
+        );
+        assert_eq!((a.worst, a.median), (b.worst, b.median));
+        assert!(close(a.deviation, b.deviation));
+        assert!((a.practice - b.practice).abs() < 0.1);
+        assert!((a.sat - b.sat).abs() < 0.1);
+    }
+
+    #[test]
+    fn tally_matches_summary_solve_by_solve() {
+        let mut rng = ChaCha8Rng::seed_from_u64(1);
+        let times = random_times(&mut rng, 1200);
+        let mut tally = Tally::of(&times[..100], Variation::Normal);
+        for i in 100..times.len() {
+            tally.push(&times[i]);
+            let summary = Summary::from_times(&times[..=i], Variation::Normal);
+            assert_same(&tally.summary(), &summary);
+        }
+    }
+
+    #[test]
+    fn tally_opened_from_a_summary_carries_on() {
+        let mut rng = ChaCha8Rng::seed_from_u64(5);
+        let times = random_times(&mut rng, 400);
+        let summary = Summary::from_times(&times[..300], Variation::Normal);
+        let solves: Vec<Outcome> = times[..300]
+            .iter()
+            .filter(|v| v.variation == Variation::Normal)
+            .map(|v| (v.penalty, v.time, v.date))
+            .collect();
+        let averages: Vec<Averages> = times[..300]
+            .iter()
+            .filter(|v| v.variation == Variation::Normal)
+            .map(averages)
+            .collect();
+        let mut tally = Tally::open(&summary, &solves, &averages, Variation::Normal);
+        assert_same(&tally.summary(), &summary);
+        for time in &times[300..] {
+            tally.push(time);
+        }
+        let summary = Summary::from_times(&times, Variation::Normal);
+        assert_same(&tally.summary(), &summary);
+    }
+
+    #[test]
+    fn tally_takes_back_the_newest_solve() {
+        let mut rng = ChaCha8Rng::seed_from_u64(2);
+        let mut times = random_times(&mut rng, 300);
+        let last = times.len() - 1;
+        times[last].variation = Variation::Normal;
+        let mut tally = Tally::of(&times[..last], Variation::Normal);
+        assert!(!tally.pop());
+        tally.push(&times[last]);
+        times[last].penalty = Penalty::Dnf;
+        assert!(tally.pop());
+        tally.push(&times[last]);
+        let summary = Summary::from_times(&times, Variation::Normal);
+        assert_same(&tally.summary(), &summary);
+    }
+
+    #[test]
+    fn tally_takes_a_solve_out_of_date_order() {
+        let mut rng = ChaCha8Rng::seed_from_u64(3);
+        let mut times = random_times(&mut rng, 50);
+        let mut tally = Tally::of(&times, Variation::Normal);
+        let mut early = Time::from(10.0);
+        early.date = times[20].date.map(|v| v - 1);
+        tally.push(&early);
+        times.push(early);
+        let summary = Summary::from_times(&times, Variation::Normal);
+        assert_same(&tally.summary(), &summary);
+    }
+
+    #[test]
+    fn tally_takes_out_any_solve() {
+        let mut rng = ChaCha8Rng::seed_from_u64(6);
+        let mut times = random_times(&mut rng, 1200);
+        let mut tally = Tally::of(&times, Variation::Normal);
+        for _ in 0..40 {
+            let at = rng.gen_range(0..times.len());
+            let removed = times.remove(at);
+            tally.remove(&removed);
+            // The averages after it come out different, as deleting it from a session would
+            let mut rolling = Rolling::of(&times[..at]);
+            for time in &mut times[at..] {
+                let old = time.clone();
+                rolling.push(time);
+                tally.reaverage(&old, time);
+            }
+            let newest = times.iter().rev().find(|v| v.variation == Variation::Normal);
+            tally.set_newest(newest);
+            let summary = Summary::from_times(&times, Variation::Normal);
+            assert_same(&tally.summary(), &summary);
+        }
+    }
+
+    #[test]
+    fn rolling_matches_trimmed_mean() {
+        let mut rng = ChaCha8Rng::seed_from_u64(4);
+        let times = random_times(&mut rng, 1200);
+        let mut rolling = Rolling::default();
+        for (i, time) in times.iter().enumerate() {
+            // A solve pushed and taken back again leaves no trace
+            if rng.gen_bool(0.1) {
+                let mut decoy = Time::from(rng.gen_range(8.0..20.0));
+                decoy.variation = time.variation;
+                rolling.push(&mut decoy);
+                rolling.pop(time.variation);
+            }
+            let mut pushed = time.clone();
+            rolling.push(&mut pushed);
+
+            let values: Vec<f32> = times[..=i]
+                .iter()
+                .filter(|v| v.variation == time.variation)
+                .map(Time::value)
+                .collect();
+            let expected = WINDOWS.map(|n| {
+                let last = values.get(values.len().checked_sub(n)?..)?;
+                Some(match n {
+                    3 => mean(last),
+                    _ => trimmed_mean(last),
+                })
+            });
+            let got = [
+                pushed.mo3,
+                pushed.ao5,
+                pushed.ao12,
+                pushed.ao50,
+                pushed.ao100,
+                pushed.ao1000,
+            ];
+            for (n, (a, b)) in WINDOWS.iter().zip(expected.into_iter().zip(got)) {
+                assert!(close(a, b), "ao{} of solve {}: {:?} vs {:?}", n, i, a, b);
+            }
+        }
+    }
+}
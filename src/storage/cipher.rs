@@ -0,0 +1,241 @@
+use std::error::Error;
+#[cfg(feature = "encrypt")]
+use std::{
+    collections::HashMap,
+    env, fs,
+    path::Path,
+    sync::{Arc, Mutex},
+};
+
+/// Marks a file as encrypted; plain files are JSON and can't start with it. The salt the
+/// key was derived with comes next, so a copy sealed on another machine still opens.
+const MAGIC: &[u8] = b"cube-tui-enc2\n";
+/// Marks a file sealed before the salt was kept in it, with the data directory's key.
+const OLD_MAGIC: &[u8] = b"cube-tui-enc1\n";
+#[cfg(feature = "encrypt")]
+const SALT: usize = 16;
+
+/// A key derived from the user's passphrase for sealing saved solves.
+///
+/// The data directory's salt and a check value live in `cipher.json`. Each sealed file
+/// carries the salt it was sealed with too, so the same passphrase opens files synced
+/// from a data directory with another salt.
+#[cfg(feature = "encrypt")]
+#[derive(Clone)]
+pub struct Cipher {
+    passphrase: Arc<String>,
+    salt: Vec<u8>,
+    key: chacha20poly1305::Key,
+    /// Keys for other salts, derived when first needed
+    others: Arc<Mutex<HashMap<Vec<u8>, chacha20poly1305::Key>>>,
+}
+
+/// Stand-in when built without the `encrypt` feature; it can never be made.
+#[cfg(not(feature = "encrypt"))]
+#[derive(Clone)]
+pub enum Cipher {}
+
+#[cfg(feature = "encrypt")]
+#[derive(serde::Serialize, serde::Deserialize)]
+struct KeyFile {
+    salt: String,
+    check: String,
+}
+
+#[cfg(feature = "encrypt")]
+impl Cipher {
+    /// Ask for the passphrase (or read `CUBE_TUI_PASSPHRASE`) and derive the key.
+    ///
+    /// The first unlock of a data directory sets the passphrase for it, so one typed in
+    /// then is asked for twice.
+    pub fn unlock(data_dir: &Path) -> Result<Self, Box<dyn Error>> {
+        let passphrase = match env::var("CUBE_TUI_PASSPHRASE") {
+            Ok(v) => v,
+            Err(_) => {
+                let passphrase = rpassword::prompt_password("Passphrase: ")?;
+                let first = !data_dir.join("cipher.json").exists();
+                if first && rpassword::prompt_password("Again: ")? != passphrase {
+                    return Err("the passphrases don't match".into());
+                }
+                passphrase
+            }
+        };
+        Self::with_passphrase(data_dir, &passphrase)
+    }
+
+    /// Derive the key for `data_dir` from `passphrase`, setting it if it's the first.
+    pub fn with_passphrase(data_dir: &Path, passphrase: &str) -> Result<Self, Box<dyn Error>> {
+        use base64::{engine::general_purpose::STANDARD, Engine};
+        use rand::RngCore;
+
+        if passphrase.is_empty() {
+            return Err("the passphrase can't be empty".into());
+        }
+
+        let path = data_dir.join("cipher.json");
+        if path.exists() {
+            let file: KeyFile = serde_json::from_str(&fs::read_to_string(&path)?)?;
+            let cipher = Self::derive(passphrase, STANDARD.decode(file.salt)?)?;
+            cipher
+                .open(&STANDARD.decode(file.check)?)
+                .map_err(|_| "wrong passphrase")?;
+            return Ok(cipher);
+        }
+
+        let mut salt = vec![0; SALT];
+        rand::rngs::OsRng.fill_bytes(&mut salt);
+        let cipher = Self::derive(passphrase, salt)?;
+        let file = KeyFile {
+            salt: STANDARD.encode(&cipher.salt),
+            check: STANDARD.encode(cipher.seal(b"cube-tui")?),
+        };
+        fs::create_dir_all(data_dir)?;
+        fs::write(&path, serde_json::to_string_pretty(&file)?)?;
+        Ok(cipher)
+    }
+
+    fn derive(passphrase: &str, salt: Vec<u8>) -> Result<Self, Box<dyn Error>> {
+        Ok(Self {
+            passphrase: Arc::new(passphrase.to_string()),
+            key: key(passphrase, &salt)?,
+            salt,
+            others: Arc::default(),
+        })
+    }
+
+    /// Encrypt `plain` as `MAGIC`, the salt, a random nonce, then the ciphertext.
+    pub fn seal(&self, plain: &[u8]) -> Result<Vec<u8>, Box<dyn Error>> {
+        use chacha20poly1305::{
+            aead::{Aead, AeadCore, KeyInit, OsRng},
+            XChaCha20Poly1305,
+        };
+        let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let sealed = XChaCha20Poly1305::new(&self.key)
+            .encrypt(&nonce, plain)
+            .map_err(|_| "encryption failed")?;
+        Ok([MAGIC, &self.salt, nonce.as_slice(), &sealed].concat())
+    }
+
+    /// Decrypt something made by `seal`, failing if it was altered.
+    pub fn open(&self, data: &[u8]) -> Result<Vec<u8>, Box<dyn Error>> {
+        use chacha20poly1305::{aead::Aead, KeyInit, XChaCha20Poly1305, XNonce};
+        let (key, body) = match data.strip_prefix(MAGIC) {
+            Some(body) if body.len() >= SALT => {
+                let (salt, body) = body.split_at(SALT);
+                (self.key_for(salt)?, body)
+            }
+            Some(_) => return Err("encrypted data is truncated".into()),
+            None => (self.key, data.strip_prefix(OLD_MAGIC).ok_or("not encrypted")?),
+        };
+        if body.len() < 24 {
+            return Err("encrypted data is truncated".into());
+        }
+        let (nonce, sealed) = body.split_at(24);
+        Ok(XChaCha20Poly1305::new(&key)
+            .decrypt(XNonce::from_slice(nonce), sealed)
+            .map_err(|_| "encrypted data is damaged")?)
+    }
+
+    /// The key for data sealed with `salt`.
+    fn key_for(&self, salt: &[u8]) -> Result<chacha20poly1305::Key, Box<dyn Error>> {
+        if salt == self.salt {
+            return Ok(self.key);
+        }
+        let mut others = self.others.lock().map_err(|_| "key cache poisoned")?;
+        if let Some(key) = others.get(salt) {
+            return Ok(*key);
+        }
+        let key = key(&self.passphrase, salt)?;
+        others.insert(salt.to_vec(), key);
+        Ok(key)
+    }
+}
+
+#[cfg(feature = "encrypt")]
+fn key(passphrase: &str, salt: &[u8]) -> Result<chacha20poly1305::Key, Box<dyn Error>> {
+    let mut key = chacha20poly1305::Key::default();
+    argon2::Argon2::default()
+        .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+        .map_err(|err| format!("deriving key: {}", err))?;
+    Ok(key)
+}
+
+#[cfg(not(feature = "encrypt"))]
+impl Cipher {
+    pub fn unlock(_: &std::path::Path) -> Result<Self, Box<dyn Error>> {
+        Err("encryption needs a build with the `encrypt` feature".into())
+    }
+
+    pub fn seal(&self, _: &[u8]) -> Result<Vec<u8>, Box<dyn Error>> {
+        match *self {}
+    }
+
+    pub fn open(&self, _: &[u8]) -> Result<Vec<u8>, Box<dyn Error>> {
+        match *self {}
+    }
+}
+
+/// Turn the contents of a file into text, decrypting it if it was saved encrypted.
+///
+/// Plain files are still read with a cipher, so turning encryption on needs no migration.
+pub fn read(data: &[u8], cipher: Option<&Cipher>) -> Result<String, Box<dyn Error>> {
+    if !data.starts_with(MAGIC) && !data.starts_with(OLD_MAGIC) {
+        return Ok(String::from_utf8_lossy(data).into_owned());
+    }
+    match cipher {
+        Some(cipher) => Ok(String::from_utf8(cipher.open(data)?)?),
+        None => Err("data is encrypted; set `encrypt = true` in the config".into()),
+    }
+}
+
+/// The bytes to write for `text`, sealed when there's a cipher.
+pub fn write(text: String, cipher: Option<&Cipher>) -> Result<Vec<u8>, Box<dyn Error>> {
+    match cipher {
+        Some(cipher) => cipher.seal(text.as_bytes()),
+        None => Ok(text.into_bytes()),
+    }
+}
+
+/// A journal line's payload: the JSON itself, or the sealed JSON in hex.
+pub fn seal_line(json: String, cipher: Option<&Cipher>) -> Result<String, Box<dyn Error>> {
+    match cipher {
+        Some(cipher) => Ok(cipher
+            .seal(json.as_bytes())?
+            .iter()
+            .map(|v| format!("{:02x}", v))
+            .collect()),
+        None => Ok(json),
+    }
+}
+
+/// The JSON in a journal line written by `seal_line`.
+pub fn open_line(payload: &str, cipher: Option<&Cipher>) -> Result<String, Box<dyn Error>> {
+    if payload.starts_with('{') {
+        return Ok(payload.to_string());
+    }
+    let bytes = (0..payload.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(payload.get(i..i + 2)?, 16).ok())
+        .collect::<Option<Vec<u8>>>()
+        .ok_or("journal line is neither JSON nor hex")?;
+    read(&bytes, cipher)
+}
+
+#[cfg(all(test, feature = "encrypt"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_passphrase_opens_data_sealed_under_another_salt() {
+        let dir = env::temp_dir().join(format!("cube-tui-cipher-{}", std::process::id()));
+        let (home, away) = (dir.join("home"), dir.join("away"));
+        let sealed = Cipher::with_passphrase(&away, "hunter2").unwrap().seal(b"9.81").unwrap();
+
+        let cipher = Cipher::with_passphrase(&home, "hunter2").unwrap();
+        assert_eq!(cipher.open(&sealed).unwrap(), b"9.81");
+        assert!(Cipher::with_passphrase(&home, "hunter3").is_err());
+        let other = Cipher::with_passphrase(&dir.join("third"), "hunter3").unwrap();
+        assert!(other.open(&sealed).is_err());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,249 @@
+use super::{averages_of, cipher, solves_of, Averages, Cipher, Outcome, Storage, Summary};
+use crate::{
+    app::{Time, Variation},
+    attempt,
+};
+use std::{
+    error::Error,
+    fs::{self, OpenOptions},
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+/// Solves appended before the journal is folded into the main store.
+const COMPACT_EVERY: usize = 20;
+
+/// Wraps a store so each new solve hits the disk as one appended line.
+///
+/// Rewriting a whole JSON file per solve is slow and can be cut short by a crash, so
+/// solves go to `<name>.journal` first and are compacted into the inner store every
+/// few solves, on flush and on the next startup. Each line ends in a tab and the CRC32
+/// of the solve so a damaged entry is dropped rather than read back wrong. A crash
+/// between saving the inner store and removing the journal leaves solves in both, so
+/// compacting skips those the store already ends with.
+pub struct Journaled<S: Storage> {
+    inner: S,
+    path: PathBuf,
+    pending: Vec<Time>,
+    dropped: usize,
+    cipher: Option<Cipher>,
+}
+
+impl<S: Storage> Journaled<S> {
+    pub fn open(inner: S, path: &Path, cipher: Option<Cipher>) -> Result<Self, Box<dyn Error>> {
+        let (pending, dropped) = replay(&path.with_extension("journal"), cipher.as_ref())?;
+        let mut journal = Self {
+            inner,
+            path: path.with_extension("journal"),
+            pending,
+            dropped,
+            cipher,
+        };
+        journal.compact()?;
+        Ok(journal)
+    }
+
+    fn compact(&mut self) -> Result<(), Box<dyn Error>> {
+        if !self.pending.is_empty() {
+            let mut times = self.inner.load()?;
+            let tail = &times[times.len().saturating_sub(self.pending.len())..];
+            self.pending
+                .retain(|v| v.date.is_none() || !tail.iter().any(|t| attempt::same(t, v)));
+            times.append(&mut self.pending);
+            self.inner.save(&times)?;
+        }
+        if self.path.exists() {
+            fs::remove_file(&self.path)?;
+        }
+        Ok(())
+    }
+}
+
+impl<S: Storage> Storage for Journaled<S> {
+    fn load(&mut self) -> Result<Vec<Time>, Box<dyn Error>> {
+        let mut times = self.inner.load()?;
+        times.extend(self.pending.iter().cloned());
+        Ok(times)
+    }
+
+    fn page(&mut self, skip: usize, limit: usize) -> Result<Vec<Time>, Box<dyn Error>> {
+        // The journal holds the newest solves, so take from it before the inner store
+        let n = self.pending.len();
+        let end = n.saturating_sub(skip);
+        let start = end.saturating_sub(limit);
+        let mut times = self.pending[start..end].to_vec();
+        let rest = limit - times.len();
+        if rest > 0 {
+            let mut older = self.inner.page(skip.saturating_sub(n), rest)?;
+            older.append(&mut times);
+            times = older;
+        }
+        Ok(times)
+    }
+
+    fn push(&mut self, time: &Time) -> Result<(), Box<dyn Error>> {
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        let line = cipher::seal_line(serde_json::to_string(time)?, self.cipher.as_ref())?;
+        writeln!(file, "{}\t{:08x}", line, crc32fast::hash(line.as_bytes()))?;
+        file.sync_data()?;
+
+        self.pending.push(time.clone());
+        if self.pending.len() >= COMPACT_EVERY {
+            self.compact()?;
+        }
+        Ok(())
+    }
+
+    fn save(&mut self, times: &[Time]) -> Result<(), Box<dyn Error>> {
+        self.pending.clear();
+        self.inner.save(times)?;
+        self.compact()
+    }
+
+    fn update(&mut self, index: usize, time: &Time) -> Result<(), Box<dyn Error>> {
+        self.compact()?;
+        self.inner.update(index, time)
+    }
+
+    fn update_from(&mut self, index: usize, times: &[Time]) -> Result<(), Box<dyn Error>> {
+        self.compact()?;
+        self.inner.update_from(index, times)
+    }
+
+    fn remove(&mut self, index: usize) -> Result<(), Box<dyn Error>> {
+        self.compact()?;
+        self.inner.remove(index)
+    }
+
+    fn flush(&mut self) -> Result<(), Box<dyn Error>> {
+        self.compact()?;
+        self.inner.flush()
+    }
+
+    fn solves(&mut self, variation: Variation) -> Result<Vec<Outcome>, Box<dyn Error>> {
+        let mut solves = self.inner.solves(variation)?;
+        solves.extend(solves_of(&self.pending, variation));
+        Ok(solves)
+    }
+
+    fn averages(&mut self, variation: Variation) -> Result<Vec<Averages>, Box<dyn Error>> {
+        let mut averages = self.inner.averages(variation)?;
+        averages.extend(averages_of(&self.pending, variation));
+        Ok(averages)
+    }
+
+    fn summary(&mut self, variation: Variation) -> Result<Summary, Box<dyn Error>> {
+        // Solves still in the journal change the latest averages and the median, so they
+        // can't be counted in apart; it's usually empty here, as opening and every change
+        // to older solves compact it
+        if !self.pending.is_empty() {
+            return Ok(Summary::from_times(&self.load()?, variation));
+        }
+        self.inner.summary(variation)
+    }
+
+    fn take_notice(&mut self) -> Option<String> {
+        let dropped = std::mem::take(&mut self.dropped);
+        let own = (dropped > 0).then(|| format!("dropped {} damaged journal entries", dropped));
+        match (self.inner.take_notice(), own) {
+            (Some(a), Some(b)) => Some(format!("{}; {}", a, b)),
+            (a, b) => a.or(b),
+        }
+    }
+}
+
+/// Read the solves left in a journal along with how many lines were damaged.
+///
+/// A torn final line is what a crash mid-write leaves behind, so it isn't counted.
+fn replay(path: &Path, cipher: Option<&Cipher>) -> Result<(Vec<Time>, usize), Box<dyn Error>> {
+    if !path.exists() {
+        return Ok((vec![], 0));
+    }
+    let data = String::from_utf8_lossy(&fs::read(path)?).into_owned();
+    let lines: Vec<&str> = data.lines().filter(|v| !v.trim().is_empty()).collect();
+    let mut times = vec![];
+    let mut dropped = 0;
+    for (i, line) in lines.iter().enumerate() {
+        match parse_line(line, cipher) {
+            Some(v) => times.push(v),
+            None if i + 1 == lines.len() && !data.ends_with('\n') => (),
+            None => dropped += 1,
+        }
+    }
+    Ok((times, dropped))
+}
+
+/// Check and parse one journal line. Lines from before checksums are taken as they are.
+fn parse_line(line: &str, cipher: Option<&Cipher>) -> Option<Time> {
+    let payload = match line.rsplit_once('\t') {
+        Some((payload, sum)) => (u32::from_str_radix(sum, 16).ok()?
+            == crc32fast::hash(payload.as_bytes()))
+        .then_some(payload)?,
+        None => line,
+    };
+    serde_json::from_str(&cipher::open_line(payload, cipher).ok()?).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::JsonStorage;
+    use super::*;
+
+    fn line(time: f32) -> String {
+        let json = serde_json::to_string(&Time::from(time)).unwrap();
+        format!("{}\t{:08x}", json, crc32fast::hash(json.as_bytes()))
+    }
+
+    #[test]
+    fn replay_skips_a_torn_last_line_and_counts_a_bad_checksum() {
+        let dir = std::env::temp_dir().join(format!("cube-tui-journal-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("default.journal");
+
+        let damaged = line(11.0).replace("11.0", "17.0");
+        let torn = &line(13.0)[..10];
+        let data = format!("{}\n{}\n{}\n{}", line(12.5), damaged, line(10.0), torn);
+        fs::write(&path, data).unwrap();
+        let (times, dropped) = replay(&path, None).unwrap();
+        let kept: Vec<f32> = times.iter().map(|v| v.time).collect();
+        assert_eq!(kept, [12.5, 10.0]);
+        assert_eq!(dropped, 1);
+
+        // A line that parses badly but ended with a newline was written whole, so counts
+        fs::write(&path, format!("{}\n{}\n", line(12.5), torn)).unwrap();
+        assert_eq!(replay(&path, None).unwrap().1, 1);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn reopening_after_a_crash_mid_compaction_keeps_one_copy() {
+        let dir = std::env::temp_dir().join(format!("cube-tui-compact-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("default.json");
+
+        let open = || Journaled::open(JsonStorage::open(&path, None).unwrap(), &path, None);
+        let mut journal = open().unwrap();
+        for (i, time) in [12.5, 10.0].into_iter().enumerate() {
+            let mut solve = Time::from(time);
+            solve.date = Some(1_700_000_000 + i as i64);
+            journal.push(&solve).unwrap();
+        }
+        // Save the store as compacting does, then stop before the journal is removed
+        let times = journal.load().unwrap();
+        journal.inner.save(&times).unwrap();
+        drop(journal);
+
+        let kept: Vec<f32> = open().unwrap().load().unwrap().iter().map(|v| v.time).collect();
+        assert_eq!(kept, [12.5, 10.0]);
+        assert!(!path.with_extension("journal").exists());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
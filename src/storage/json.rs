@@ -0,0 +1,273 @@
+use super::{
+    averages_of, cipher, quarantine, recovered, schema, solves_of, Averages, Cipher, Outcome,
+    Storage, Summary,
+};
+use crate::app::{Time, Variation};
+use serde_json::json;
+use std::{
+    error::Error,
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// Keeps the whole session in a single pretty-printed JSON array, encrypted when there's
+/// a cipher.
+pub struct JsonStorage {
+    path: PathBuf,
+    times: Vec<Time>,
+    cipher: Option<Cipher>,
+    notice: Option<String>,
+}
+
+impl JsonStorage {
+    pub fn open(path: &Path, cipher: Option<Cipher>) -> Result<Self, Box<dyn Error>> {
+        let mut storage = Self {
+            path: path.to_path_buf(),
+            times: vec![],
+            cipher,
+            notice: None,
+        };
+        if !path.exists() {
+            // Pick up the plain-text list written by older versions
+            storage.times = read_legacy(&path.with_extension(""))?;
+            return Ok(storage);
+        }
+
+        let data = match cipher::read(&fs::read(path)?, storage.cipher.as_ref()) {
+            Ok(v) => v,
+            // The passphrase was checked on unlock, so a file it can't open is damaged
+            Err(err) if storage.cipher.is_some() => {
+                storage.recover("", &err.to_string())?;
+                return Ok(storage);
+            }
+            Err(err) => return Err(err),
+        };
+        if data.trim().is_empty() {
+            return Ok(storage);
+        }
+        match decode(&data) {
+            Ok(v) => storage.times = v,
+            // A file from a newer build isn't damaged, so leave it alone
+            Err(err) if serde_json::from_str(&data).is_ok_and(|v| schema::TIMES.is_newer(&v)) => {
+                return Err(err)
+            }
+            Err(err) => storage.recover(&data, &err.to_string())?,
+        }
+        Ok(storage)
+    }
+
+    /// Keep what can be salvaged from a damaged file and move the original aside.
+    fn recover(&mut self, data: &str, err: &str) -> Result<(), Box<dyn Error>> {
+        self.times = salvage(data);
+        let moved = quarantine(&self.path)?;
+        self.write()?;
+        self.notice = Some(recovered(&self.path, err, self.times.len(), &moved));
+        Ok(())
+    }
+
+    fn write(&self) -> Result<(), Box<dyn Error>> {
+        write(&self.path, &self.times, self.cipher.as_ref())
+    }
+}
+
+impl Storage for JsonStorage {
+    fn load(&mut self) -> Result<Vec<Time>, Box<dyn Error>> {
+        Ok(self.times.clone())
+    }
+
+    fn page(&mut self, skip: usize, limit: usize) -> Result<Vec<Time>, Box<dyn Error>> {
+        let end = self.times.len().saturating_sub(skip);
+        Ok(self.times[end.saturating_sub(limit)..end].to_vec())
+    }
+
+    fn push(&mut self, time: &Time) -> Result<(), Box<dyn Error>> {
+        self.times.push(time.clone());
+        self.write()
+    }
+
+    fn save(&mut self, times: &[Time]) -> Result<(), Box<dyn Error>> {
+        self.times = times.to_vec();
+        self.write()
+    }
+
+    fn update(&mut self, index: usize, time: &Time) -> Result<(), Box<dyn Error>> {
+        if let Some(v) = self.times.get_mut(index) {
+            *v = time.clone();
+        }
+        self.write()
+    }
+
+    fn update_from(&mut self, index: usize, times: &[Time]) -> Result<(), Box<dyn Error>> {
+        for (v, time) in self.times.iter_mut().skip(index).zip(times) {
+            *v = time.clone();
+        }
+        self.write()
+    }
+
+    fn remove(&mut self, index: usize) -> Result<(), Box<dyn Error>> {
+        if index < self.times.len() {
+            self.times.remove(index);
+        }
+        self.write()
+    }
+
+    fn flush(&mut self) -> Result<(), Box<dyn Error>> {
+        self.write()
+    }
+
+    fn solves(&mut self, variation: Variation) -> Result<Vec<Outcome>, Box<dyn Error>> {
+        Ok(solves_of(&self.times, variation))
+    }
+
+    fn averages(&mut self, variation: Variation) -> Result<Vec<Averages>, Box<dyn Error>> {
+        Ok(averages_of(&self.times, variation))
+    }
+
+    fn summary(&mut self, variation: Variation) -> Result<Summary, Box<dyn Error>> {
+        Ok(Summary::from_times(&self.times, variation))
+    }
+
+    fn take_notice(&mut self) -> Option<String> {
+        self.notice.take()
+    }
+}
+
+/// Write the full solve history to `path`, creating parent directories as needed.
+pub fn write(path: &Path, times: &[Time], cipher: Option<&Cipher>) -> Result<(), Box<dyn Error>> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    // Write to a sibling file first so a crash mid-write can't truncate the history
+    let tmp = path.with_extension("tmp");
+    fs::write(&tmp, cipher::write(encode(times)?, cipher)?)?;
+    fs::rename(&tmp, path)?;
+    Ok(())
+}
+
+/// Parse a solve document of any known version, verifying its checksum if it has one.
+pub fn decode(data: &str) -> Result<Vec<Time>, Box<dyn Error>> {
+    let doc = schema::TIMES.upgrade(serde_json::from_str(data)?)?;
+    let times: Vec<Time> = serde_json::from_value(doc["times"].clone())?;
+    if let Some(sum) = doc.get("checksum").and_then(|v| v.as_str()) {
+        if sum != checksum(&times)? {
+            return Err("checksum mismatch".into());
+        }
+    }
+    Ok(times)
+}
+
+/// Serialize solves as a current-version document.
+pub fn encode(times: &[Time]) -> Result<String, Box<dyn Error>> {
+    let doc = schema::TIMES.stamp(json!({ "checksum": checksum(times)?, "times": times }))?;
+    Ok(serde_json::to_string_pretty(&doc)?)
+}
+
+/// CRC32 of the solves in their compact form, so formatting changes don't matter.
+fn checksum(times: &[Time]) -> Result<String, Box<dyn Error>> {
+    let data = serde_json::to_string(times)?;
+    Ok(format!("{:08x}", crc32fast::hash(data.as_bytes())))
+}
+
+/// Pull out whichever solves still parse from a damaged document.
+///
+/// Each object sitting directly inside an array outside a string is a candidate, which is
+/// a solve whether the document is a bare list or keeps them under `times`, while objects
+/// nested in a solve, such as a multi-blind result, are parsed along with it.
+fn salvage(data: &str) -> Vec<Time> {
+    let mut times = vec![];
+    // The brackets open around the current place, each object's with where it starts if
+    // it's a candidate
+    let mut open: Vec<(char, Option<usize>)> = vec![];
+    let (mut in_string, mut escaped) = (false, false);
+    for (i, c) in data.char_indices() {
+        if in_string {
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '"' => in_string = false,
+                _ => (),
+            }
+            continue;
+        }
+        match c {
+            '"' => in_string = true,
+            '[' => open.push(('[', None)),
+            '{' => {
+                let in_array = open.last().is_some_and(|(v, _)| *v == '[');
+                open.push(('{', in_array.then_some(i)));
+            }
+            ']' => _ = open.pop(),
+            '}' => {
+                let start = open.pop().and_then(|(_, start)| start);
+                if let Some(time) = start.and_then(|s| serde_json::from_str(&data[s..=i]).ok()) {
+                    times.push(time);
+                }
+            }
+            _ => (),
+        }
+    }
+    times
+}
+
+/// Read the old newline-separated list of seconds used before solves were stored as JSON.
+fn read_legacy(path: &Path) -> Result<Vec<Time>, Box<dyn Error>> {
+    if !path.exists() {
+        return Ok(vec![]);
+    }
+    Ok(fs::read_to_string(path)?
+        .lines()
+        .filter_map(|v| v.parse::<f32>().ok())
+        .map(Time::from)
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::Multiblind;
+
+    #[test]
+    fn salvage_keeps_multiblind_solves_of_a_truncated_file() {
+        let mut blind = Time::from(2400.0);
+        blind.multiblind = Some(Multiblind {
+            solved: 8,
+            attempted: 10,
+        });
+        let times = vec![Time::from(12.5), blind, Time::from(11.0), Time::from(13.0)];
+        let data = encode(&times).unwrap();
+        // Cut off partway into the last solve
+        let cut = data.rfind("13.0").unwrap();
+        let salvaged = salvage(&data[..cut]);
+        let kept: Vec<f32> = salvaged.iter().map(|v| v.time).collect();
+        assert_eq!(kept, [12.5, 2400.0, 11.0]);
+        let result = salvaged[1].multiblind.unwrap();
+        assert_eq!((result.solved, result.attempted), (8, 10));
+    }
+
+    #[test]
+    fn salvage_reads_a_bare_list() {
+        let data = r#"[{"time": 10.0, "ao5": null, "ao12": null}, {"time": 9.5, "ao5"#;
+        let salvaged = salvage(data);
+        assert_eq!(salvaged.len(), 1);
+        assert_eq!(salvaged[0].time, 10.0);
+    }
+
+    #[test]
+    fn decode_reads_a_version_1_list() {
+        let data = r#"[{"time": 10.0, "ao5": null, "ao12": null}]"#;
+        let times = decode(data).unwrap();
+        assert_eq!(times.len(), 1);
+        assert_eq!(times[0].time, 10.0);
+    }
+
+    #[test]
+    fn decode_checks_the_checksum() {
+        let times = vec![Time::from(12.5), Time::from(11.0)];
+        let data = encode(&times).unwrap();
+        let kept: Vec<f32> = decode(&data).unwrap().iter().map(|v| v.time).collect();
+        assert_eq!(kept, [12.5, 11.0]);
+        let edited = data.replace("11.0", "10.0");
+        let err = decode(&edited).err().map(|v| v.to_string());
+        assert_eq!(err.as_deref(), Some("checksum mismatch"));
+    }
+}
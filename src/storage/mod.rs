@@ -0,0 +1,310 @@
+mod cipher;
+mod journal;
+mod json;
+pub mod schema;
+#[cfg(feature = "sqlite")]
+mod sqlite;
+
+use super::app::{deviation, trimmed_mean, Penalty, Time, Variation};
+use chrono::Local;
+use ordered_float::OrderedFloat;
+use std::{
+    error::Error,
+    fs,
+    path::{Path, PathBuf},
+};
+
+pub use cipher::Cipher;
+pub use cipher::{read as unseal, write as seal};
+pub use journal::Journaled;
+#[cfg(feature = "sync")]
+pub use json::decode;
+pub use json::{encode, JsonStorage};
+#[cfg(feature = "sqlite")]
+pub use sqlite::SqliteStorage;
+
+/// Session-wide numbers shown in the Bests row, for the solves of one variation.
+#[derive(Default, Clone, Copy)]
+pub struct Summary {
+    /// Solves of every variation
+    pub count: usize,
+    pub pbsingle: Option<OrderedFloat<f32>>,
+    pub pbmo3: Option<OrderedFloat<f32>>,
+    pub pbao5: Option<OrderedFloat<f32>>,
+    pub pbao12: Option<OrderedFloat<f32>>,
+    pub pbao50: Option<OrderedFloat<f32>>,
+    pub pbao100: Option<OrderedFloat<f32>>,
+    pub pbao1000: Option<OrderedFloat<f32>>,
+    pub ao50: Option<OrderedFloat<f32>>,
+    pub ao100: Option<OrderedFloat<f32>>,
+    pub ao1000: Option<OrderedFloat<f32>>,
+    pub mean: Option<OrderedFloat<f32>>,
+    pub spread: Spread,
+}
+
+/// Solves further apart than this, in seconds, were done at different sittings.
+pub const SITTING_GAP: i64 = 30 * 60;
+
+/// How a variation's solves spread out, for the Stats view. DNFs are counted but left out
+/// of the rest.
+#[derive(Default, Clone, Copy)]
+pub struct Spread {
+    pub solves: usize,
+    pub dnfs: usize,
+    pub plus_twos: usize,
+    pub worst: Option<OrderedFloat<f32>>,
+    pub median: Option<OrderedFloat<f32>>,
+    /// Sample standard deviation, which takes two solves
+    pub deviation: Option<OrderedFloat<f32>>,
+    /// Seconds spent solving, DNFs included
+    pub practice: f32,
+    /// Runs of dated solves with no long break between them
+    pub sittings: usize,
+    /// Seconds from the start of each sitting's first solve to the end of its last
+    pub sat: f32,
+    /// Solves in the sittings
+    pub sat_solves: usize,
+}
+
+impl Spread {
+    /// From each solve's penalty, the time it took and when it finished.
+    pub fn of(solves: impl Iterator<Item = Outcome>) -> Self {
+        let mut spread = Self::default();
+        let mut solved = vec![];
+        let mut dated = vec![];
+        for (penalty, time, date) in solves {
+            spread.solves += 1;
+            spread.practice += time;
+            match penalty {
+                Penalty::None => solved.push(OrderedFloat(time)),
+                Penalty::PlusTwo => {
+                    spread.plus_twos += 1;
+                    solved.push(OrderedFloat(time + 2.0));
+                }
+                Penalty::Dnf => spread.dnfs += 1,
+            }
+            if let Some(date) = date {
+                dated.push((date, time));
+            }
+        }
+        dated.sort_by_key(|v| v.0);
+        spread.sat_solves = dated.len();
+        // Each sitting runs from when its first solve started to when its last finished
+        let mut first: Option<(i64, f32)> = None;
+        let mut last = 0;
+        for &(date, time) in &dated {
+            if first.is_some() && date - last <= SITTING_GAP {
+                last = date;
+                continue;
+            }
+            if let Some((date, time)) = first {
+                spread.sat += (last - date) as f32 + time;
+            }
+            spread.sittings += 1;
+            first = Some((date, time));
+            last = date;
+        }
+        if let Some((date, time)) = first {
+            spread.sat += (last - date) as f32 + time;
+        }
+        solved.sort();
+        let n = solved.len();
+        spread.worst = solved.last().copied();
+        spread.median = match n {
+            0 => None,
+            _ if n % 2 == 1 => Some(solved[n / 2]),
+            _ => Some((solved[n / 2 - 1] + solved[n / 2]) / 2.0),
+        };
+        let values: Vec<f32> = solved.iter().map(|v| v.0).collect();
+        spread.deviation = deviation(&values);
+        spread
+    }
+
+    /// Solves an hour over the sittings, once they've taken a minute; a lone quick solve
+    /// would make it meaninglessly high.
+    pub fn pace(&self) -> Option<f32> {
+        (self.sat >= 60.0).then(|| self.sat_solves as f32 * 3600.0 / self.sat)
+    }
+}
+
+impl Summary {
+    pub fn from_times(times: &[Time], variation: Variation) -> Self {
+        let count = times.len();
+        let times: Vec<&Time> = times.iter().filter(|v| v.variation == variation).collect();
+        // DNFs never count as bests and are left out of the mean
+        let best = |f: fn(&Time) -> Option<OrderedFloat<f32>>| {
+            let found = times.iter().copied().filter_map(f);
+            found.filter(|v| v.is_finite()).min()
+        };
+        let values: Vec<f32> = times.iter().map(|v| v.value()).collect();
+        let latest = |n: usize| {
+            let start = values.len().checked_sub(n)?;
+            Some(trimmed_mean(&values[start..]))
+        };
+        let solved: Vec<f32> = values.iter().copied().filter(|v| v.is_finite()).collect();
+        let sum: f32 = solved.iter().sum();
+
+        Self {
+            count,
+            pbsingle: best(|v| Some(OrderedFloat(v.value()))),
+            pbmo3: best(|v| v.mo3),
+            pbao5: best(|v| v.ao5),
+            pbao12: best(|v| v.ao12),
+            pbao50: best(|v| v.ao50),
+            pbao100: best(|v| v.ao100),
+            pbao1000: best(|v| v.ao1000),
+            ao50: latest(50),
+            ao100: latest(100),
+            ao1000: latest(1000),
+            mean: (!solved.is_empty()).then(|| OrderedFloat(sum / solved.len() as f32)),
+            spread: Spread::of(times.iter().map(|v| (v.penalty, v.time, v.date))),
+        }
+    }
+}
+
+/// A solve's penalty, the time it took and when it finished: all of it a tally or a
+/// spread counts besides the averages.
+pub type Outcome = (Penalty, f32, Option<i64>);
+
+/// The mo3 and each average from ao5 up kept with a solve.
+pub type Averages = [Option<OrderedFloat<f32>>; 6];
+
+pub fn averages(time: &Time) -> Averages {
+    [
+        time.mo3, time.ao5, time.ao12, time.ao50, time.ao100, time.ao1000,
+    ]
+}
+
+/// What `Storage::averages` gives for `times`.
+fn averages_of(times: &[Time], variation: Variation) -> Vec<Averages> {
+    times
+        .iter()
+        .filter(|v| v.variation == variation)
+        .map(averages)
+        .collect()
+}
+
+/// What `Storage::solves` gives for `times`.
+fn solves_of(times: &[Time], variation: Variation) -> Vec<Outcome> {
+    times
+        .iter()
+        .filter(|v| v.variation == variation)
+        .map(|v| (v.penalty, v.time, v.date))
+        .collect()
+}
+
+/// A place solves are kept between runs.
+///
+/// Indices are chronological positions within the session, oldest first.
+pub trait Storage {
+    /// Every solve in the session, oldest first.
+    fn load(&mut self) -> Result<Vec<Time>, Box<dyn Error>>;
+
+    /// Up to `limit` solves ending `skip` solves back from the newest, oldest first.
+    ///
+    /// Lets a session show its latest solves without reading the whole history.
+    fn page(&mut self, skip: usize, limit: usize) -> Result<Vec<Time>, Box<dyn Error>> {
+        Ok(slice_page(self.load()?, skip, limit))
+    }
+
+    /// Record a newly completed solve.
+    fn push(&mut self, time: &Time) -> Result<(), Box<dyn Error>>;
+
+    /// Replace the whole session with `times`.
+    fn save(&mut self, times: &[Time]) -> Result<(), Box<dyn Error>>;
+
+    /// Replace the solve at `index` with `time`.
+    fn update(&mut self, index: usize, time: &Time) -> Result<(), Box<dyn Error>>;
+
+    /// Replace the solves from `index` on with `times`, in one go, as when their averages
+    /// were worked out again.
+    fn update_from(&mut self, index: usize, times: &[Time]) -> Result<(), Box<dyn Error>> {
+        for (i, time) in times.iter().enumerate() {
+            self.update(index + i, time)?;
+        }
+        Ok(())
+    }
+
+    /// Remove the solve at `index`.
+    fn remove(&mut self, index: usize) -> Result<(), Box<dyn Error>>;
+
+    /// Flush anything still buffered.
+    fn flush(&mut self) -> Result<(), Box<dyn Error>> {
+        Ok(())
+    }
+
+    /// The penalty, time and finish of each of the session's solves of `variation`, oldest
+    /// first; what a tally counts besides the bests, without reading whole solves.
+    fn solves(&mut self, variation: Variation) -> Result<Vec<Outcome>, Box<dyn Error>> {
+        Ok(solves_of(&self.load()?, variation))
+    }
+
+    /// The averages kept with each of the session's solves of `variation`, oldest first;
+    /// what a tally keeps besides.
+    fn averages(&mut self, variation: Variation) -> Result<Vec<Averages>, Box<dyn Error>> {
+        Ok(averages_of(&self.load()?, variation))
+    }
+
+    /// Aggregate stats for the session's solves of `variation`.
+    fn summary(&mut self, variation: Variation) -> Result<Summary, Box<dyn Error>> {
+        Ok(Summary::from_times(&self.load()?, variation))
+    }
+
+    /// Something worth telling the user about opening the store, like having recovered
+    /// from damage. Only returned once.
+    fn take_notice(&mut self) -> Option<String> {
+        None
+    }
+}
+
+/// Move a damaged file aside as `<name>.corrupt-<timestamp>` so nothing more is lost.
+fn quarantine(path: &Path) -> Result<PathBuf, Box<dyn Error>> {
+    let name = path.file_name().and_then(|v| v.to_str()).unwrap_or("times");
+    let dest = path.with_file_name(format!(
+        "{}.corrupt-{}",
+        name,
+        Local::now().format("%Y%m%d-%H%M%S")
+    ));
+    fs::rename(path, &dest)?;
+    Ok(dest)
+}
+
+/// Status line text for a file that had to be salvaged.
+fn recovered(path: &Path, err: &str, count: usize, moved: &Path) -> String {
+    let name = |p: &Path| {
+        p.file_name()
+            .and_then(|v| v.to_str())
+            .unwrap_or("")
+            .to_string()
+    };
+    format!(
+        "{} was damaged ({}): recovered {} solves, original kept as {}",
+        name(path),
+        err,
+        count,
+        name(moved)
+    )
+}
+
+/// Cut the window `Storage::page` describes out of a full history.
+fn slice_page(mut times: Vec<Time>, skip: usize, limit: usize) -> Vec<Time> {
+    let end = times.len().saturating_sub(skip);
+    times.truncate(end);
+    times.split_off(end.saturating_sub(limit))
+}
+
+/// Open the backend matching the file extension of `path`.
+///
+/// `.db` files use SQLite when built with the `sqlite` feature, anything else is JSON
+/// behind a crash-safe journal, sealed with `cipher` if there is one.
+pub fn open(path: &Path, cipher: Option<&Cipher>) -> Result<Box<dyn Storage>, Box<dyn Error>> {
+    #[cfg(feature = "sqlite")]
+    if path.extension().is_some_and(|v| v == "db") {
+        if cipher.is_some() {
+            return Err("SQLite sessions can't be encrypted".into());
+        }
+        return Ok(Box::new(SqliteStorage::open(path)?));
+    }
+    let inner = JsonStorage::open(path, cipher.cloned())?;
+    Ok(Box::new(Journaled::open(inner, path, cipher.cloned())?))
+}
@@ -0,0 +1,111 @@
+use serde_json::{json, Value};
+use std::error::Error;
+
+/// Turns version `n` of a document into version `n + 1`.
+pub type Migration = fn(Value) -> Result<Value, Box<dyn Error>>;
+
+/// The shape of a persisted JSON document and how to bring old copies up to date.
+///
+/// Documents carry a top-level `version`; one without it is version 1. `migrations[i]`
+/// upgrades version `i + 1`, so adding a step bumps the current version.
+pub struct Schema {
+    pub name: &'static str,
+    pub migrations: &'static [Migration],
+}
+
+impl Schema {
+    pub fn current(&self) -> u64 {
+        self.migrations.len() as u64 + 1
+    }
+
+    fn version(doc: &Value) -> u64 {
+        doc.get("version").and_then(|v| v.as_u64()).unwrap_or(1)
+    }
+
+    /// Whether `doc` was written by a newer build, rather than being unreadable.
+    pub fn is_newer(&self, doc: &Value) -> bool {
+        Self::version(doc) > self.current()
+    }
+
+    /// Run every migration newer than the document's version.
+    pub fn upgrade(&self, mut doc: Value) -> Result<Value, Box<dyn Error>> {
+        let mut version = Self::version(&doc);
+        if version > self.current() {
+            return Err(format!(
+                "{} data is version {}, newer than this build understands ({})",
+                self.name,
+                version,
+                self.current()
+            )
+            .into());
+        }
+        while version < self.current() {
+            doc = self.migrations[version as usize - 1](doc)?;
+            version += 1;
+        }
+        self.stamp(doc)
+    }
+
+    /// Mark a freshly serialized document with the current version.
+    pub fn stamp(&self, mut doc: Value) -> Result<Value, Box<dyn Error>> {
+        doc.as_object_mut()
+            .ok_or_else(|| format!("{} data is not an object", self.name))?
+            .insert("version".to_string(), json!(self.current()));
+        Ok(doc)
+    }
+}
+
+/// A session's solves.
+pub const TIMES: Schema = Schema {
+    name: "times",
+    migrations: &[times_v1],
+};
+
+/// A session's `session.json`.
+pub const SESSION: Schema = Schema {
+    name: "session",
+    migrations: &[],
+};
+
+/// A session's `attempts.json`.
+pub const ATTEMPTS: Schema = Schema {
+    name: "attempts",
+    migrations: &[],
+};
+
+/// A session's `deleted.json`, the solves deleted from it for syncing to leave out.
+#[cfg(feature = "sync")]
+pub const DELETED: Schema = Schema {
+    name: "deleted",
+    migrations: &[],
+};
+
+/// Version 1 was a bare array of solves.
+fn times_v1(doc: Value) -> Result<Value, Box<dyn Error>> {
+    match doc {
+        Value::Array(_) => Ok(json!({ "times": doc })),
+        _ => Err("times data is neither a list nor versioned".into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn upgrade_wraps_a_version_1_list() {
+        let doc = TIMES.upgrade(json!([{ "time": 10.0 }])).unwrap();
+        assert_eq!(doc, json!({ "version": 2, "times": [{ "time": 10.0 }] }));
+        // Already current, so only the version is touched
+        assert_eq!(TIMES.upgrade(doc.clone()).unwrap(), doc);
+    }
+
+    #[test]
+    fn upgrade_refuses_a_newer_version() {
+        let doc = json!({ "version": 3, "times": [] });
+        assert!(TIMES.is_newer(&doc));
+        assert!(TIMES.upgrade(doc).is_err());
+        // No build wrote an object without a version
+        assert!(TIMES.upgrade(json!({ "times": [] })).is_err());
+    }
+}
@@ -0,0 +1,650 @@
+use super::{quarantine, recovered, Averages, JsonStorage, Outcome, Spread, Storage, Summary};
+use crate::app::{trimmed_mean, Multiblind, Penalty, Time, Variation};
+use ordered_float::OrderedFloat;
+use rusqlite::{params, Connection, OptionalExtension};
+use std::{error::Error, path::Path};
+
+/// A solve's time with any +2 added, or NULL for a DNF, which aggregates skip.
+const COUNTED: &str = "CASE p.kind WHEN 'DNF' THEN NULL WHEN '+2' THEN s.time + 2 ELSE s.time END";
+/// How SQLite writes the infinite averages DNFs make.
+const DNF_AVERAGE: &str = "9e999";
+
+/// Each step upgrades the database from the `user_version` matching its position.
+const MIGRATIONS: [fn(&Connection) -> rusqlite::Result<()>; 11] = [
+    create_tables,
+    add_dates,
+    add_solutions,
+    add_phases,
+    add_multiblind,
+    add_moves,
+    add_penalty_sources,
+    add_memo,
+    add_variations,
+    add_large_averages,
+    add_mo3,
+];
+
+fn create_tables(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS sessions (
+            id INTEGER PRIMARY KEY,
+            name TEXT NOT NULL UNIQUE
+        );
+        CREATE TABLE IF NOT EXISTS solves (
+            id INTEGER PRIMARY KEY,
+            session_id INTEGER NOT NULL REFERENCES sessions(id) ON DELETE CASCADE,
+            time REAL NOT NULL,
+            ao5 REAL,
+            ao12 REAL,
+            scramble TEXT NOT NULL DEFAULT ''
+        );
+        CREATE TABLE IF NOT EXISTS penalties (
+            solve_id INTEGER PRIMARY KEY REFERENCES solves(id) ON DELETE CASCADE,
+            kind TEXT NOT NULL CHECK (kind IN ('+2', 'DNF'))
+        );
+        CREATE INDEX IF NOT EXISTS solves_session ON solves(session_id, id);
+        INSERT OR IGNORE INTO sessions (id, name) VALUES (1, 'default');",
+    )
+}
+
+fn add_dates(conn: &Connection) -> rusqlite::Result<()> {
+    // Unversioned databases from before migrations may already have the column
+    let has_date: bool = conn.query_row(
+        "SELECT COUNT(*) > 0 FROM pragma_table_info('solves') WHERE name = 'date'",
+        [],
+        |row| row.get(0),
+    )?;
+    if !has_date {
+        conn.execute_batch("ALTER TABLE solves ADD COLUMN date INTEGER")?;
+    }
+    Ok(())
+}
+
+fn add_solutions(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute_batch("ALTER TABLE solves ADD COLUMN solution TEXT NOT NULL DEFAULT ''")
+}
+
+fn add_phases(conn: &Connection) -> rusqlite::Result<()> {
+    // Space separated, like the turns of a solution
+    conn.execute_batch("ALTER TABLE solves ADD COLUMN phases TEXT NOT NULL DEFAULT ''")
+}
+
+fn add_multiblind(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute_batch(
+        "ALTER TABLE solves ADD COLUMN solved INTEGER;
+        ALTER TABLE solves ADD COLUMN attempted INTEGER;",
+    )
+}
+
+fn add_moves(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute_batch("ALTER TABLE solves ADD COLUMN moves INTEGER")
+}
+
+fn add_penalty_sources(conn: &Connection) -> rusqlite::Result<()> {
+    // Set when inspection gave the penalty, rather than it being given by hand
+    conn.execute_batch("ALTER TABLE penalties ADD COLUMN inspection INTEGER NOT NULL DEFAULT 0")
+}
+
+fn add_memo(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute_batch("ALTER TABLE solves ADD COLUMN memo REAL")
+}
+
+fn add_variations(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute_batch("ALTER TABLE solves ADD COLUMN variation TEXT NOT NULL DEFAULT 'normal'")
+}
+
+fn add_large_averages(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute_batch(
+        "ALTER TABLE solves ADD COLUMN ao50 REAL;
+        ALTER TABLE solves ADD COLUMN ao100 REAL;
+        ALTER TABLE solves ADD COLUMN ao1000 REAL;",
+    )
+}
+
+fn add_mo3(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute_batch("ALTER TABLE solves ADD COLUMN mo3 REAL")
+}
+
+fn migrate(conn: &mut Connection) -> Result<(), Box<dyn Error>> {
+    let version: usize =
+        conn.query_row("PRAGMA user_version", [], |row| row.get::<_, i64>(0))? as usize;
+    if version > MIGRATIONS.len() {
+        return Err(format!(
+            "database is version {}, newer than this build understands ({})",
+            version,
+            MIGRATIONS.len()
+        )
+        .into());
+    }
+    for (i, step) in MIGRATIONS.iter().enumerate().skip(version) {
+        let tx = conn.transaction()?;
+        step(&tx)?;
+        tx.pragma_update(None, "user_version", (i + 1) as i64)?;
+        tx.commit()?;
+    }
+    Ok(())
+}
+
+/// Ask SQLite to verify its page structure, which catches a truncated or overwritten file.
+fn check(conn: &Connection) -> Result<(), String> {
+    match conn.query_row("PRAGMA quick_check", [], |row| row.get::<_, String>(0)) {
+        Ok(v) if v == "ok" => Ok(()),
+        Ok(v) => Err(v),
+        Err(err) => Err(err.to_string()),
+    }
+}
+
+/// Read whatever solve rows are still reachable in a damaged database.
+fn salvage(conn: &Connection) -> Vec<Time> {
+    let mut stmt = match conn.prepare(
+        "SELECT s.time, s.ao5, s.ao12, s.scramble, p.kind, s.date, s.solution, s.phases,
+         s.solved, s.attempted, s.moves, p.inspection, s.memo, s.variation, s.ao50, s.ao100,
+         s.ao1000, s.mo3
+         FROM solves s LEFT JOIN penalties p ON p.solve_id = s.id ORDER BY s.id",
+    ) {
+        Ok(v) => v,
+        Err(_) => return vec![],
+    };
+    let times = match stmt.query_map([], read_row) {
+        Ok(rows) => rows.map_while(Result::ok).collect(),
+        Err(_) => vec![],
+    };
+    times
+}
+
+/// Stores solves relationally and answers the Bests row with SQL.
+pub struct SqliteStorage {
+    conn: Connection,
+    session: i64,
+    notice: Option<String>,
+}
+
+impl SqliteStorage {
+    pub fn open(path: &Path) -> Result<Self, Box<dyn Error>> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let mut conn = Connection::open(path)?;
+        let mut salvaged = None;
+        if let Err(err) = check(&conn) {
+            let times = salvage(&conn);
+            drop(conn);
+            let moved = quarantine(path)?;
+            salvaged = Some((recovered(path, &err, times.len(), &moved), times));
+            conn = Connection::open(path)?;
+        }
+        conn.execute_batch("PRAGMA foreign_keys = ON")?;
+        migrate(&mut conn)?;
+        let mut storage = Self {
+            conn,
+            session: 1,
+            notice: None,
+        };
+        if let Some((notice, times)) = salvaged {
+            storage.save(&times)?;
+            storage.notice = Some(notice);
+            return Ok(storage);
+        }
+
+        // Bring over an existing JSON history the first time the database is used
+        let json_path = path.with_extension("json");
+        if storage.count()? == 0 && json_path.exists() {
+            let tx = storage.conn.transaction()?;
+            for time in JsonStorage::open(&json_path, None)?.load()? {
+                insert(&tx, storage.session, &time)?;
+            }
+            tx.commit()?;
+        }
+        Ok(storage)
+    }
+
+    fn count(&self) -> Result<usize, Box<dyn Error>> {
+        let n: i64 = self.conn.query_row(
+            "SELECT COUNT(*) FROM solves WHERE session_id = ?1",
+            params![self.session],
+            |row| row.get(0),
+        )?;
+        Ok(n as usize)
+    }
+
+    /// Trimmed mean of the latest `n` solves of `variation`.
+    fn latest_aon(
+        &self,
+        n: usize,
+        variation: Variation,
+    ) -> Result<Option<OrderedFloat<f32>>, Box<dyn Error>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {} FROM solves s LEFT JOIN penalties p ON p.solve_id = s.id
+             WHERE s.session_id = ?1 AND s.variation = ?3 ORDER BY s.id DESC LIMIT ?2",
+            COUNTED
+        ))?;
+        // A DNF comes back as NULL and is infinitely slow
+        let rows = stmt.query_map(params![self.session, n as i64, variation.as_str()], |row| {
+            let value: Option<f64> = row.get(0)?;
+            Ok(value.map_or(f32::INFINITY, |v| v as f32))
+        })?;
+        let values = rows.collect::<Result<Vec<f32>, _>>()?;
+        if values.len() < n || n < 3 {
+            return Ok(None);
+        }
+        Ok(Some(trimmed_mean(&values)))
+    }
+
+    fn spread(&mut self, variation: Variation) -> Result<Spread, Box<dyn Error>> {
+        Ok(Spread::of(self.solves(variation)?.into_iter()))
+    }
+}
+
+impl Storage for SqliteStorage {
+    fn load(&mut self) -> Result<Vec<Time>, Box<dyn Error>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT s.time, s.ao5, s.ao12, s.scramble, p.kind, s.date, s.solution, s.phases,
+             s.solved, s.attempted, s.moves, p.inspection, s.memo, s.variation, s.ao50, s.ao100,
+             s.ao1000, s.mo3
+             FROM solves s LEFT JOIN penalties p ON p.solve_id = s.id
+             WHERE s.session_id = ?1 ORDER BY s.id",
+        )?;
+        let rows = stmt.query_map(params![self.session], read_row)?;
+        Ok(rows.collect::<Result<Vec<Time>, _>>()?)
+    }
+
+    fn page(&mut self, skip: usize, limit: usize) -> Result<Vec<Time>, Box<dyn Error>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT s.time, s.ao5, s.ao12, s.scramble, p.kind, s.date, s.solution, s.phases,
+             s.solved, s.attempted, s.moves, p.inspection, s.memo, s.variation, s.ao50, s.ao100,
+             s.ao1000, s.mo3
+             FROM solves s LEFT JOIN penalties p ON p.solve_id = s.id
+             WHERE s.session_id = ?1 ORDER BY s.id DESC LIMIT ?2 OFFSET ?3",
+        )?;
+        let rows = stmt.query_map(params![self.session, limit as i64, skip as i64], read_row)?;
+        let mut times = rows.collect::<Result<Vec<Time>, _>>()?;
+        times.reverse();
+        Ok(times)
+    }
+
+    fn push(&mut self, time: &Time) -> Result<(), Box<dyn Error>> {
+        insert(&self.conn, self.session, time)
+    }
+
+    fn save(&mut self, times: &[Time]) -> Result<(), Box<dyn Error>> {
+        let tx = self.conn.transaction()?;
+        tx.execute(
+            "DELETE FROM solves WHERE session_id = ?1",
+            params![self.session],
+        )?;
+        for time in times {
+            insert(&tx, self.session, time)?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    fn update(&mut self, index: usize, time: &Time) -> Result<(), Box<dyn Error>> {
+        let tx = self.conn.transaction()?;
+        update(&tx, self.session, index, time)?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    fn update_from(&mut self, index: usize, times: &[Time]) -> Result<(), Box<dyn Error>> {
+        let tx = self.conn.transaction()?;
+        for (i, time) in times.iter().enumerate() {
+            update(&tx, self.session, index + i, time)?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    fn remove(&mut self, index: usize) -> Result<(), Box<dyn Error>> {
+        if let Some(id) = nth_id(&self.conn, self.session, index)? {
+            self.conn
+                .execute("DELETE FROM solves WHERE id = ?1", params![id])?;
+        }
+        Ok(())
+    }
+
+    fn solves(&mut self, variation: Variation) -> Result<Vec<Outcome>, Box<dyn Error>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT p.kind, s.time, s.date FROM solves s LEFT JOIN penalties p ON p.solve_id = s.id
+             WHERE s.session_id = ?1 AND s.variation = ?2 ORDER BY s.id",
+        )?;
+        let rows = stmt.query_map(params![self.session, variation.as_str()], |row| {
+            let time = row.get::<_, f64>(1)? as f32;
+            Ok((penalty(row.get(0)?), time, row.get(2)?))
+        })?;
+        Ok(rows.collect::<Result<Vec<_>, _>>()?)
+    }
+
+    fn averages(&mut self, variation: Variation) -> Result<Vec<Averages>, Box<dyn Error>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT mo3, ao5, ao12, ao50, ao100, ao1000 FROM solves
+             WHERE session_id = ?1 AND variation = ?2 ORDER BY id",
+        )?;
+        let rows = stmt.query_map(params![self.session, variation.as_str()], |row| {
+            let mut averages = [None; 6];
+            for (i, v) in averages.iter_mut().enumerate() {
+                *v = average(row.get(i)?);
+            }
+            Ok(averages)
+        })?;
+        Ok(rows.collect::<Result<Vec<_>, _>>()?)
+    }
+
+    fn summary(&mut self, variation: Variation) -> Result<Summary, Box<dyn Error>> {
+        type Row = (Option<f64>, Option<f64>, Option<f64>, Option<f64>);
+        let ((mean, pbsingle, pbmo3, pbao5), (pbao12, pbao50, pbao100, pbao1000)): (Row, Row) =
+            self.conn.query_row(
+                &format!(
+                    "SELECT AVG({0}), MIN({0}), MIN(NULLIF(s.mo3, {1})), MIN(NULLIF(s.ao5, {1})),
+                    MIN(NULLIF(s.ao12, {1})), MIN(NULLIF(s.ao50, {1})),
+                    MIN(NULLIF(s.ao100, {1})), MIN(NULLIF(s.ao1000, {1}))
+                    FROM solves s LEFT JOIN penalties p ON p.solve_id = s.id
+                    WHERE s.session_id = ?1 AND s.variation = ?2",
+                    COUNTED, DNF_AVERAGE
+                ),
+                params![self.session, variation.as_str()],
+                |row| {
+                    Ok((
+                        (row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?),
+                        (row.get(4)?, row.get(5)?, row.get(6)?, row.get(7)?),
+                    ))
+                },
+            )?;
+        Ok(Summary {
+            count: self.count()?,
+            pbsingle: average(pbsingle),
+            pbmo3: average(pbmo3),
+            pbao5: average(pbao5),
+            pbao12: average(pbao12),
+            pbao50: average(pbao50),
+            pbao100: average(pbao100),
+            pbao1000: average(pbao1000),
+            ao50: self.latest_aon(50, variation)?,
+            ao100: self.latest_aon(100, variation)?,
+            ao1000: self.latest_aon(1000, variation)?,
+            mean: average(mean),
+            spread: self.spread(variation)?,
+        })
+    }
+
+    fn take_notice(&mut self) -> Option<String> {
+        self.notice.take()
+    }
+}
+
+/// A penalty as the penalties table has its kind, with no row for none.
+fn penalty(kind: Option<String>) -> Penalty {
+    match kind.as_deref() {
+        Some("+2") => Penalty::PlusTwo,
+        Some("DNF") => Penalty::Dnf,
+        _ => Penalty::None,
+    }
+}
+
+fn read_row(row: &rusqlite::Row) -> rusqlite::Result<Time> {
+    let mut time = Time::from(row.get::<_, f64>(0)? as f32);
+    time.ao5 = row
+        .get::<_, Option<f64>>(1)?
+        .map(|v| OrderedFloat(v as f32));
+    time.ao12 = row
+        .get::<_, Option<f64>>(2)?
+        .map(|v| OrderedFloat(v as f32));
+    time.scramble = row.get(3)?;
+    time.penalty = penalty(row.get(4)?);
+    time.date = row.get(5)?;
+    time.solution = row.get(6)?;
+    time.phases = row
+        .get::<_, String>(7)?
+        .split_whitespace()
+        .filter_map(|v| v.parse().ok())
+        .collect();
+    time.multiblind = match (row.get(8)?, row.get(9)?) {
+        (Some(solved), Some(attempted)) => Some(Multiblind { solved, attempted }),
+        _ => None,
+    };
+    time.moves = row.get(10)?;
+    time.inspection_penalty = row.get::<_, Option<bool>>(11)?.unwrap_or(false);
+    time.memo = row.get::<_, Option<f64>>(12)?.map(|v| v as f32);
+    time.variation = Variation::parse(&row.get::<_, String>(13)?).unwrap_or_default();
+    time.ao50 = average(row.get(14)?);
+    time.ao100 = average(row.get(15)?);
+    time.ao1000 = average(row.get(16)?);
+    time.mo3 = average(row.get(17)?);
+    Ok(time)
+}
+
+fn average(v: Option<f64>) -> Option<OrderedFloat<f32>> {
+    v.map(|v| OrderedFloat(v as f32))
+}
+
+/// Replace the solve at `index` in `session` with `time`, penalty and all.
+fn update(
+    conn: &Connection,
+    session: i64,
+    index: usize,
+    time: &Time,
+) -> Result<(), Box<dyn Error>> {
+    if let Some(id) = nth_id(conn, session, index)? {
+        conn.execute(
+            "UPDATE solves
+             SET time = ?2, ao5 = ?3, ao12 = ?4, scramble = ?5, date = ?6, solution = ?7,
+                 phases = ?8, solved = ?9, attempted = ?10, moves = ?11, memo = ?12,
+                 variation = ?13, ao50 = ?14, ao100 = ?15, ao1000 = ?16, mo3 = ?17
+             WHERE id = ?1",
+            params![
+                id,
+                time.time as f64,
+                time.ao5.map(|v| v.0 as f64),
+                time.ao12.map(|v| v.0 as f64),
+                time.scramble,
+                time.date,
+                time.solution,
+                phases(time),
+                time.multiblind.map(|v| v.solved),
+                time.multiblind.map(|v| v.attempted),
+                time.moves,
+                time.memo.map(|v| v as f64),
+                time.variation.as_str(),
+                time.ao50.map(|v| v.0 as f64),
+                time.ao100.map(|v| v.0 as f64),
+                time.ao1000.map(|v| v.0 as f64),
+                time.mo3.map(|v| v.0 as f64),
+            ],
+        )?;
+        conn.execute("DELETE FROM penalties WHERE solve_id = ?1", params![id])?;
+        insert_penalty(conn, id, time)?;
+    }
+    Ok(())
+}
+
+/// The row id of the solve at `index` in the session, oldest first.
+fn nth_id(conn: &Connection, session: i64, index: usize) -> rusqlite::Result<Option<i64>> {
+    conn.query_row(
+        "SELECT id FROM solves WHERE session_id = ?1 ORDER BY id LIMIT 1 OFFSET ?2",
+        params![session, index as i64],
+        |row| row.get(0),
+    )
+    .optional()
+}
+
+fn insert(conn: &Connection, session: i64, time: &Time) -> Result<(), Box<dyn Error>> {
+    conn.execute(
+        "INSERT INTO solves
+            (session_id, time, ao5, ao12, scramble, date, solution, phases, solved, attempted,
+             moves, memo, variation, ao50, ao100, ao1000, mo3)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17)",
+        params![
+            session,
+            time.time as f64,
+            time.ao5.map(|v| v.0 as f64),
+            time.ao12.map(|v| v.0 as f64),
+            time.scramble,
+            time.date,
+            time.solution,
+            phases(time),
+            time.multiblind.map(|v| v.solved),
+            time.multiblind.map(|v| v.attempted),
+            time.moves,
+            time.memo.map(|v| v as f64),
+            time.variation.as_str(),
+            time.ao50.map(|v| v.0 as f64),
+            time.ao100.map(|v| v.0 as f64),
+            time.ao1000.map(|v| v.0 as f64),
+            time.mo3.map(|v| v.0 as f64),
+        ],
+    )?;
+    insert_penalty(conn, conn.last_insert_rowid(), time)
+}
+
+/// A solve's phase times as stored.
+fn phases(time: &Time) -> String {
+    let phases: Vec<String> = time.phases.iter().map(f32::to_string).collect();
+    phases.join(" ")
+}
+
+fn insert_penalty(conn: &Connection, id: i64, time: &Time) -> Result<(), Box<dyn Error>> {
+    if time.penalty != Penalty::None {
+        conn.execute(
+            "INSERT INTO penalties (solve_id, kind, inspection) VALUES (?1, ?2, ?3)",
+            params![id, time.penalty.as_str(), time.inspection_penalty],
+        )?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stats::Rolling;
+    use rand::{Rng, SeedableRng};
+    use rand_chacha::ChaCha8Rng;
+
+    fn in_memory() -> SqliteStorage {
+        let mut conn = Connection::open_in_memory().unwrap();
+        migrate(&mut conn).unwrap();
+        SqliteStorage {
+            conn,
+            session: 1,
+            notice: None,
+        }
+    }
+
+    /// Solves with penalties, a few one-handed and a few undated, averages worked out.
+    fn solves(n: usize) -> Vec<Time> {
+        let mut rng = ChaCha8Rng::seed_from_u64(2);
+        let mut rolling = Rolling::default();
+        (0..n)
+            .map(|i| {
+                let mut time = Time::from(rng.gen_range(8.0..20.0));
+                time.penalty = match rng.gen_range(0..15) {
+                    0 => Penalty::Dnf,
+                    1 => Penalty::PlusTwo,
+                    _ => Penalty::None,
+                };
+                if rng.gen_bool(0.1) {
+                    time.variation = Variation::OneHanded;
+                }
+                time.date = rng.gen_bool(0.9).then_some(1_700_000_000 + 40 * i as i64);
+                rolling.push(&mut time);
+                time
+            })
+            .collect()
+    }
+
+    fn close(a: Option<OrderedFloat<f32>>, b: Option<OrderedFloat<f32>>) -> bool {
+        match (a, b) {
+            (Some(a), Some(b)) => a == b || (a.0 - b.0).abs() < 1e-3,
+            (a, b) => a == b,
+        }
+    }
+
+    #[test]
+    fn summary_matches_going_over_the_solves() {
+        let times = solves(1200);
+        let mut storage = in_memory();
+        storage.save(&times).unwrap();
+        for variation in [Variation::Normal, Variation::OneHanded] {
+            let a = storage.summary(variation).unwrap();
+            let b = Summary::from_times(&times, variation);
+            assert_eq!(a.count, b.count);
+            let stats = |v: &Summary| {
+                [
+                    v.pbsingle, v.pbmo3, v.pbao5, v.pbao12, v.pbao50, v.pbao100, v.pbao1000,
+                    v.ao50, v.ao100, v.ao1000, v.mean,
+                ]
+            };
+            for (a, b) in stats(&a).into_iter().zip(stats(&b)) {
+                assert!(close(a, b), "{:?} against {:?} for {}", a, b, variation);
+            }
+            let (a, b) = (a.spread, b.spread);
+            assert_eq!((a.solves, a.dnfs, a.plus_twos), (b.solves, b.dnfs, b.plus_twos));
+        }
+        assert_eq!(storage.load().unwrap().len(), times.len());
+    }
+
+    #[test]
+    fn migrate_brings_an_empty_database_up_to_date() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        migrate(&mut conn).unwrap();
+        let version: i64 = conn
+            .query_row("PRAGMA user_version", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(version as usize, MIGRATIONS.len());
+        let columns: Vec<String> = conn
+            .prepare("SELECT name FROM pragma_table_info('solves')")
+            .unwrap()
+            .query_map([], |row| row.get(0))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        for column in ["date", "variation", "ao1000", "mo3"] {
+            assert!(columns.iter().any(|v| v == column), "no {} column", column);
+        }
+        // Nothing left to do the second time
+        migrate(&mut conn).unwrap();
+    }
+
+    #[test]
+    fn migrate_takes_an_unversioned_database_with_dates() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        create_tables(&conn).unwrap();
+        conn.execute_batch(
+            "ALTER TABLE solves ADD COLUMN date INTEGER;
+            INSERT INTO solves (session_id, time, date) VALUES (1, 9.5, 1700000000);",
+        )
+        .unwrap();
+        migrate(&mut conn).unwrap();
+        let mut storage = SqliteStorage {
+            conn,
+            session: 1,
+            notice: None,
+        };
+        let times = storage.load().unwrap();
+        assert_eq!((times[0].time, times[0].date), (9.5, Some(1700000000)));
+        assert_eq!(times[0].variation, Variation::Normal);
+    }
+
+    #[test]
+    fn migrate_refuses_a_newer_database() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        let newer = MIGRATIONS.len() as i64 + 1;
+        conn.pragma_update(None, "user_version", newer).unwrap();
+        assert!(migrate(&mut conn).is_err());
+    }
+
+    #[test]
+    fn salvage_reads_every_reachable_solve() {
+        let times = solves(40);
+        let mut storage = in_memory();
+        storage.save(&times).unwrap();
+        assert_eq!(check(&storage.conn), Ok(()));
+        let salvaged = salvage(&storage.conn);
+        let key = |v: &Time| (v.time.to_bits(), v.penalty, v.date, v.variation, v.ao5);
+        let found: Vec<_> = salvaged.iter().map(key).collect();
+        assert_eq!(found, times.iter().map(key).collect::<Vec<_>>());
+
+        // A database missing its tables salvages nothing, rather than failing
+        let empty = Connection::open_in_memory().unwrap();
+        assert!(salvage(&empty).is_empty());
+    }
+}
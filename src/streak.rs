@@ -0,0 +1,96 @@
+//! Runs kept going: days in a row with a solve, and solves in a row under the target. Also
+//! how many solves each day had, which the calendar shows.
+
+use super::app::{Time, Variation};
+use chrono::{Local, NaiveDate, TimeZone};
+use std::collections::BTreeMap;
+
+/// How long a run is now, and the longest it has been.
+#[derive(Default, Clone, Copy)]
+pub struct Streak {
+    pub current: usize,
+    pub best: usize,
+}
+
+impl Streak {
+    /// Carry the run on, or end it.
+    fn step(&mut self, kept: bool) {
+        self.current = match kept {
+            true => self.current + 1,
+            false => 0,
+        };
+        self.best = self.best.max(self.current);
+    }
+}
+
+#[derive(Default, Clone, Copy)]
+pub struct Streaks {
+    /// Days in a row with a solve of any kind, still going if the last was yesterday
+    pub days: Streak,
+    /// Solves of the variation in a row that beat the target, if there is one
+    pub target: Option<Streak>,
+    /// The day of the newest dated solve
+    last: Option<NaiveDate>,
+}
+
+impl Streaks {
+    /// Carry the streaks on to `time`, the newest solve.
+    pub fn push(&mut self, time: &Time, variation: Variation, target: Option<f32>) {
+        if let Some(day) = day(time.date).filter(|v| self.last != Some(*v)) {
+            self.days.current = match self.last {
+                Some(v) if v.succ_opt() == Some(day) => self.days.current + 1,
+                _ => 1,
+            };
+            self.days.best = self.days.best.max(self.days.current);
+            self.last = Some(day);
+        }
+        if let Some((streak, target)) = self.target.as_mut().zip(target) {
+            if time.variation == variation {
+                streak.step(time.value() < target);
+            }
+        }
+    }
+
+    /// The streaks as they stand today, with the day streak over once a whole day has
+    /// gone by without a solve.
+    pub fn today(mut self) -> Self {
+        let today = Local::now().date_naive();
+        if self.last.is_some_and(|v| v.succ_opt().is_some_and(|v| v < today)) {
+            self.days.current = 0;
+        }
+        self
+    }
+}
+
+/// The streaks over `times`, oldest first.
+pub fn of(times: &[Time], variation: Variation, target: Option<f32>) -> Streaks {
+    let mut streaks = Streaks {
+        target: target.map(|_| Streak::default()),
+        ..Streaks::default()
+    };
+    for time in times {
+        streaks.push(time, variation, target);
+    }
+    streaks
+}
+
+/// How many solves of any kind were done each day.
+pub fn per_day(times: &[Time]) -> BTreeMap<NaiveDate, usize> {
+    let mut days = BTreeMap::new();
+    for time in times {
+        count_day(&mut days, time);
+    }
+    days
+}
+
+/// Count `time` in on the day it was done.
+pub fn count_day(days: &mut BTreeMap<NaiveDate, usize>, time: &Time) {
+    if let Some(day) = day(time.date) {
+        *days.entry(day).or_default() += 1;
+    }
+}
+
+/// The local day a solve finished on.
+fn day(date: Option<i64>) -> Option<NaiveDate> {
+    Some(Local.timestamp_opt(date?, 0).single()?.date_naive())
+}
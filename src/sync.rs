@@ -0,0 +1,141 @@
+use super::app::Time;
+use super::storage::{self, Cipher};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use serde_json::Value;
+use std::{collections::HashSet, env, error::Error, io::Read};
+
+/// A deleted solve's finish timestamp and time, kept so a copy that still has it doesn't
+/// bring it back.
+pub type Tombstone = (i64, f32);
+
+/// A WebDAV file holding a session as the same JSON written locally, encrypted the same
+/// way too, along with the solves deleted from it.
+///
+/// Configured with `CUBE_TUI_WEBDAV_URL` (the URL of a folder, which keeps each session
+/// as `<session>.json`) and optionally `CUBE_TUI_WEBDAV_USER` and
+/// `CUBE_TUI_WEBDAV_PASSWORD` for basic auth.
+pub struct Remote {
+    url: String,
+    auth: Option<String>,
+}
+
+impl Remote {
+    /// The file for the session `id`.
+    pub fn from_env(id: &str) -> Result<Self, Box<dyn Error>> {
+        let dir = env::var("CUBE_TUI_WEBDAV_URL").map_err(|_| "CUBE_TUI_WEBDAV_URL is not set")?;
+        let url = format!("{}/{}.json", dir.trim_end_matches('/'), id);
+        let auth = env::var("CUBE_TUI_WEBDAV_USER").ok().map(|user| {
+            let password = env::var("CUBE_TUI_WEBDAV_PASSWORD").unwrap_or_default();
+            format!(
+                "Basic {}",
+                STANDARD.encode(format!("{}:{}", user, password))
+            )
+        });
+        Ok(Self { url, auth })
+    }
+
+    /// Fetch the remote session. A file that doesn't exist yet is empty.
+    pub fn pull(&self, cipher: Option<&Cipher>) -> Result<Pulled, Box<dyn Error>> {
+        let mut req = ureq::get(&self.url);
+        if let Some(auth) = &self.auth {
+            req = req.set("Authorization", auth);
+        }
+        match req.call() {
+            Ok(res) => {
+                // Without an ETag the upload can't be made conditional
+                let guard = res.header("ETag").map(|v| ("If-Match", v.to_string()));
+                let mut data = vec![];
+                res.into_reader().read_to_end(&mut data)?;
+                let text = storage::unseal(&data, cipher)?;
+                let doc: Value = serde_json::from_str(&text)?;
+                Ok(Pulled {
+                    times: storage::decode(&text)?,
+                    deleted: serde_json::from_value(doc["deleted"].clone()).unwrap_or_default(),
+                    guard,
+                })
+            }
+            Err(ureq::Error::Status(404, _)) => Ok(Pulled {
+                times: vec![],
+                deleted: vec![],
+                guard: Some(("If-None-Match", "*".to_string())),
+            }),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// Upload the session, unless the remote copy changed since `pulled` was fetched, in
+    /// which case nothing is written and this returns false.
+    pub fn push(
+        &self,
+        times: &[Time],
+        deleted: &[Tombstone],
+        pulled: &Pulled,
+        cipher: Option<&Cipher>,
+    ) -> Result<bool, Box<dyn Error>> {
+        let content_type = match cipher {
+            Some(_) => "application/octet-stream",
+            None => "application/json",
+        };
+        let mut req = ureq::put(&self.url).set("Content-Type", content_type);
+        if let Some(auth) = &self.auth {
+            req = req.set("Authorization", auth);
+        }
+        if let Some((header, value)) = &pulled.guard {
+            req = req.set(header, value);
+        }
+        let mut doc: Value = serde_json::from_str(&storage::encode(times)?)?;
+        doc["deleted"] = serde_json::to_value(deleted)?;
+        let data = storage::seal(serde_json::to_string_pretty(&doc)?, cipher)?;
+        match req.send_bytes(&data) {
+            Ok(_) => Ok(true),
+            Err(ureq::Error::Status(412, _)) => Ok(false),
+            Err(err) => Err(err.into()),
+        }
+    }
+}
+
+/// The remote session as fetched.
+pub struct Pulled {
+    pub times: Vec<Time>,
+    pub deleted: Vec<Tombstone>,
+    /// The precondition that makes the next upload fail if someone else's came first
+    guard: Option<(&'static str, String)>,
+}
+
+/// Remote solves that aren't already held locally.
+///
+/// Solves are matched by their finish timestamp and time, as merging matches them, so
+/// when both sides have the same solve the local copy wins. Undated remote solves can't be
+/// matched and are left out.
+pub fn missing(local: &[Time], remote: Vec<Time>) -> Vec<Time> {
+    let key = |v: &Time| Some((v.date?, v.time.to_bits()));
+    let seen: HashSet<(i64, u32)> = local.iter().filter_map(key).collect();
+    remote
+        .into_iter()
+        .filter(|v| key(v).is_some_and(|v| !seen.contains(&v)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn solve(time: f32, date: Option<i64>) -> Time {
+        let mut solve = Time::from(time);
+        solve.date = date;
+        solve
+    }
+
+    #[test]
+    fn missing_matches_date_and_time() {
+        let local = [solve(9.5, Some(100))];
+        let remote = vec![
+            solve(9.5, Some(100)),
+            solve(11.2, Some(100)),
+            solve(10.0, None),
+        ];
+        let missing = missing(&local, remote);
+        let found: Vec<(f32, Option<i64>)> = missing.iter().map(|v| (v.time, v.date)).collect();
+        assert_eq!(found, [(11.2, Some(100))]);
+    }
+}
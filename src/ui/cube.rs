@@ -0,0 +1,122 @@
+use crate::app::CubeView;
+use crate::scramble::Net;
+use tui::{
+    style::{Color, Style},
+    text::{Span, Spans},
+};
+
+/// Sticker colors in the order URFDLB, for WCA orientation: white top, green front.
+const COLORS: [Color; 6] = [
+    Color::White,
+    Color::Red,
+    Color::Green,
+    Color::Yellow,
+    Color::Indexed(208),
+    Color::Blue,
+];
+
+/// Pixels per sticker edge in the 3D view, so every cube comes out about the same size.
+fn scale(n: usize) -> usize {
+    (12 / n).max(2)
+}
+
+/// Pixels across and down the drawing of a cube with `n` layers.
+fn pixels(view: CubeView, n: usize) -> (usize, usize) {
+    match view {
+        CubeView::Net => (4 * n + 3, 3 * n + 2),
+        // The U and R faces recede at half depth, adding half a face each way
+        _ => (3 * n * scale(n) / 2, 3 * n * scale(n) / 2),
+    }
+}
+
+/// Columns and rows the drawing takes up; two pixels stack in each row.
+pub fn size(view: CubeView, n: usize) -> (usize, usize) {
+    let (width, height) = pixels(view, n);
+    (width, height.div_ceil(2))
+}
+
+/// The cube drawn in `view`, which mustn't be `Hidden`.
+pub fn lines(view: CubeView, net: &Net) -> Vec<Spans<'static>> {
+    let grid = match view {
+        CubeView::Net => unfolded(net),
+        _ => isometric(net),
+    };
+    half_blocks(&grid)
+}
+
+/// U over L F R B over D, a column between faces, one sticker per pixel.
+fn unfolded(net: &Net) -> Vec<Vec<Option<Color>>> {
+    let n = net.n;
+    let (width, height) = pixels(CubeView::Net, n);
+    let mut grid = vec![vec![None; width]; height];
+    // Where each face's top left sticker goes, in the order URFDLB
+    let origins = [
+        (n + 1, 0),
+        (2 * n + 2, n + 1),
+        (n + 1, n + 1),
+        (n + 1, 2 * n + 2),
+        (0, n + 1),
+        (3 * n + 3, n + 1),
+    ];
+    for (face, (x, y)) in origins.into_iter().enumerate() {
+        for (i, &color) in net.faces[face].iter().enumerate() {
+            grid[y + i / n][x + i % n] = Some(COLORS[color]);
+        }
+    }
+    grid
+}
+
+/// The U, F and R faces in a cabinet projection, with U and R receding up and to the
+/// right at half depth.
+///
+/// Each pixel is traced back to the sticker under its centre, measured in stickers from
+/// the top left corner of F.
+fn isometric(net: &Net) -> Vec<Vec<Option<Color>>> {
+    let n = net.n;
+    let (width, height) = pixels(CubeView::Isometric, n);
+    let (size, scale) = (n as f32, scale(n) as f32);
+    let sticker = |face: usize, row: f32, col: f32| {
+        let inside = (0.0..size).contains(&row) && (0.0..size).contains(&col);
+        inside.then(|| COLORS[net.faces[face][row as usize * n + col as usize]])
+    };
+    (0..height)
+        .map(|py| {
+            (0..width)
+                .map(|px| {
+                    let u = (px as f32 + 0.5) / scale;
+                    let v = (py as f32 + 0.5) / scale - size / 2.0;
+                    if v < 0.0 && u + v < size {
+                        // How far back from the front edge of U
+                        let depth = -2.0 * v;
+                        sticker(0, size - depth, u + v)
+                    } else if u < size {
+                        sticker(2, v, u)
+                    } else {
+                        let depth = 2.0 * (u - size);
+                        sticker(1, v + depth / 2.0, depth)
+                    }
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// Terminal cells are about twice as tall as wide, so each holds two pixels, one above
+/// the other, as a half block over a background. That keeps pixels square.
+fn half_blocks(grid: &[Vec<Option<Color>>]) -> Vec<Spans<'static>> {
+    let empty = vec![None; grid.first().map_or(0, |v| v.len())];
+    grid.chunks(2)
+        .map(|pair| {
+            let lower = pair.get(1).unwrap_or(&empty);
+            let cells = pair[0].iter().zip(lower).map(|cell| match cell {
+                (Some(top), Some(bottom)) => {
+                    Span::styled("▀", Style::default().fg(*top).bg(*bottom))
+                }
+                (Some(top), None) => Span::styled("▀", Style::default().fg(*top)),
+                (None, Some(bottom)) => Span::styled("▄", Style::default().fg(*bottom)),
+                (None, None) => Span::raw(" "),
+            });
+            Spans::from(cells.collect::<Vec<Span>>())
+        })
+        .collect()
+}
@@ -0,0 +1,43 @@
+/// Bucket sizes in seconds to pick from when none is given, narrowest first.
+const STEPS: [f32; 14] = [
+    0.1, 0.2, 0.25, 0.5, 1.0, 2.0, 5.0, 10.0, 15.0, 30.0, 60.0, 120.0, 300.0, 600.0,
+];
+
+/// `values` counted into buckets `size` seconds wide, or the narrowest that make no more
+/// than `most` buckets. Returns the size and each bucket's start with its count; buckets
+/// between the fastest and slowest are kept even when empty, so gaps show.
+pub fn buckets(values: &[f32], size: Option<f32>, most: usize) -> (f32, Vec<(f32, u64)>) {
+    let fastest = values.iter().copied().fold(f32::INFINITY, f32::min);
+    let slowest = values.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+    let span = |size: f32| ((slowest / size).floor() - (fastest / size).floor()) as usize + 1;
+    let size = size.unwrap_or_else(|| {
+        STEPS
+            .into_iter()
+            .find(|v| values.is_empty() || span(*v) <= most)
+            .unwrap_or(STEPS[STEPS.len() - 1])
+    });
+    if values.is_empty() {
+        return (size, vec![]);
+    }
+    let first = (fastest / size).floor();
+    let mut buckets: Vec<(f32, u64)> = (0..span(size))
+        .map(|i| ((first + i as f32) * size, 0))
+        .collect();
+    let last = buckets.len() - 1;
+    for v in values {
+        let i = ((v / size).floor() - first) as usize;
+        buckets[i.min(last)].1 += 1;
+    }
+    (size, buckets)
+}
+
+/// A bucket's start, to as many decimals as the size needs.
+pub fn label(start: f32, size: f32) -> String {
+    let decimals = (0..3)
+        .find(|v| {
+            let scaled = size * 10f32.powi(*v);
+            (scaled - scaled.round()).abs() < 1e-3
+        })
+        .unwrap_or(3) as usize;
+    format!("{:.*}", decimals, start)
+}
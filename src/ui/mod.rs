@@ -1,84 +1,382 @@
+mod cube;
+mod histogram;
+
 use super::app::*;
+use super::command;
+use super::compare::{Side, PERCENTILES};
+use super::config::Config;
+use super::hardware::stackmat::State;
+use super::records::STATS;
+use super::scramble::{self, Net};
+use super::session::Standing;
+use super::storage::{Cipher, Spread, Summary};
+use super::streak::Streak;
+use chrono::{Datelike, Local, TimeZone};
 use crossterm::event::{self, Event, KeyCode};
 use ordered_float::OrderedFloat;
 use std::{
-    env,
     error::Error,
-    path::Path,
     time::{Duration, Instant},
 };
 use tui::{
     backend::Backend,
     layout::{Alignment, Constraint, Direction, Layout, Rect},
-    style::{Color, Style},
-    widgets::{Block, Borders, Cell, Chart, Paragraph, Row, Table, Wrap},
+    style::{Color, Modifier, Style},
+    symbols::Marker,
+    text::{Span, Spans},
+    widgets::{
+        Axis, BarChart, Block, Borders, Cell, Chart, Clear, Dataset, GraphType, List, ListItem,
+        Paragraph, Row, Table, Wrap,
+    },
     Frame, Terminal,
 };
 
-const HELP_TEXT: &'static str = include_str!("../text/help.txt");
-const WELCOME_TEXT: &'static str = include_str!("../text/welcome.txt");
+const HELP_TEXT: &str = include_str!("../text/help.txt");
+const WELCOME_TEXT: &str = include_str!("../text/welcome.txt");
+/// The latest solves that goals, targets and penalty rates are measured over in the Stats view.
+const LATEST_SOLVES: usize = 100;
+/// How often to look for a scramble from the worker while one is on its way.
+const SCRAMBLE_POLL: Duration = Duration::from_millis(50);
+/// Other keys this soon after one of a slam that stopped a solve in place of space are
+/// taken to be from the same slam of the keyboard, and ignored.
+const SLAM_GAP: Duration = Duration::from_millis(100);
+/// Keys of the slam repeat while held, after the terminal's delay of up to half a second.
+const SLAM_REPEAT: Duration = Duration::from_millis(600);
+/// How often to check whether space has been let go while it's held.
+const RELEASE_POLL: Duration = Duration::from_millis(10);
+/// How often to read a stackmat or smart cube, which change state with no key pressed.
+const DEVICE_POLL: Duration = Duration::from_millis(50);
+/// Redraw rate while the timer runs.
+const FRAME: Duration = Duration::from_millis(16);
+/// Redraw rate while idle.
+const IDLE_FRAME: Duration = Duration::from_millis(1000);
+/// Rate of `App::on_tick`.
+const TICK: Duration = Duration::from_millis(1000);
 
-pub fn run<B: Backend>(terminal: &mut Terminal<B>) -> Result<(), Box<dyn Error>> {
-    // Load times from file
-    let pathstr = env::var("HOME")? + "/.local/share/cube-tui/times";
-    let path = Path::new(&pathstr);
-    let mut app = App::new(Duration::from_millis(1000), path)?;
-    app.load_times()?;
+pub fn run<B: Backend>(
+    terminal: &mut Terminal<B>,
+    config: Config,
+    cipher: Option<Cipher>,
+    piped: Option<String>,
+) -> Result<(), Box<dyn Error>> {
+    let mut app = App::new(&config, cipher)?;
+    app.status = app
+        .start_daily()
+        .unwrap_or_else(|err| Some(format!("error: daily session: {}", err)));
+    if let Some(text) = piped {
+        app.status = command::append_list(&mut app, &text)?;
+    }
 
     // Main loop and tick logic
     let mut last_tick = Instant::now();
+    let mut next_frame = Instant::now();
+    let mut dirty = true;
     loop {
-        terminal.draw(|f| match app.active_screen {
-            Screen::Default => render_default(f, &mut app),
-            Screen::Help => render_help(f),
-        })?;
+        app.receive_scrambles();
+        app.timer.check_release();
+        if let Err(err) = app.poll_stackmat() {
+            app.status = Some(error(err));
+        }
+        if let Err(err) = app.poll_smartcube() {
+            app.status = Some(error(err));
+        }
+        app.poll_fmc();
+        app.poll_drill();
+        if let Err(err) = app.poll_metronome() {
+            app.status = Some(error(err));
+        }
+        if let Err(err) = app.poll_inspection() {
+            app.status = Some(error(err));
+        }
+        // Workers and devices change things without a key press
+        if app.generating() || app.stackmat.is_some() || app.smartcube.is_some() {
+            dirty = true;
+        }
+        let now = Instant::now();
+        if dirty || now >= next_frame {
+            terminal.draw(|f| match app.active_screen {
+                Screen::Default => render_default(f, &mut app),
+                Screen::Help => render_help(f),
+                Screen::Restore => {
+                    render_default(f, &mut app);
+                    render_restore(f, &mut app);
+                }
+                Screen::Sessions => {
+                    render_default(f, &mut app);
+                    render_sessions(f, &mut app);
+                }
+                Screen::Notes => {
+                    render_default(f, &mut app);
+                    render_notes(f, &app);
+                }
+            })?;
+            dirty = false;
+            let frame = match app.timer.busy() || app.drilling() {
+                true => FRAME,
+                false => IDLE_FRAME,
+            };
+            next_frame = now + frame;
+        }
 
         // Non-blocking key detection
-        let timeout = app
-            .tick_rate
-            .checked_sub(last_tick.elapsed())
-            .unwrap_or_else(|| Duration::from_secs(0));
+        let mut timeout = next_frame
+            .min(last_tick + TICK)
+            .saturating_duration_since(Instant::now());
+        // Check back soon for a scramble being generated
+        if app.generating() {
+            timeout = timeout.min(SCRAMBLE_POLL);
+        }
+        // Letting go of space is only noticed by it no longer repeating, so look often
+        if app.timer.holding() {
+            timeout = timeout.min(RELEASE_POLL);
+        }
+        if app.stackmat.is_some() || app.smartcube.is_some() {
+            timeout = timeout.min(DEVICE_POLL);
+        }
         if event::poll(timeout)? {
-            if let Event::Key(key) = event::read()? {
-                match key.code {
+            let event = event::read()?;
+            dirty = true;
+            // Taken before any handling, so a stop isn't late by however long that takes
+            let received = Instant::now();
+            if let Event::Paste(text) = &event {
+                if let Some(input) = app.input.as_mut() {
+                    let separator = match input.mode {
+                        InputMode::Command | InputMode::Tags => ", ",
+                        InputMode::Scramble
+                        | InputMode::Time
+                        | InputMode::Multiblind
+                        | InputMode::Solution
+                        | InputMode::Session
+                        | InputMode::Rename
+                        | InputMode::TagFilter => " ",
+                    };
+                    input.text += &text.replace(['\r', '\n'], separator);
+                } else if let Screen::Notes = app.active_screen {
+                    app.notes += &text.replace("\r\n", "\n").replace('\r', "\n");
+                }
+            }
+            if let Event::Key(key) = event {
+                if app.input.is_some() {
+                    handle_input(&mut app, key.code);
+                    continue;
+                }
+                if let Screen::Restore = app.active_screen {
+                    match key.code {
+                        KeyCode::Char('j') | KeyCode::Down => app.mv_backup(Dir::Down),
+                        KeyCode::Char('k') | KeyCode::Up => app.mv_backup(Dir::Up),
+                        KeyCode::Enter => {
+                            app.status = app
+                                .restore_backup()
+                                .unwrap_or_else(|err| Some(format!("error: {}", err)))
+                        }
+                        KeyCode::Esc | KeyCode::Char('q') => app.esc(),
+                        _ => (),
+                    }
+                    continue;
+                }
+                if let Screen::Notes = app.active_screen {
+                    match key.code {
+                        KeyCode::Char(c) => app.notes.push(c),
+                        KeyCode::Enter => app.notes.push('\n'),
+                        KeyCode::Tab => app.notes.push_str("    "),
+                        KeyCode::Backspace => _ = app.notes.pop(),
+                        KeyCode::Esc => app.status = Some(app.save_notes().unwrap_or_else(error)),
+                        _ => (),
+                    }
+                    continue;
+                }
+                if let Screen::Sessions = app.active_screen {
+                    if app.deleting {
+                        app.status = match key.code {
+                            KeyCode::Char('y') => Some(
+                                app.delete_session()
+                                    .unwrap_or_else(|err| format!("error: {}", err)),
+                            ),
+                            _ => {
+                                app.deleting = false;
+                                Some("nothing deleted".to_string())
+                            }
+                        };
+                        continue;
+                    }
+                    match key.code {
+                        KeyCode::Char('j') | KeyCode::Down => app.mv_session(Dir::Down),
+                        KeyCode::Char('k') | KeyCode::Up => app.mv_session(Dir::Up),
+                        KeyCode::Enter => {
+                            app.status = app
+                                .switch_selected()
+                                .unwrap_or_else(|err| Some(format!("error: {}", err)))
+                        }
+                        KeyCode::Char('n') => {
+                            app.active_screen = Screen::Default;
+                            app.open_input(InputMode::Session);
+                        }
+                        KeyCode::Char('r') => app.open_input(InputMode::Rename),
+                        KeyCode::Char('d') => app.deleting = true,
+                        KeyCode::Char('a') => {
+                            app.status = Some(app.archive_session().unwrap_or_else(error))
+                        }
+                        KeyCode::Char('A') => {
+                            app.status = Some(app.toggle_show_archived().unwrap_or_else(error))
+                        }
+                        KeyCode::Char('t') => app.open_tags(),
+                        KeyCode::Char('/') => app.open_input(InputMode::TagFilter),
+                        KeyCode::Esc | KeyCode::Char('q') => app.esc(),
+                        _ => (),
+                    }
+                    continue;
+                }
+                let stopping = app.stop_on_any_key && app.timer.on;
+                let code = match key.code {
+                    KeyCode::Char('s') if app.memorizing() => key.code,
+                    v => match press(&mut app.slammed, v, received, stopping) {
+                        Some(v) => v,
+                        None => continue,
+                    },
+                };
+                app.status = None;
+                match code {
                     KeyCode::Char('q') => {
                         app.write_times()?;
+                        app.auto_export()?;
                         return Ok(());
                     }
-                    KeyCode::Char(' ') => match app.timer.space_press() {
-                        Some(mut t) => {
-                            t.gen_stats(&app.times.times);
-                            app.times.insert(t);
-                            app.tick_rate = Duration::from_millis(1000);
-                            app.new_scramble();
+                    KeyCode::Char(' ') if app.drilling() => {
+                        if let Some(drill) = app.drill.as_mut() {
+                            drill.press(received);
                         }
-                        None => app.tick_rate = Duration::from_millis(100),
-                    },
+                    }
+                    KeyCode::Char(' ') if !app.sessions.current.meta.event.timed() => {
+                        app.fmc_press()
+                    }
+                    KeyCode::Char(' ') if app.typing => app.open_input(InputMode::Time),
+                    KeyCode::Char(' ') => {
+                        if let Some(t) = app.timer.space_press(received) {
+                            if let Err(err) = app.add_time(t) {
+                                app.status = Some(error(err));
+                            }
+                        }
+                    }
                     KeyCode::Esc => app.esc(),
                     KeyCode::Enter => app.route.enter(),
-                    KeyCode::Char('h') => app.mv(Dir::Left),
-                    KeyCode::Char('j') => app.mv(Dir::Down),
-                    KeyCode::Char('k') => app.mv(Dir::Up),
-                    KeyCode::Char('l') => app.mv(Dir::Right),
-                    KeyCode::Char('d') => app.del(),
+                    KeyCode::Char('h') => app.mv(Dir::Left)?,
+                    KeyCode::Char('j') => app.mv(Dir::Down)?,
+                    KeyCode::Char('k') => app.mv(Dir::Up)?,
+                    KeyCode::Char('l') => app.mv(Dir::Right)?,
+                    KeyCode::Char('d') if app.route.active_block == ActiveBlock::Times => {
+                        app.del()?
+                    }
+                    KeyCode::Char('d') => {
+                        app.status = Some(app.toggle_penalty(Penalty::Dnf).unwrap_or_else(error))
+                    }
+                    KeyCode::Char('2') => {
+                        app.status =
+                            Some(app.toggle_penalty(Penalty::PlusTwo).unwrap_or_else(error))
+                    }
+                    KeyCode::Char('t') => {
+                        app.status = Some(app.toggle_typing().unwrap_or_else(error))
+                    }
+                    KeyCode::Char('H') => app.status = Some(app.toggle_hide_timer()),
+                    KeyCode::Char('s') if app.memorizing() => {
+                        app.status = app.end_memo(received).err().map(error)
+                    }
+                    KeyCode::Char('s') => app.status = app.open_sessions().err().map(error),
+                    KeyCode::Char('n') => app.show_next = !app.show_next,
+                    KeyCode::Char('N') => app.status = app.open_notes().err().map(error),
+                    KeyCode::Char('v') => app.cube_view = app.cube_view.next(),
+                    KeyCode::Char('r') => app.status = app.skip_scramble().err().map(error),
+                    KeyCode::Char('b') => app.status = app.previous_scramble().err().map(error),
+                    KeyCode::Char('c') => {
+                        app.status = Some(app.copy_scramble(false).unwrap_or_else(error))
+                    }
+                    KeyCode::Char('C') => {
+                        app.status = Some(app.copy_scramble(true).unwrap_or_else(error))
+                    }
+                    KeyCode::Char('y') => {
+                        app.status = Some(app.copy_best("ao5").unwrap_or_else(error))
+                    }
                     KeyCode::Char('?') => app.help(),
+                    KeyCode::Char(':') => app.open_input(InputMode::Command),
+                    KeyCode::Char('m') => app.open_input(InputMode::Scramble),
                     _ => (),
                 }
             }
         }
-        if last_tick.elapsed() >= app.tick_rate {
+        if last_tick.elapsed() >= TICK {
             app.on_tick();
             last_tick = Instant::now();
         }
     }
 }
 
+fn error(err: Box<dyn Error>) -> String {
+    format!("error: {}", err)
+}
+
+/// What pressing `code` does: nothing if it's more of the slam that last stopped a solve,
+/// space included, and space if it's the first key of a slam `stopping` a solve.
+fn press(
+    slammed: &mut Option<(Instant, Vec<KeyCode>)>,
+    code: KeyCode,
+    at: Instant,
+    stopping: bool,
+) -> Option<KeyCode> {
+    if slam_key(slammed, code, at) {
+        return None;
+    }
+    match code {
+        KeyCode::Char(' ') => Some(code),
+        v if stopping => {
+            *slammed = Some((at, vec![v]));
+            Some(KeyCode::Char(' '))
+        }
+        v => Some(v),
+    }
+}
+
+/// Whether `code` is more of the slam that last stopped a solve: one of its keys still
+/// held, or another that came down with them.
+fn slam_key(slammed: &mut Option<(Instant, Vec<KeyCode>)>, code: KeyCode, at: Instant) -> bool {
+    let Some((last, keys)) = slammed.as_mut() else {
+        return false;
+    };
+    let gap = at.saturating_duration_since(*last);
+    let held = keys.contains(&code);
+    if gap >= SLAM_GAP && !(held && gap < SLAM_REPEAT) {
+        return false;
+    }
+    *last = at;
+    if !held {
+        keys.push(code);
+    }
+    true
+}
+
+fn handle_input(app: &mut App, code: KeyCode) {
+    let input = match app.input.as_mut() {
+        Some(v) => v,
+        None => return,
+    };
+    match code {
+        KeyCode::Char(c) => input.text.push(c),
+        KeyCode::Backspace => _ = input.text.pop(),
+        KeyCode::Esc => app.cancel_input(),
+        KeyCode::Enter => app.submit_input(),
+        _ => (),
+    }
+}
+
 fn render_default<B: Backend>(f: &mut Frame<B>, app: &mut App) {
     // define chunks
+    let outer_chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(0), Constraint::Length(1)].as_ref())
+        .split(f.size());
+
     let chunks = Layout::default()
         .direction(Direction::Horizontal)
         .constraints([Constraint::Length(40), Constraint::Percentage(100)].as_ref())
-        .split(f.size());
+        .split(outer_chunks[0]);
 
     let left_chunks = Layout::default()
         .direction(Direction::Vertical)
@@ -92,12 +390,40 @@ fn render_default<B: Backend>(f: &mut Frame<B>, app: &mut App) {
         )
         .split(chunks[0]);
 
+    let cube = match app.cube_view {
+        _ if app.scramble.is_empty() => None,
+        CubeView::Hidden => None,
+        _ => scramble::net(app.sessions.current.meta.event, &app.scramble),
+    };
+    let (net_width, net_height) = cube
+        .as_ref()
+        .map_or((0, 0), |v| cube::size(app.cube_view, v.n));
+
+    // Grow the scramble block to fit long scrambles, keeping a blank line above
+    let width = match cube {
+        // Leave a gap of two columns before the net
+        Some(_) => chunks[1].width.saturating_sub(net_width as u16 + 4) as usize,
+        None => chunks[1].width.saturating_sub(2) as usize,
+    };
+    let scramble = scramble_lines(pending(&app.scramble), width);
+    let next = match app.show_next {
+        true => scramble_lines(pending(&app.next_scramble), width),
+        false => vec![],
+    };
+    // The preview gets a blank line and a label of its own
+    let next_height = match next.is_empty() {
+        true => 0,
+        false => next.len() + 2,
+    };
     let right_chunks = Layout::default()
         .direction(Direction::Vertical)
         .constraints(
             [
-                Constraint::Length(5),
-                Constraint::Length(3),
+                Constraint::Length(
+                    (scramble.len().max(2) + next_height).max(net_height) as u16 + 3,
+                ),
+                // Room for the larger averages' bests under them
+                Constraint::Length(4),
                 Constraint::Percentage(100),
             ]
             .as_ref(),
@@ -110,9 +436,42 @@ fn render_default<B: Backend>(f: &mut Frame<B>, app: &mut App) {
     render_times(f, app, left_chunks[2]);
 
     // render right side
-    render_scramble(f, app, right_chunks[0]);
+    render_scramble(f, app, scramble, next, cube, right_chunks[0]);
     render_bests(f, app, right_chunks[1]);
     render_main(f, app, right_chunks[2]);
+
+    render_status_line(f, app, outer_chunks[1]);
+}
+
+fn prompt(app: &App, mode: InputMode) -> String {
+    match mode {
+        InputMode::Command => ":".to_string(),
+        InputMode::Scramble => "scramble: ".to_string(),
+        InputMode::Time => "time: ".to_string(),
+        InputMode::Multiblind => format!("cubes solved (of {}): ", app.attempted()),
+        InputMode::Solution => "solution: ".to_string(),
+        InputMode::Rename => "rename to: ".to_string(),
+        InputMode::Tags => "tags, split by commas: ".to_string(),
+        InputMode::TagFilter => "only sessions tagged (empty for all): ".to_string(),
+        InputMode::Session => "new session (name, then puzzle if not this one's): ".to_string(),
+    }
+}
+
+fn render_status_line<B: Backend>(f: &mut Frame<B>, app: &mut App, layout_chunk: Rect) {
+    let (text, style) = match (&app.input, &app.status) {
+        (Some(input), _) => (
+            format!("{}{}", prompt(app, input.mode), input.text),
+            Style::default().fg(Color::White),
+        ),
+        (None, Some(status)) => (status.clone(), Style::default().fg(Color::LightBlue)),
+        (None, None) => (String::new(), Style::default()),
+    };
+    let paragraph = Paragraph::new(text).style(style);
+    f.render_widget(paragraph, layout_chunk);
+    if let Some(input) = &app.input {
+        let offset = prompt(app, input.mode).len() + input.text.len();
+        f.set_cursor(layout_chunk.x + offset as u16, layout_chunk.y);
+    }
 }
 
 fn render_help<B: Backend>(f: &mut Frame<B>) {
@@ -127,6 +486,128 @@ fn render_help<B: Backend>(f: &mut Frame<B>) {
     f.render_widget(paragraph, chunks[0]);
 }
 
+fn render_restore<B: Backend>(f: &mut Frame<B>, app: &mut App) {
+    let area = centered_rect(50, 50, f.size());
+    let items: Vec<ListItem> = app
+        .backup_list
+        .iter()
+        .map(|v| {
+            ListItem::new(
+                v.file_name()
+                    .and_then(|v| v.to_str())
+                    .unwrap_or("")
+                    .to_string(),
+            )
+        })
+        .collect();
+    let list = List::new(items)
+        .block(
+            Block::default()
+                .title("Restore backup (enter to restore, esc to cancel)")
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(Color::LightGreen)),
+        )
+        .highlight_style(
+            Style::default()
+                .fg(Color::LightGreen)
+                .add_modifier(Modifier::BOLD),
+        );
+    f.render_widget(Clear, area);
+    f.render_stateful_widget(list, area, &mut app.backup_state);
+}
+
+/// Every session with its puzzle, the current one marked.
+fn render_sessions<B: Backend>(f: &mut Frame<B>, app: &mut App) {
+    let area = centered_rect(70, 50, f.size());
+    let current = &app.sessions.current.id;
+    let items: Vec<ListItem> = app
+        .session_list
+        .iter()
+        .map(|(id, meta)| {
+            let mark = if id == current { "*" } else { " " };
+            // The first line of its notes, to tell sessions of a puzzle apart
+            let notes = meta.notes.lines().next().unwrap_or("");
+            let puzzle = puzzle_name(meta);
+            let mut line = format!("{} {:<24}{:<12}", mark, id, puzzle);
+            if meta.archived {
+                line += "archived  ";
+            }
+            if !meta.tags.is_empty() {
+                line += &format!("[{}]  ", meta.tags.join(", "));
+            }
+            ListItem::new(line + notes)
+        })
+        .collect();
+    let (title, color) = match (app.deleting, &app.session_tag) {
+        (true, _) => (
+            "Delete it with every solve? (y to delete, any other key keeps it)".to_string(),
+            Color::LightRed,
+        ),
+        (false, tag) => {
+            let tagged = tag.as_ref().map(|v| format!(" tagged {}", v));
+            let keys = "enter switch, n new, r rename, d delete, a archive, t tag, / filter";
+            let title = format!("Sessions{} ({})", tagged.unwrap_or_default(), keys);
+            (title, Color::LightGreen)
+        }
+    };
+    let list = List::new(items)
+        .block(
+            Block::default()
+                .title(title)
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(color)),
+        )
+        .highlight_style(
+            Style::default()
+                .fg(Color::LightGreen)
+                .add_modifier(Modifier::BOLD),
+        );
+    f.render_widget(Clear, area);
+    f.render_stateful_widget(list, area, &mut app.session_state);
+}
+
+/// The session's notes being written, with a cursor at the end.
+fn render_notes<B: Backend>(f: &mut Frame<B>, app: &App) {
+    let area = centered_rect(60, 60, f.size());
+    let title = format!("Notes: {} (esc to save)", app.sessions.current.meta.name);
+    let paragraph = Paragraph::new(format!("{}_", app.notes))
+        .block(
+            Block::default()
+                .title(title)
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(Color::LightGreen)),
+        )
+        .wrap(Wrap { trim: false });
+    f.render_widget(Clear, area);
+    f.render_widget(paragraph, area);
+}
+
+/// A rectangle of the given percentage size in the middle of `r`.
+fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
+    let vertical = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints(
+            [
+                Constraint::Percentage((100 - percent_y) / 2),
+                Constraint::Percentage(percent_y),
+                Constraint::Percentage((100 - percent_y) / 2),
+            ]
+            .as_ref(),
+        )
+        .split(r);
+    Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints(
+            [
+                Constraint::Percentage((100 - percent_x) / 2),
+                Constraint::Percentage(percent_x),
+                Constraint::Percentage((100 - percent_x) / 2),
+            ]
+            .as_ref(),
+        )
+        .split(vertical[1])[1]
+}
+
 fn render_help_and_tools<B: Backend>(f: &mut Frame<B>, app: &mut App, layout_chunk: Rect) {
     let chunks = Layout::default()
         .direction(Direction::Horizontal)
@@ -155,16 +636,124 @@ fn render_help_and_tools<B: Backend>(f: &mut Frame<B>, app: &mut App, layout_chu
 }
 
 fn render_timer<B: Backend>(f: &mut Frame<B>, app: &mut App, layout_chunk: Rect) {
-    let text = format!("\n\n{}", app.timer.text());
+    let stackmat = app.stackmat.as_ref().map(|v| v.state());
+    let mut text = match app.sessions.current.meta.event.timed() {
+        true if app.timer.on && app.hide_timer => "\n\nsolving…".to_string(),
+        true => format!("\n\n{}", app.timer.text(&app.time_format)),
+        false => match &app.fmc {
+            Some(attempt) => {
+                // Whole seconds left, counting down to 0:00
+                let left = FMC_LIMIT.saturating_sub(attempt.start.elapsed());
+                let secs = left.as_secs_f32().ceil() as u64;
+                format!(
+                    "\n\n{}:{:02}\n\nspace to write the solution",
+                    secs / 60,
+                    secs % 60
+                )
+            }
+            None => "\n\nspace starts the hour".to_string(),
+        },
+    };
+    // A drill takes the panel over from the timer until its time is up
+    if let Some(drill) = app.drill.as_ref().filter(|v| !v.finished) {
+        text = match drill.reps.last() {
+            _ if !drill.running() => "\n\nspace starts the drill".to_string(),
+            Some(last) => format!(
+                "\n\n{:.0}\n\nrep {}, last {}",
+                drill.left().as_secs_f32().ceil(),
+                drill.reps.len() + 1,
+                app.time_format.time(*last)
+            ),
+            None => format!("\n\n{:.0}\n\nrep 1", drill.left().as_secs_f32().ceil()),
+        };
+    }
+    if let Some(i) = app.timer.phase() {
+        text += &format!("\n{}", app.phase_name(i));
+    } else if app.timer.on && app.sessions.current.meta.event == scramble::Event::Cube3Blind {
+        let part = match app.timer.memo {
+            None => "memo",
+            Some(_) => "execution",
+        };
+        text += &format!("\n{}", part);
+    }
+    // What the next solve can make of the averages it would finish
+    let solving = app.timer.on || app.timer.inspecting.is_some();
+    if !solving && !app.drilling() && app.fmc.is_none() {
+        // How the solve just done compares with the session's, unless it's a DNF
+        let session = &mut app.sessions.current;
+        let last = session.times.times.last().map(Time::value);
+        let event = session.meta.event;
+        let standing = match last.filter(|v| v.is_finite() && app.timer.lasttime.is_some()) {
+            // Multi-blind ranks by cubes solved first, which a value can't show
+            Some(value) if event != scramble::Event::Multiblind => {
+                session.standing(value).ok().flatten()
+            }
+            _ => None,
+        };
+        match standing {
+            Some(Standing::Top(v)) => text += &format!("\ntop {}% of the session", v),
+            Some(Standing::Bottom(v)) => text += &format!("\nbottom {}% of the session", v),
+            None => {}
+        }
+        let session = &app.sessions.current;
+        for n in [5, 12] {
+            if let Some((best, worst)) = session.times.possible(n, session.meta.variation) {
+                text += &format!(
+                    "\nnext ao{}: BPA {}, WPA {}",
+                    n,
+                    app.time_format.average(Some(best), "-"),
+                    app.time_format.average(Some(worst), "-")
+                );
+            }
+        }
+    }
+    if let Some(attempt) = &app.attempt {
+        text += &format!(
+            "\n\n{} attempt: solve {} of {}",
+            attempt.format,
+            attempt.solves.len() + 1,
+            attempt.format.solves()
+        );
+    }
+    if let Some(state) = stackmat {
+        let state = state.map_or("no signal", |v| v.as_str());
+        text += &format!("\n\nstackmat: {}", state);
+    }
+    if let Some(cube) = &app.smartcube {
+        text += &format!("\n\nsmart cube: {}", cube.status());
+    }
+    let hands_on = matches!(
+        stackmat.flatten(),
+        Some(State::HandsOn | State::LeftHand | State::RightHand)
+    );
+    let target = app.sessions.current.meta.target;
     let borderstyle = app.get_border_style_from_id(ActiveBlock::Timer);
     let mut paragraphstyle = Style::default();
     paragraphstyle = match app.timer.on {
-        false => match app.timer.lasttime {
-            Some(_) => paragraphstyle.fg(Color::LightBlue),
-            None => paragraphstyle.fg(Color::White),
+        false if app.timer.ready() == Some(false) || hands_on => paragraphstyle.fg(Color::Red),
+        false if app.timer.ready() == Some(true) || stackmat.flatten() == Some(State::Ready) => {
+            paragraphstyle.fg(Color::Green)
+        }
+        false if app.timer.inspecting.is_some() => paragraphstyle.fg(Color::Yellow),
+        false => match (app.timer.lasttime, target) {
+            (Some(_), Some(target)) => match app.sessions.current.times.times.last() {
+                Some(t) => paragraphstyle.fg(target_color(t.value(), target)),
+                None => paragraphstyle.fg(Color::LightBlue),
+            },
+            (Some(_), None) => paragraphstyle.fg(Color::LightBlue),
+            (None, _) => paragraphstyle.fg(Color::White),
+        },
+        // Turning red as the target passes would give the hidden time away
+        true => match app.timer.starttime.zip(target) {
+            Some((start, target)) if !app.hide_timer => {
+                paragraphstyle.fg(target_color(start.elapsed().as_secs_f32(), target))
+            }
+            _ => paragraphstyle.fg(Color::LightGreen),
         },
-        true => paragraphstyle.fg(Color::LightGreen),
     };
+    if app.beat_flash() {
+        paragraphstyle = paragraphstyle.add_modifier(Modifier::REVERSED);
+    }
     let paragraph = Paragraph::new(text)
         .block(
             Block::default()
@@ -181,98 +770,260 @@ fn render_timer<B: Backend>(f: &mut Frame<B>, app: &mut App, layout_chunk: Rect)
 fn render_times<B: Backend>(f: &mut Frame<B>, app: &mut App, layout_chunk: Rect) {
     let selected_style = app.get_highlight_style_from_id(ActiveBlock::Times);
     let normal_style = Style::default().fg(Color::White);
-    let header_cells = ["i", "time", "ao5", "ao12"].iter().map(|h| Cell::from(*h));
+    let header_cells = ["i", "time", "mo3", "ao5", "ao12"]
+        .iter()
+        .map(|h| Cell::from(*h));
     let header = Row::new(header_cells)
         .style(normal_style)
         .height(1)
         .bottom_margin(1);
-    let rows = app.times.times.iter().rev().enumerate().map(|(i, t)| {
-        let ao5 = match t.ao5 {
-            Some(v) => format!("{:.2}", v),
-            None => "-".to_string(),
-        };
-        let ao12 = match t.ao12 {
-            Some(v) => format!("{:.2}", v),
-            None => "-".to_string(),
+    let times = &app.sessions.current.times.times;
+    let target = app.sessions.current.meta.target;
+    let rows = times.iter().rev().enumerate().map(|(i, t)| {
+        let mo3 = app.time_format.average(t.mo3, "-");
+        let ao5 = app.time_format.average(t.ao5, "-");
+        let ao12 = app.time_format.average(t.ao12, "-");
+        let single = match t.variation {
+            Variation::Normal => app.time_format.single(t),
+            v => format!("{} {}", app.time_format.single(t), v),
         };
         let cells = vec![
             i.to_string(),
-            format!("{:.2}", t.time),
+            single,
+            mo3,
             format!("{}", ao5),
             format!("{}", ao12),
         ];
-        Row::new(cells)
+        match target {
+            Some(v) => Row::new(cells).style(Style::default().fg(target_color(t.value(), v))),
+            None => Row::new(cells),
+        }
     });
+    // How often the loaded solves beat the target
+    let hits = match target {
+        Some(v) if !times.is_empty() => {
+            let hit = times.iter().filter(|t| t.value() < v).count();
+            format!(", sub-{} {}%", v, hit * 100 / times.len())
+        }
+        _ => String::new(),
+    };
+    let meta = &app.sessions.current.meta;
+    let event = event_name(app);
     let border_style = app.get_border_style_from_id(ActiveBlock::Times);
     let table = Table::new(rows)
         .header(header)
         .block(
             Block::default()
                 .borders(Borders::ALL)
-                .title("Table")
+                .title(format!("Times: {} ({}){}", meta.name, event, hits))
                 .border_style(border_style),
         )
         .highlight_style(selected_style)
         .widths(&[
-            Constraint::Ratio(1, 10),
-            Constraint::Ratio(3, 10),
-            Constraint::Ratio(3, 10),
-            Constraint::Ratio(3, 10),
+            Constraint::Ratio(1, 9),
+            Constraint::Ratio(2, 9),
+            Constraint::Ratio(2, 9),
+            Constraint::Ratio(2, 9),
+            Constraint::Ratio(2, 9),
         ]);
     f.render_stateful_widget(table, layout_chunk, &mut app.times_state);
 }
 
-fn render_scramble<B: Backend>(f: &mut Frame<B>, app: &mut App, layout_chunk: Rect) {
+/// Green for a solve that beat the session's target, red for one that didn't.
+fn target_color(value: f32, target: f32) -> Color {
+    match value < target {
+        true => Color::Green,
+        false => Color::Red,
+    }
+}
+
+/// A scramble, or a note that it's still on its way from the worker.
+fn pending(scramble: &str) -> &str {
+    match scramble.is_empty() {
+        true => "generating…",
+        false => scramble,
+    }
+}
+
+/// Break a scramble into lines of at most `width` columns, keeping the lines it already
+/// has, like a Megaminx scramble's.
+///
+/// A line too long for the panel is laid out in columns, each move padded to the widest
+/// one, so its moves stay easy to follow down the panel.
+fn scramble_lines(scramble: &str, width: usize) -> Vec<String> {
+    let mut out = vec![];
+    for line in scramble.lines() {
+        if line.len() <= width {
+            out.push(line.to_string());
+            continue;
+        }
+        let moves: Vec<&str> = line.split_whitespace().collect();
+        let cell = moves.iter().map(|v| v.len()).max().unwrap_or(0) + 1;
+        let per_line = (width / cell).max(1);
+        for row in moves.chunks(per_line) {
+            out.push(row.iter().map(|v| format!("{:<cell$}", v)).collect());
+        }
+    }
+    // Pad every line to the same width so centering keeps the columns lined up
+    let longest = out.iter().map(|v| v.len()).max().unwrap_or(0);
+    if out.len() > 1 {
+        for line in &mut out {
+            *line = format!("{:<longest$}", line);
+        }
+    }
+    out
+}
+
+fn render_scramble<B: Backend>(
+    f: &mut Frame<B>,
+    app: &mut App,
+    lines: Vec<String>,
+    next: Vec<String>,
+    cube: Option<Net>,
+    layout_chunk: Rect,
+) {
     let border_style = app.get_border_style_from_id(ActiveBlock::Scramble);
-    let paragraph = Paragraph::new(format!("\n{}", app.scramble.clone()))
-        .block(
-            Block::default()
-                .title("Scramble")
-                .borders(Borders::ALL)
-                .border_style(border_style),
-        )
+    let mut modes = vec![];
+    if let Some(subset) = app.scramble_settings().subset {
+        modes.push(subset.to_string());
+    }
+    if let Some(seed) = &app.seed {
+        modes.push(format!("seed \"{}\"", seed));
+    }
+    let title = match modes.is_empty() {
+        true => "Scramble".to_string(),
+        false => format!("Scramble ({})", modes.join(", ")),
+    };
+    let mut text: Vec<Spans> = vec![Spans::from("")];
+    text.extend(lines.into_iter().map(Spans::from));
+    if !next.is_empty() {
+        let dim = Style::default().fg(Color::DarkGray);
+        text.push(Spans::from(""));
+        text.push(Spans::from(Span::styled("next", dim)));
+        text.extend(next.into_iter().map(|v| Spans::from(Span::styled(v, dim))));
+    }
+    let block = Block::default()
+        .title(title)
+        .borders(Borders::ALL)
+        .border_style(border_style);
+    let inner = block.inner(layout_chunk);
+    f.render_widget(block, layout_chunk);
+
+    let mut text_chunk = inner;
+    if let Some(cube) = cube {
+        let (width, height) = cube::size(app.cube_view, cube.n);
+        let net_chunk = Rect {
+            x: inner.right().saturating_sub(width as u16 + 1),
+            y: inner.y + 1,
+            width: (width as u16).min(inner.width),
+            height: (height as u16).min(inner.height.saturating_sub(1)),
+        };
+        text_chunk.width = net_chunk.x.saturating_sub(inner.x + 1);
+        f.render_widget(Paragraph::new(cube::lines(app.cube_view, &cube)), net_chunk);
+    }
+    let paragraph = Paragraph::new(text)
         .style(Style::default().fg(Color::White))
-        .alignment(Alignment::Center)
-        .wrap(Wrap { trim: true });
-    f.render_widget(paragraph, layout_chunk);
+        .alignment(Alignment::Center);
+    // Already wrapped; the wrapper would trim the padding that keeps the columns aligned
+    f.render_widget(paragraph, text_chunk);
 }
 
 fn render_bests<B: Backend>(f: &mut Frame<B>, app: &mut App, layout_chunk: Rect) {
+    // The config's own stats come after the built-in ones
+    let custom: Vec<(String, String)> = {
+        let format = app.time_format;
+        let names = app.custom_stats.iter().map(|v| v.name.clone());
+        match app.sessions.current.custom(&app.custom_stats) {
+            Ok(values) => names
+                .zip(values)
+                .map(|(name, v)| (name, format.average(v, "n/a")))
+                .collect(),
+            Err(_) => names.map(|name| (name, "error".to_string())).collect(),
+        }
+    };
+    let blocks = 8 + custom.len() as u32;
     let chunks = Layout::default()
         .direction(Direction::Horizontal)
-        .constraints(
-            [
-                Constraint::Ratio(1, 6),
-                Constraint::Ratio(1, 6),
-                Constraint::Ratio(1, 6),
-                Constraint::Ratio(1, 6),
-                Constraint::Ratio(1, 6),
-                Constraint::Ratio(1, 6),
-            ]
-            .as_ref(),
-        )
+        .constraints(vec![Constraint::Ratio(1, blocks); blocks as usize])
         .split(layout_chunk);
 
-    render_stat(f, app, "PB Single", app.times.pbsingle, chunks[0]);
-    render_stat(f, app, "PB ao5", app.times.pbao5, chunks[1]);
-    render_stat(f, app, "PB ao12", app.times.pbao12, chunks[2]);
-    render_stat(f, app, "ao100", app.times.ao100, chunks[3]);
-    render_stat(f, app, "ao1k", app.times.ao1k, chunks[4]);
-    render_stat(f, app, "avg", app.times.rollingavg, chunks[5]);
+    let times = &app.sessions.current.times;
+    let format = &app.time_format;
+    let mut stats: Vec<(&str, String)> = [
+        ("PB Single", times.pbsingle),
+        ("PB mo3", times.pbmo3),
+        ("PB ao5", times.pbao5),
+        ("PB ao12", times.pbao12),
+    ]
+    .into_iter()
+    .map(|(title, v)| (title, format.average(v, "n/a")))
+    .collect();
+    // The larger averages show where they stand now above their best
+    for (title, latest, best) in [
+        ("ao50", times.ao50, times.pbao50),
+        ("ao100", times.ao100, times.pbao100),
+        ("ao1000", times.ao1000, times.pbao1000),
+    ] {
+        let text = match best {
+            Some(_) => format!(
+                "{}\nbest {}",
+                format.average(latest, "n/a"),
+                format.average(best, "n/a")
+            ),
+            None => "n/a".to_string(),
+        };
+        stats.push((title, text));
+    }
+    stats.push(("avg", format.average(times.rollingavg, "n/a")));
+    // The bests are of the session's variation alone
+    let variation = app.sessions.current.meta.variation;
+    // Multi-blind ranks by cubes solved before time, so its best isn't the fastest
+    match app.sessions.current.meta.event {
+        scramble::Event::Multiblind => {
+            stats[0].1 = app
+                .best_multiblind()
+                .map_or("n/a".to_string(), |v| app.time_format.single(v));
+        }
+        // A fewest-moves single is a whole number of moves
+        scramble::Event::Fmc => {
+            if let Some(v) = times.pbsingle.filter(|v| v.is_finite()) {
+                stats[0].1 = v.to_string();
+            }
+        }
+        // What the next solve needs for the average it ends to be a PB, while one can be
+        _ => {
+            for (i, n, pb) in [(2, 5, times.pbao5), (3, 12, times.pbao12)] {
+                let Some(needed) = pb.and_then(|pb| times.needed(n, variation, pb)) else {
+                    continue;
+                };
+                let needed = match needed.is_infinite() {
+                    true => "any time".to_string(),
+                    false => format!("sub-{}", format.time(needed.0)),
+                };
+                stats[i].1 = format!("{}\n{}", stats[i].1, needed);
+            }
+        }
+    }
+    for (name, text) in &custom {
+        stats.push((name, text.clone()));
+    }
+    for ((title, text), chunk) in stats.into_iter().zip(chunks) {
+        let title = match variation {
+            Variation::Normal => title.to_string(),
+            v => format!("{} {}", title, v),
+        };
+        render_stat(f, app, &title, text, chunk);
+    }
 }
 
 fn render_stat<B: Backend>(
     f: &mut Frame<B>,
     app: &mut App,
     title: &str,
-    stat: Option<OrderedFloat<f32>>,
+    text: String,
     layout_chunk: Rect,
 ) {
     let border_style = app.get_border_style_from_id(ActiveBlock::Stats);
-    let text = match stat {
-        Some(v) => format!("{:.2}", v),
-        None => "n/a".to_string(),
-    };
     let paragraph = Paragraph::new(text)
         .block(
             Block::default()
@@ -287,10 +1038,590 @@ fn render_stat<B: Backend>(
 }
 
 fn render_main<B: Backend>(f: &mut Frame<B>, app: &mut App, layout_chunk: Rect) {
+    if app.route.active_block == ActiveBlock::Times && app.selected_solve().is_some() {
+        return render_solve(f, app, layout_chunk);
+    }
     match app.tool {
         Tool::Welcome => render_welcome(f, app, layout_chunk),
-        Tool::Chart => render_chart(f, app, layout_chunk),
+        Tool::Drill => render_drill(f, app, layout_chunk),
+        Tool::Stats => render_stats(f, app, layout_chunk),
+        Tool::Histogram { bucket } => render_histogram(f, app, layout_chunk, bucket),
+        Tool::Pbs => render_pbs(f, app, layout_chunk),
+        Tool::Chart { count } => render_chart(f, app, layout_chunk, count),
+        Tool::Compare => render_compare(f, app, layout_chunk),
+        Tool::Calendar => render_calendar(f, app, layout_chunk),
+        Tool::Records => render_records(f, app, layout_chunk),
+        Tool::Kinch => render_kinch(f, app, layout_chunk),
+    }
+}
+
+/// The alg being drilled and each rep's time, newest first.
+fn render_drill<B: Backend>(f: &mut Frame<B>, app: &mut App, layout_chunk: Rect) {
+    let border_style = app.get_border_style_from_id(ActiveBlock::Main);
+    let Some(drill) = &app.drill else {
+        return;
+    };
+    let format = &app.time_format;
+    let mut lines = vec![format!("alg: {}", drill.alg)];
+    if let (Some(mean), Some(best)) = (drill.mean(), drill.best()) {
+        lines.push(format!(
+            "reps: {}, mean: {}, best: {}",
+            drill.reps.len(),
+            format.time(mean),
+            format.time(best)
+        ));
     }
+    lines.push(String::new());
+    for (i, rep) in drill.reps.iter().enumerate().rev() {
+        lines.push(format!("{:<4}{}", i + 1, format.time(*rep)));
+    }
+    let paragraph = Paragraph::new(lines.join("\n"))
+        .block(
+            Block::default()
+                .title(format!("Drill: {}", drill.title()))
+                .borders(Borders::ALL)
+                .border_style(border_style),
+        )
+        .style(Style::default().fg(Color::White));
+    f.render_widget(paragraph, layout_chunk);
+}
+
+/// Numbers over every solve of the session's variation, loaded or not.
+fn render_stats<B: Backend>(f: &mut Frame<B>, app: &mut App, layout_chunk: Rect) {
+    let border_style = app.get_border_style_from_id(ActiveBlock::Main);
+    let streaks = app.sessions.current.streaks();
+    let target = app.sessions.current.meta.target;
+    let times = &app.sessions.current.times;
+    let spread = &times.spread;
+    let format = &app.time_format;
+    // Undated solves can't be placed in a sitting, so they're left out of the pace
+    let sittings = match spread.sittings {
+        0 => "n/a".to_string(),
+        n => format!("{}, {} at the timer", n, practice(spread.sat)),
+    };
+    let pace = match spread.pace() {
+        Some(v) => format!("{:.1} solves an hour", v),
+        None => "n/a".to_string(),
+    };
+    let mut lines = [
+        ("solves", solves(spread)),
+        ("best", format.average(times.pbsingle, "n/a")),
+        ("worst", format.average(spread.worst, "n/a")),
+        ("mean", format.average(times.rollingavg, "n/a")),
+        ("median", format.average(spread.median, "n/a")),
+        ("std dev", format.average(spread.deviation, "n/a")),
+        ("practice", practice(spread.practice)),
+        ("sittings", sittings),
+        ("pace", pace),
+    ]
+    .map(|(name, value)| format!("{:<15}{}", name, value))
+    .to_vec();
+    // The averages now, with how much their singles vary, to tell a steady one from a lucky one
+    let variation = app.sessions.current.meta.variation;
+    let latest = times.times.iter().rev().find(|v| v.variation == variation);
+    for (name, n, average) in [
+        ("ao5", 5, latest.and_then(|v| v.ao5)),
+        ("ao12", 12, latest.and_then(|v| v.ao12)),
+        ("ao100", 100, latest.and_then(|v| v.ao100)),
+    ] {
+        let mut line = format!("{:<15}{}", name, format.average(average, "n/a"));
+        if let Some(v) = average.and(times.consistency(n, variation)) {
+            line.push_str(&format!(", σ {}", format.average(Some(v), "n/a")));
+        }
+        lines.push(line);
+    }
+    if let Some(v) = times.speed(variation) {
+        lines.push(format!(
+            "{:<15}{}, weighted to recent solves",
+            "speed",
+            format.average(Some(v), "")
+        ));
+    }
+    // Penalties creeping up lately is a sign of getting careless
+    let (dnfs, plus_twos, count) = times.penalties(variation, LATEST_SOLVES);
+    for (name, all, latest) in [
+        ("DNF rate", spread.dnfs, dnfs),
+        ("+2 rate", spread.plus_twos, plus_twos),
+    ] {
+        lines.push(format!(
+            "{:<15}{}, {} of the last {}",
+            name,
+            rate(all, spread.solves),
+            rate(latest, count),
+            count
+        ));
+    }
+    // Targets and goals go by how the latest solves are going
+    let share = |time: f32| {
+        let (hits, count) = times.beating(time, variation, LATEST_SOLVES);
+        (hits * 100 / count.max(1), count)
+    };
+    if let Some(target) = target {
+        let (percent, count) = share(target);
+        lines.push(format!(
+            "{:<15}{}% of the last {}",
+            format!("sub-{}", target),
+            percent,
+            count
+        ));
+    }
+    for goal in &app.sessions.current.meta.goals {
+        let (percent, count) = share(goal.time);
+        let met = match percent as f32 >= goal.percent {
+            true => ", met",
+            false => "",
+        };
+        lines.push(format!(
+            "{:<15}{}% of the last {}, aiming for {}%{}",
+            format!("sub-{} goal", goal.time),
+            percent,
+            count,
+            goal.percent,
+            met
+        ));
+    }
+    let streak = |name: &str, v: Streak| format!("{:<15}{} (best {})", name, v.current, v.best);
+    match streaks {
+        Ok(streaks) => {
+            lines.push(streak("day streak", streaks.days));
+            if let Some((v, target)) = streaks.target.zip(target) {
+                lines.push(streak(&format!("sub-{} streak", target), v));
+            }
+        }
+        Err(err) => lines.push(format!("streaks: {}", err)),
+    }
+    let paragraph = Paragraph::new(lines.join("\n"))
+        .block(
+            Block::default()
+                .title(format!("Stats ({})", event_name(app)))
+                .borders(Borders::ALL)
+                .border_style(border_style),
+        )
+        .style(Style::default().fg(Color::White));
+    f.render_widget(paragraph, layout_chunk);
+}
+
+/// Shades from a day without solves up to the busiest days showing.
+const HEAT: [Color; 5] = [
+    Color::DarkGray,
+    Color::Indexed(22),
+    Color::Indexed(28),
+    Color::Indexed(34),
+    Color::Indexed(40),
+];
+
+/// Every other weekday is named down the side, as there's only a row each.
+const WEEKDAYS: [&str; 7] = ["Mon", "", "Wed", "", "Fri", "", ""];
+
+/// Solves each day of the latest weeks that fit, a column a week and a row a weekday,
+/// shaded against the busiest of them.
+fn render_calendar<B: Backend>(f: &mut Frame<B>, app: &mut App, layout_chunk: Rect) {
+    let border_style = app.get_border_style_from_id(ActiveBlock::Main);
+    // Each day is a square and a space, after the weekday names
+    let weeks = (layout_chunk.width.saturating_sub(2 + 4) / 2).max(1) as i64;
+    let today = Local::now().date_naive();
+    let monday = today - chrono::Duration::days(today.weekday().num_days_from_monday() as i64);
+    let first = monday - chrono::Duration::weeks(weeks - 1);
+    let text: Vec<Spans> = match app.sessions.current.days() {
+        Ok(days) => {
+            let shown = days.range(first..=today);
+            let (solves, active) = shown.clone().fold((0, 0), |(n, d), v| (n + v.1, d + 1));
+            let busiest = shown.map(|v| *v.1).max().unwrap_or(1);
+            let heat = |count: usize| HEAT[(count * 4).div_ceil(busiest).min(4)];
+
+            // A month is named over the first week starting in it
+            let mut months = " ".repeat(4);
+            for week in 1..weeks {
+                let start = first + chrono::Duration::weeks(week);
+                let at = 4 + 2 * week as usize;
+                if start.day() <= 7 && at >= months.len() {
+                    months.push_str(&" ".repeat(at - months.len()));
+                    months.push_str(&start.format("%b").to_string());
+                }
+            }
+            let mut lines = vec![Spans::from(months)];
+            for (weekday, name) in WEEKDAYS.into_iter().enumerate() {
+                let mut spans = vec![Span::raw(format!("{:<4}", name))];
+                for week in 0..weeks {
+                    let date = first + chrono::Duration::days(week * 7 + weekday as i64);
+                    if date > today {
+                        break;
+                    }
+                    let count = days.get(&date).copied().unwrap_or_default();
+                    spans.push(Span::styled("■ ", Style::default().fg(heat(count))));
+                }
+                lines.push(Spans::from(spans));
+            }
+            let mut legend = vec![Span::raw("    less ")];
+            legend.extend(HEAT.map(|v| Span::styled("■ ", Style::default().fg(v))));
+            legend.push(Span::raw("more"));
+            lines.push(Spans::default());
+            lines.push(Spans::from(legend));
+            lines.push(Spans::from(format!(
+                "    {} solves on {} days over the last {} weeks",
+                solves, active, weeks
+            )));
+            lines
+        }
+        Err(err) => vec![Spans::from(format!("couldn't read the solves: {}", err))],
+    };
+    let paragraph = Paragraph::new(text)
+        .block(
+            Block::default()
+                .title("Calendar")
+                .borders(Borders::ALL)
+                .border_style(border_style),
+        )
+        .style(Style::default().fg(Color::White));
+    f.render_widget(paragraph, layout_chunk);
+}
+
+/// Two sessions' counts, means, bests and spread in columns.
+fn render_compare<B: Backend>(f: &mut Frame<B>, app: &mut App, layout_chunk: Rect) {
+    let border_style = app.get_border_style_from_id(ActiveBlock::Main);
+    let Some(comparison) = &app.comparison else {
+        return;
+    };
+    let format = &app.time_format;
+    let row = |name: &str, cells: [String; 2]| format!("{:<15}{:<24}{}", name, cells[0], cells[1]);
+    let each = |f: &dyn Fn(&Side) -> String| comparison.sides.each_ref().map(f);
+    let stat = |f: fn(&Summary) -> Option<OrderedFloat<f32>>| {
+        each(&|v: &Side| format.average(f(&v.summary), "n/a"))
+    };
+    let mut lines = vec![
+        row("", each(&|v| format!("{} ({})", v.id, v.meta.event))),
+        row("solves", each(&|v| solves(&v.summary.spread))),
+        row("mean", stat(|v| v.mean)),
+        row("best", stat(|v| v.pbsingle)),
+        row("worst", stat(|v| v.spread.worst)),
+        row("std dev", stat(|v| v.spread.deviation)),
+        row("PB mo3", stat(|v| v.pbmo3)),
+        row("PB ao5", stat(|v| v.pbao5)),
+        row("PB ao12", stat(|v| v.pbao12)),
+        row("PB ao100", stat(|v| v.pbao100)),
+        row("practice", each(&|v| practice(v.summary.spread.practice))),
+    ];
+    // Where the solves fall, fastest first, so how each is spread shows beside the other
+    for percent in PERCENTILES {
+        let name = format!("{}% under", percent);
+        let cells = each(&|v| format.average(v.percentile(percent), "n/a"));
+        lines.push(row(&name, cells));
+    }
+    let title = match comparison.variation {
+        Variation::Normal => "Compare".to_string(),
+        v => format!("Compare ({})", v),
+    };
+    let paragraph = Paragraph::new(lines.join("\n"))
+        .block(
+            Block::default()
+                .title(title)
+                .borders(Borders::ALL)
+                .border_style(border_style),
+        )
+        .style(Style::default().fg(Color::White));
+    f.render_widget(paragraph, layout_chunk);
+}
+
+/// The session's bests beside the fastest of every session of the event, and where each
+/// was done.
+fn render_records<B: Backend>(f: &mut Frame<B>, app: &mut App, layout_chunk: Rect) {
+    let border_style = app.get_border_style_from_id(ActiveBlock::Main);
+    let Some(records) = &app.records else {
+        return;
+    };
+    let format = &app.time_format;
+    let session = &app.sessions.current;
+    let current = &session.id;
+    // The session's bests as they stand, unless it's on another variation since
+    let summary = match session.meta.variation == records.variation {
+        true => session.summary(),
+        false => records.current,
+    };
+    let row = |name: &str, mine: String, best: String| format!("{:<10}{:<18}{}", name, mine, best);
+    let mut lines = vec![row("", "this session".to_string(), "all time".to_string())];
+    for ((name, stat), best) in STATS.iter().zip(records.best(&summary)) {
+        let mine = format.average(stat(&summary), "n/a");
+        let best = match best {
+            Some(v) => {
+                let value = format.average(Some(v.value), "");
+                match &v.session == current {
+                    true => format!("{} here", value),
+                    false => format!("{} in {}", value, v.session),
+                }
+            }
+            None => "n/a".to_string(),
+        };
+        lines.push(row(name, mine, best));
+    }
+    lines.push(String::new());
+    lines.push(match records.sessions {
+        1 => format!("this is the only {} session", records.event),
+        n => format!("over {} {} sessions", n, records.event),
+    });
+    let title = match records.variation {
+        Variation::Normal => format!("Records: {}", records.event),
+        v => format!("Records: {} ({})", records.event, v),
+    };
+    let paragraph = Paragraph::new(lines.join("\n"))
+        .block(
+            Block::default()
+                .title(title)
+                .borders(Borders::ALL)
+                .border_style(border_style),
+        )
+        .style(Style::default().fg(Color::White));
+    f.render_widget(paragraph, layout_chunk);
+}
+
+/// Each event's PB scored against its world record, with the mean of every score.
+fn render_kinch<B: Backend>(f: &mut Frame<B>, app: &mut App, layout_chunk: Rect) {
+    let border_style = app.get_border_style_from_id(ActiveBlock::Main);
+    let Some(kinch) = &app.kinch else {
+        return;
+    };
+    let mut lines = vec![];
+    for part in &kinch.parts {
+        let pb = match &part.pb {
+            Some((kind, pb)) => format!("{} {}", kind, pb),
+            None => "none yet".to_string(),
+        };
+        lines.push(format!("{:<10}{:>7.2}   {}", part.name, part.score, pb));
+    }
+    lines.push(String::new());
+    lines.push(format!("{:<10}{:>7.2}", "overall", kinch.score));
+    let paragraph = Paragraph::new(lines.join("\n"))
+        .block(
+            Block::default()
+                .title("Kinch score")
+                .borders(Borders::ALL)
+                .border_style(border_style),
+        )
+        .style(Style::default().fg(Color::White));
+    f.render_widget(paragraph, layout_chunk);
+}
+
+/// How many of the loaded solves took each time, DNFs aside.
+fn render_histogram<B: Backend>(
+    f: &mut Frame<B>,
+    app: &mut App,
+    layout_chunk: Rect,
+    bucket: Option<f32>,
+) {
+    let border_style = app.get_border_style_from_id(ActiveBlock::Main);
+    let session = &app.sessions.current;
+    let values: Vec<f32> = session
+        .times
+        .times
+        .iter()
+        .filter(|v| v.variation == session.meta.variation)
+        .map(Time::value)
+        .filter(|v| v.is_finite())
+        .collect();
+    // Bars narrower than five columns can't fit their labels
+    let inner = layout_chunk.width.saturating_sub(2);
+    let (size, buckets) = histogram::buckets(&values, bucket, (inner / 6).max(1) as usize);
+    let labels: Vec<String> = buckets
+        .iter()
+        .map(|(start, _)| histogram::label(*start, size))
+        .collect();
+    let data: Vec<(&str, u64)> = labels
+        .iter()
+        .map(String::as_str)
+        .zip(buckets.iter().map(|v| v.1))
+        .collect();
+    let width = (inner / data.len().max(1) as u16).saturating_sub(1).max(5);
+    let chart = BarChart::default()
+        .block(
+            Block::default()
+                .title(format!(
+                    "Histogram ({}): {}s buckets",
+                    event_name(app),
+                    histogram::label(size, size)
+                ))
+                .borders(Borders::ALL)
+                .border_style(border_style),
+        )
+        .data(&data)
+        .bar_width(width)
+        .bar_gap(1)
+        .bar_style(Style::default().fg(Color::LightBlue))
+        .value_style(Style::default().fg(Color::Black).bg(Color::LightBlue))
+        .label_style(Style::default().fg(Color::White));
+    f.render_widget(chart, layout_chunk);
+}
+
+/// Each best the session's variation has set, newest first, with how much it took off.
+fn render_pbs<B: Backend>(f: &mut Frame<B>, app: &mut App, layout_chunk: Rect) {
+    let border_style = app.get_border_style_from_id(ActiveBlock::Main);
+    let title = format!("Personal bests ({})", event_name(app));
+    let format = app.time_format;
+    let variation = app.sessions.current.meta.variation;
+    let text = match app.sessions.current.pbs() {
+        Ok(pbs) => {
+            let mut lines = vec![format!(
+                "{:<18}{:<8}{:>10}{:>10}{:>8}",
+                "date", "stat", "time", "by", "solve"
+            )];
+            for pb in pbs.iter().rev().filter(|v| v.variation == variation) {
+                let date = pb
+                    .date
+                    .and_then(|v| Local.timestamp_opt(v, 0).single())
+                    .map_or("-".to_string(), |v| v.format("%Y-%m-%d %H:%M").to_string());
+                let by = pb.previous.map_or(String::new(), |v| {
+                    format!("-{}", format.time((v - pb.value).0))
+                });
+                lines.push(format!(
+                    "{:<18}{:<8}{:>10}{:>10}{:>8}",
+                    date,
+                    pb.stat.as_str(),
+                    format.time(pb.value.0),
+                    by,
+                    pb.solve + 1
+                ));
+            }
+            lines.join("\n")
+        }
+        Err(err) => format!("couldn't read the solves: {}", err),
+    };
+    let paragraph = Paragraph::new(text)
+        .block(
+            Block::default()
+                .title(title)
+                .borders(Borders::ALL)
+                .border_style(border_style),
+        )
+        .style(Style::default().fg(Color::White));
+    f.render_widget(paragraph, layout_chunk);
+}
+
+/// The session's event, with its variation when that isn't the usual one.
+fn event_name(app: &App) -> String {
+    puzzle_name(&app.sessions.current.meta)
+}
+
+/// How many solves there are, with how many of them were DNFs.
+fn solves(spread: &Spread) -> String {
+    match spread.dnfs {
+        0 => spread.solves.to_string(),
+        n => format!("{} ({} DNF)", spread.solves, n),
+    }
+}
+
+/// `n` as a share of `of`, to a tenth of a percent.
+fn rate(n: usize, of: usize) -> String {
+    format!("{:.1}%", n as f32 * 100.0 / of.max(1) as f32)
+}
+
+/// Time spent solving, to the second.
+fn practice(secs: f32) -> String {
+    let secs = secs.round() as u64;
+    match secs / 3600 {
+        0 => format!("{}m {:02}s", secs / 60, secs % 60),
+        hours => format!("{}h {:02}m {:02}s", hours, secs / 60 % 60, secs % 60),
+    }
+}
+
+/// The solve selected in the Times block, with its phases against the session's means.
+fn render_solve<B: Backend>(f: &mut Frame<B>, app: &mut App, layout_chunk: Rect) {
+    let border_style = app.get_border_style_from_id(ActiveBlock::Main);
+    let Some(solve) = app.selected_solve() else {
+        return;
+    };
+    let format = &app.time_format;
+    let mut lines = vec![format!("time: {}", format.single(solve))];
+    if solve.variation != Variation::Normal {
+        lines.push(format!("variation: {}", solve.variation));
+    }
+    if solve.penalty != Penalty::None {
+        let source = match solve.inspection_penalty {
+            true => "for going over inspection",
+            false => "given by hand",
+        };
+        lines.push(format!("penalty: {}, {}", solve.penalty.as_str(), source));
+    }
+    if let Some(result) = solve.multiblind {
+        lines.push(format!(
+            "cubes: {}/{}, points: {}, WCA result: {:010}",
+            result.solved,
+            result.attempted,
+            result.points(),
+            result.code(solve.value())
+        ));
+    }
+    if let Some(date) = solve.date.and_then(|v| Local.timestamp_opt(v, 0).single()) {
+        lines.push(format!("date: {}", date.format("%Y-%m-%d %H:%M")));
+    }
+    if !solve.scramble.is_empty() {
+        lines.push(format!("scramble: {}", solve.scramble));
+    }
+    if !solve.solution.is_empty() {
+        let length = match solve.moves {
+            Some(v) => format!("{} moves", v),
+            None => format!("{} turns", solve.solution.split_whitespace().count()),
+        };
+        lines.push(format!("solution ({}): {}", length, solve.solution));
+    }
+    if let Some(memo) = solve.memo {
+        // Means over the loaded solves with memo split off
+        let split: Vec<(f32, f32)> = app
+            .sessions
+            .current
+            .times
+            .times
+            .iter()
+            .filter_map(|v| v.memo.map(|memo| (memo, v.time - memo)))
+            .collect();
+        let n = split.len() as f32;
+        let means = [
+            split.iter().map(|v| v.0).sum::<f32>() / n,
+            split.iter().map(|v| v.1).sum::<f32>() / n,
+        ];
+        lines.push(String::new());
+        lines.push(format!("{:<16}{:>10}{:>10}", "", "time", "mean"));
+        for ((name, time), mean) in [("memo", memo), ("execution", solve.time - memo)]
+            .into_iter()
+            .zip(means)
+        {
+            lines.push(format!(
+                "{:<16}{:>10}{:>10}",
+                name,
+                format.time(time),
+                format.time(mean)
+            ));
+        }
+    }
+    if !solve.phases.is_empty() {
+        // Means over the loaded solves split into as many phases
+        let split: Vec<&Time> = app
+            .sessions
+            .current
+            .times
+            .times
+            .iter()
+            .filter(|v| v.phases.len() == solve.phases.len())
+            .collect();
+        lines.push(String::new());
+        lines.push(format!("{:<16}{:>10}{:>10}", "phase", "time", "mean"));
+        for (i, phase) in solve.phases.iter().enumerate() {
+            let mean = split.iter().map(|v| v.phases[i]).sum::<f32>() / split.len() as f32;
+            lines.push(format!(
+                "{:<16}{:>10}{:>10}",
+                app.phase_name(i),
+                format.time(*phase),
+                format.time(mean)
+            ));
+        }
+    }
+    let paragraph = Paragraph::new(lines.join("\n"))
+        .block(
+            Block::default()
+                .title("Solve")
+                .borders(Borders::ALL)
+                .border_style(border_style),
+        )
+        .style(Style::default().fg(Color::White))
+        .wrap(Wrap { trim: false });
+    f.render_widget(paragraph, layout_chunk);
 }
 
 fn render_welcome<B: Backend>(f: &mut Frame<B>, app: &mut App, layout_chunk: Rect) {
@@ -306,6 +1637,97 @@ fn render_welcome<B: Backend>(f: &mut Frame<B>, app: &mut App, layout_chunk: Rec
     f.render_widget(paragraph, layout_chunk);
 }
 
-fn render_chart<B: Backend>(f: &mut Frame<B>, app: &mut App, layout_chunk: Rect) {
-    let border_style = app.get_border_style_from_id(ActiveBlock::Stats);
+/// The latest `count` solves of the session's variation, and their ao12, oldest on the
+/// left. DNFs leave gaps.
+fn render_chart<B: Backend>(f: &mut Frame<B>, app: &mut App, layout_chunk: Rect, count: usize) {
+    let border_style = app.get_border_style_from_id(ActiveBlock::Main);
+    let session = &app.sessions.current;
+    let solves: Vec<&Time> = session
+        .times
+        .times
+        .iter()
+        .filter(|v| v.variation == session.meta.variation)
+        .collect();
+    let solves = &solves[solves.len().saturating_sub(count)..];
+    let points = |f: fn(&Time) -> Option<f32>| -> Vec<(f64, f64)> {
+        solves
+            .iter()
+            .enumerate()
+            .filter_map(|(i, v)| Some((i as f64, f(v).filter(|v| v.is_finite())? as f64)))
+            .collect()
+    };
+    let singles = points(|v| Some(v.value()));
+    let averages = points(|v| v.ao12.map(|v| v.0));
+    let (low, high) = singles
+        .iter()
+        .chain(&averages)
+        .fold((f64::INFINITY, f64::NEG_INFINITY), |(low, high), v| {
+            (low.min(v.1), high.max(v.1))
+        });
+    let (low, high) = match low <= high {
+        true => (low.floor(), high.ceil().max(low.floor() + 1.0)),
+        false => (0.0, 1.0),
+    };
+    let format = &app.time_format;
+    let y_labels = [low, (low + high) / 2.0, high]
+        .map(|v| Span::raw(format.time(v as f32)))
+        .to_vec();
+    let x_labels = vec![Span::raw(format!("-{}", solves.len())), Span::raw("latest")];
+    let datasets = vec![
+        Dataset::default()
+            .name("single")
+            .marker(Marker::Braille)
+            .graph_type(GraphType::Line)
+            .style(Style::default().fg(Color::LightBlue))
+            .data(&singles),
+        Dataset::default()
+            .name("ao12")
+            .marker(Marker::Braille)
+            .graph_type(GraphType::Line)
+            .style(Style::default().fg(Color::Yellow))
+            .data(&averages),
+    ];
+    let chart = Chart::new(datasets)
+        .block(
+            Block::default()
+                .title(format!("Chart ({})", event_name(app)))
+                .borders(Borders::ALL)
+                .border_style(border_style),
+        )
+        .x_axis(
+            Axis::default()
+                .bounds([0.0, solves.len().saturating_sub(1).max(1) as f64])
+                .labels(x_labels)
+                .style(Style::default().fg(Color::White)),
+        )
+        .y_axis(
+            Axis::default()
+                .bounds([low, high])
+                .labels(y_labels)
+                .style(Style::default().fg(Color::White)),
+        )
+        .hidden_legend_constraints((Constraint::Ratio(1, 2), Constraint::Ratio(1, 2)));
+    f.render_widget(chart, layout_chunk);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_space_inside_a_slam_is_swallowed() {
+        let start = Instant::now();
+        let mut slammed = None;
+        let mut key = |code, ms, stopping| {
+            let at = start + Duration::from_millis(ms);
+            press(&mut slammed, KeyCode::Char(code), at, stopping)
+        };
+        let space = Some(KeyCode::Char(' '));
+        assert_eq!(key('f', 0, true), space);
+        assert_eq!(key(' ', 40, false), None);
+        // Held down, and repeating
+        assert_eq!(key(' ', 400, false), None);
+        // Pressed again once the slam is over
+        assert_eq!(key(' ', 1200, false), space);
+    }
 }